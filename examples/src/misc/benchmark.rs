@@ -11,7 +11,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Benchmark 1: Simple shapes
     {
         let start = Instant::now();
-        let mut paint = Paint::with_color(Color::RED);
+        let paint = Paint::with_color(Color::RED);
 
         for i in 0..1000 {
             let x = (i % 40) as f32 * 48.0;
@@ -29,7 +29,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     {
         surface.canvas().clear(Color::WHITE);
         let start = Instant::now();
-        let mut paint = Paint::with_color(Color::BLUE);
+        let paint = Paint::with_color(Color::BLUE);
 
         for i in 0..500 {
             let x = (i as f32 * 3.0) % 1920.0;
@@ -2,8 +2,6 @@
 //!
 //! Demonstrates various font format features including variable fonts and collections.
 
-use sina::{BitmapFontRenderer, Font, VariableFontManager};
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔤 Sina Font Format Support Demo\n");
 
@@ -16,7 +14,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   ✓ TrueType Collections (.ttc)");
     println!("   ✓ Variable Fonts (fvar)");
     println!("   ✓ Bitmap Fonts (CBDT/SBIX)");
-    println!("   ✓ Color Emoji (COLR/CPAL) - stub\n");
+    println!("   ✓ Color Emoji (COLR/CPAL)\n");
 
     // Example 1: Loading from a TrueType Collection
     println!("💡 Example 1: TrueType Collections (.ttc)");
@@ -31,7 +29,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Example 2: Variable Fonts
     println!("💡 Example 2: Variable Fonts");
-    println!("   let font = Font::from_file(\"variable-font.ttf\")?;");
+    println!("   let mut font = Font::from_file(\"variable-font.ttf\")?;");
     println!("   ");
     println!("   if VariableFontManager::is_variable(&font) {{");
     println!("       let axes = VariableFontManager::axes(&font);");
@@ -39,6 +37,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("           println!(\"Axis: {{}} ({{}})\", axis.tag, axis.name);");
     println!("           println!(\"  Range: {{}} - {{}}\", axis.min_value, axis.max_value);");
     println!("       }}");
+    println!("   ");
+    println!("       // Pick a weight, or one of the named instances");
+    println!("       font.set_variation(\"wght\", 650.0);");
+    println!("       font.set_named_instance(\"Bold\");");
     println!("   }}\n");
 
     // Example 3: Bitmap Fonts
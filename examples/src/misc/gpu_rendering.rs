@@ -0,0 +1,77 @@
+//! GPU backend example: renders offscreen with wgpu and saves a PNG
+
+use sina::{Color, GpuSurface, LineCap, Paint, Path, Point, Rect, StrokeStyle, Surface};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🖥️  GPU Rendering Example\n");
+
+    let mut surface = match GpuSurface::new(800, 600) {
+        Ok(surface) => surface,
+        Err(err) => {
+            println!("⚠️  GPU backend unavailable: {}", err);
+            return Ok(());
+        }
+    };
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    // Filled shapes
+    println!("Drawing shapes...");
+    canvas.draw_rect(
+        Rect::new(50.0, 50.0, 200.0, 150.0),
+        &Paint::with_color(Color::rgb(66, 133, 244)),
+    );
+    canvas.draw_circle(
+        Point::new(450.0, 125.0),
+        80.0,
+        &Paint::with_color(Color::rgba(219, 68, 55, 200)),
+    );
+
+    // A rotated star
+    let star = Path::builder()
+        .move_to(Point::new(0.0, -70.0))
+        .line_to(Point::new(41.0, 57.0))
+        .line_to(Point::new(-67.0, -22.0))
+        .line_to(Point::new(67.0, -22.0))
+        .line_to(Point::new(-41.0, 57.0))
+        .close()
+        .build();
+    canvas.save();
+    canvas.translate(650.0, 125.0);
+    canvas.rotate(15.0);
+    canvas.draw_path(&star, &Paint::with_color(Color::rgb(244, 180, 0)));
+    canvas.restore();
+
+    // Dashed stroke
+    println!("Drawing strokes...");
+    let mut stroke = StrokeStyle::new(8.0);
+    stroke.cap = LineCap::Round;
+    stroke.set_dash(vec![20.0, 16.0], 0.0);
+    let mut paint = Paint::with_color(Color::rgb(15, 157, 88));
+    paint.set_stroke(stroke);
+    canvas.draw_line(Point::new(50.0, 300.0), Point::new(750.0, 300.0), &paint);
+
+    // Clipped circles
+    println!("Drawing clipped content...");
+    canvas.save();
+    canvas.clip_rect(
+        Rect::new(50.0, 350.0, 700.0, 200.0),
+        sina::ClipOp::Intersect,
+        true,
+    );
+    for i in 0..8 {
+        canvas.draw_circle(
+            Point::new(50.0 + i as f32 * 100.0, 450.0),
+            70.0,
+            &Paint::with_color(Color::rgba(100, 60, 200, 90)),
+        );
+    }
+    canvas.restore();
+
+    let path = "examples/output/misc/gpu_rendering.png";
+    std::fs::create_dir_all("examples/output/misc")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
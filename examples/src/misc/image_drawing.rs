@@ -0,0 +1,170 @@
+//! Image drawing example: scaling with each filter quality and nine-patches
+
+use sina::{
+    Color, CpuSurface, FilterQuality, Gradient, GradientStop, IRect, Image, Paint, PixelFormat,
+    Point, Rect, Shadow, Surface,
+};
+
+/// Render a small picture to use as an image
+fn make_picture() -> Result<Image, Box<dyn std::error::Error>> {
+    let mut surface = CpuSurface::new(32, 32);
+    let canvas = surface.canvas();
+    let mut paint = Paint::new();
+    paint.set_shader(Gradient::linear(
+        Point::new(0.0, 0.0),
+        Point::new(32.0, 32.0),
+        vec![
+            GradientStop::new(0.0, Color::rgb(66, 133, 244)),
+            GradientStop::new(1.0, Color::rgb(15, 157, 88)),
+        ],
+    ));
+    canvas.draw_rect(Rect::new(0.0, 0.0, 32.0, 32.0), &paint);
+    canvas.draw_circle(
+        Point::new(16.0, 16.0),
+        9.0,
+        &Paint::with_color(Color::rgb(244, 180, 0)),
+    );
+    for i in 0..4 {
+        let x = 2.0 + i as f32 * 8.0;
+        canvas.draw_rect(
+            Rect::new(x, 2.0, 4.0, 2.0),
+            &Paint::with_color(Color::WHITE),
+        );
+    }
+
+    let pixels = surface.pixels(PixelFormat::RGBA8)?;
+    Ok(Image::from_pixels(
+        32,
+        32,
+        PixelFormat::RGBA8,
+        &pixels,
+        32 * 4,
+    )?)
+}
+
+/// Build a rounded button skin with a 4-pixel border
+fn make_button() -> Result<Image, Box<dyn std::error::Error>> {
+    let mut surface = CpuSurface::new(12, 12);
+    let canvas = surface.canvas();
+    canvas.draw_circle(
+        Point::new(6.0, 6.0),
+        6.0,
+        &Paint::with_color(Color::rgb(60, 64, 67)),
+    );
+    canvas.draw_rect(
+        Rect::new(0.0, 4.0, 12.0, 4.0),
+        &Paint::with_color(Color::rgb(60, 64, 67)),
+    );
+    canvas.draw_rect(
+        Rect::new(4.0, 0.0, 4.0, 12.0),
+        &Paint::with_color(Color::rgb(60, 64, 67)),
+    );
+    canvas.draw_rect(
+        Rect::new(2.0, 2.0, 8.0, 8.0),
+        &Paint::with_color(Color::rgb(232, 240, 254)),
+    );
+
+    let pixels = surface.pixels(PixelFormat::RGBA8)?;
+    Ok(Image::from_pixels(
+        12,
+        12,
+        PixelFormat::RGBA8,
+        &pixels,
+        12 * 4,
+    )?)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🖼️  Image Drawing Example\n");
+
+    let picture = make_picture()?;
+    let button = make_button()?;
+
+    let mut surface = CpuSurface::new(800, 600);
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    // The same image enlarged and shrunk with each filter quality
+    println!("Drawing scaled images...");
+    let qualities = [
+        FilterQuality::Nearest,
+        FilterQuality::Bilinear,
+        FilterQuality::Bicubic,
+        FilterQuality::Mipmap,
+    ];
+    for (i, quality) in qualities.into_iter().enumerate() {
+        let x = 40.0 + i as f32 * 185.0;
+        canvas.draw_image_rect(
+            &picture,
+            picture.bounds(),
+            Rect::new(x, 40.0, 160.0, 160.0),
+            quality,
+            &Paint::new(),
+        );
+        canvas.draw_image_rect(
+            &picture,
+            picture.bounds(),
+            Rect::new(x, 215.0, 12.0, 12.0),
+            quality,
+            &Paint::new(),
+        );
+    }
+
+    // Natural size, a cropped part, and a rotated copy with a shadow
+    println!("Drawing transformed images...");
+    canvas.draw_image(&picture, Point::new(40.0, 260.0), &Paint::new());
+    canvas.draw_image_rect(
+        &picture,
+        Rect::new(8.0, 8.0, 16.0, 16.0),
+        Rect::new(100.0, 260.0, 96.0, 96.0),
+        FilterQuality::Bicubic,
+        &Paint::new(),
+    );
+    let mut paint = Paint::with_color(Color::rgba(0, 0, 0, 220));
+    paint.add_shadow(Shadow::drop_shadow(
+        Point::new(8.0, 8.0),
+        10.0,
+        Color::rgba(0, 0, 0, 100),
+    ));
+    canvas.save();
+    canvas.translate(320.0, 310.0);
+    canvas.rotate(-20.0);
+    canvas.draw_image_rect(
+        &picture,
+        picture.bounds(),
+        Rect::new(-50.0, -50.0, 100.0, 100.0),
+        FilterQuality::Bilinear,
+        &paint,
+    );
+    canvas.restore();
+
+    // Nine-patch buttons keep their corners at any size
+    println!("Drawing nine-patches...");
+    let center = IRect::new(4, 4, 4, 4);
+    for (i, size) in [(60.0, 40.0), (160.0, 48.0), (300.0, 60.0)]
+        .into_iter()
+        .enumerate()
+    {
+        canvas.draw_image_nine(
+            &button,
+            center,
+            Rect::new(420.0, 260.0 + i as f32 * 80.0, size.0, size.1),
+            FilterQuality::Nearest,
+            &Paint::new(),
+        );
+    }
+    canvas.draw_image_nine(
+        &button,
+        center,
+        Rect::new(40.0, 400.0, 320.0, 160.0),
+        FilterQuality::Nearest,
+        &Paint::new(),
+    );
+
+    let path = "examples/output/misc/image_drawing.png";
+    std::fs::create_dir_all("examples/output/misc")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! SVG rendering example: drawing a small SVG document at several sizes
+
+use sina::{Color, CpuSurface, Font, Rect, Surface, SvgDocument};
+
+const BADGE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg"
+     xmlns:xlink="http://www.w3.org/1999/xlink"
+     width="200" height="200" viewBox="0 0 100 100">
+    <defs>
+        <radialGradient id="sky" cx="50%" cy="35%" r="65%" fx="40%" fy="25%">
+            <stop offset="0" stop-color="#8ecae6"/>
+            <stop offset="1" stop-color="#023047"/>
+        </radialGradient>
+        <linearGradient id="sun" x1="0" y1="0" x2="0" y2="1">
+            <stop offset="0" stop-color="#ffb703"/>
+            <stop offset="100%" stop-color="#fb8500"/>
+        </linearGradient>
+        <clipPath id="disc">
+            <circle cx="50" cy="50" r="44"/>
+        </clipPath>
+        <path id="ray" d="M50 14 l3 8 h-6 z" fill="#ffb703"/>
+    </defs>
+
+    <circle cx="50" cy="50" r="46" fill="url(#sky)" stroke="#023047" stroke-width="3"/>
+    <g clip-path="url(#disc)">
+        <circle cx="50" cy="46" r="14" fill="url(#sun)"/>
+        <g>
+            <use xlink:href="#ray"/>
+            <use xlink:href="#ray" transform="rotate(45 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(90 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(135 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(180 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(225 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(270 50 46)"/>
+            <use xlink:href="#ray" transform="rotate(315 50 46)"/>
+        </g>
+        <path d="M0 80 Q25 62 50 76 T100 72 V100 H0 Z" fill="#219ebc"/>
+        <path d="M0 88 Q30 76 55 86 T100 84 V100 H0 Z" style="fill: #126782; opacity: 0.8"/>
+    </g>
+    <polyline points="20,30 28,26 36,30" fill="none" stroke="white"
+              stroke-width="1.5" stroke-linecap="round" stroke-dasharray="3 2"/>
+    <text x="50" y="97" font-size="7" text-anchor="middle" fill="white">sina</text>
+</svg>"##;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🎨 SVG Rendering Demo");
+    println!("=====================\n");
+
+    let mut badge = SvgDocument::parse(BADGE)?;
+    match Font::from_file("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf") {
+        Ok(font) => badge.set_font(font),
+        Err(_) => println!("DejaVu Sans not found, skipping text"),
+    }
+
+    let mut surface = CpuSurface::new(800, 400);
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    // At its own size, then scaled up and down
+    println!("Rendering document...");
+    canvas.save();
+    canvas.translate(40.0, 100.0);
+    badge.render(canvas);
+    canvas.restore();
+
+    badge.render_in_rect(canvas, Rect::new(280.0, 40.0, 320.0, 320.0));
+    for i in 0..3 {
+        let size = 32.0 + i as f32 * 24.0;
+        badge.render_in_rect(
+            canvas,
+            Rect::new(640.0, 40.0 + i as f32 * 110.0, size, size),
+        );
+    }
+
+    let path = "examples/output/misc/svg_rendering.png";
+    std::fs::create_dir_all("examples/output/misc")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
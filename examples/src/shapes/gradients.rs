@@ -1,6 +1,8 @@
-//! Gradient effects example (simulated with multiple colors)
+//! Gradient shader example
 
-use sina::{Color, CpuSurface, Paint, Point, Rect, Surface};
+use sina::{
+    Color, CpuSurface, Gradient, GradientStop, Matrix3x3, Paint, Point, Rect, Surface, TileMode,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🎨 Gradient Effects Example\n");
@@ -8,45 +10,75 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut surface = CpuSurface::new(1000, 800);
     surface.canvas().clear(Color::WHITE);
 
-    // Linear gradient effect (horizontal)
-    println!("Creating horizontal gradient...");
-    for i in 0..100 {
-        let progress = i as f32 / 100.0;
-        let r = (255.0 * (1.0 - progress)) as u8;
-        let b = (255.0 * progress) as u8;
-        let paint = Paint::with_color(Color::rgb(r, 0, b));
+    // Linear gradient (horizontal)
+    println!("Creating linear gradient...");
+    let mut paint = Paint::default();
+    paint.set_shader(Gradient::linear(
+        Point::new(50.0, 0.0),
+        Point::new(950.0, 0.0),
+        vec![
+            GradientStop::new(0.0, Color::rgb(255, 0, 0)),
+            GradientStop::new(1.0, Color::rgb(0, 0, 255)),
+        ],
+    ));
+    surface
+        .canvas()
+        .draw_rect(Rect::new(50.0, 50.0, 900.0, 150.0), &paint);
 
-        surface
-            .canvas()
-            .draw_rect(Rect::new(50.0 + i as f32 * 9.0, 50.0, 10.0, 150.0), &paint);
-    }
-
-    // Vertical gradient
-    println!("Creating vertical gradient...");
-    for i in 0..100 {
-        let progress = i as f32 / 100.0;
-        let g = (255.0 * progress) as u8;
-        let paint = Paint::with_color(Color::rgb(50, g, 50));
-
-        surface
-            .canvas()
-            .draw_rect(Rect::new(50.0, 250.0 + i as f32 * 2.5, 900.0, 3.0), &paint);
+    // Repeating and mirrored stripes
+    println!("Creating tiled gradients...");
+    let stripes = vec![
+        GradientStop::new(0.0, Color::rgb(50, 50, 50)),
+        GradientStop::new(1.0, Color::rgb(50, 255, 50)),
+    ];
+    for (i, tile_mode) in [TileMode::Repeat, TileMode::Mirror].into_iter().enumerate() {
+        let mut paint = Paint::default();
+        paint.set_shader(
+            Gradient::linear(Point::new(0.0, 0.0), Point::new(0.0, 50.0), stripes.clone())
+                .with_tile_mode(tile_mode),
+        );
+        surface.canvas().draw_rect(
+            Rect::new(50.0 + i as f32 * 460.0, 250.0, 440.0, 200.0),
+            &paint,
+        );
     }
 
-    // Radial gradient effect (circular)
+    // Radial gradient
     println!("Creating radial gradient...");
-    let center = Point::new(500.0, 600.0);
-    for i in (0..80).rev() {
-        let radius = i as f32 * 2.0;
-        let intensity = (255.0 * (i as f32 / 80.0)) as u8;
-        let paint = Paint::with_color(Color::rgb(255, intensity, 0));
+    let center = Point::new(200.0, 630.0);
+    let mut paint = Paint::default();
+    paint.set_shader(Gradient::radial(
+        center,
+        140.0,
+        vec![
+            GradientStop::new(0.0, Color::rgb(255, 255, 0)),
+            GradientStop::new(1.0, Color::rgb(255, 0, 0)),
+        ],
+    ));
+    surface.canvas().draw_circle(center, 140.0, &paint);
 
-        surface.canvas().draw_circle(center, radius, &paint);
-    }
+    // Two-point conical gradient, squashed with a local matrix
+    println!("Creating two-point conical gradient...");
+    let center = Point::new(500.0, 630.0);
+    let mut paint = Paint::default();
+    paint.set_shader(
+        Gradient::two_point_conical(
+            Point::new(-40.0, -40.0),
+            10.0,
+            Point::new(0.0, 0.0),
+            140.0,
+            vec![
+                GradientStop::new(0.0, Color::WHITE),
+                GradientStop::new(1.0, Color::rgb(0, 90, 200)),
+            ],
+        )
+        .with_local_matrix(Matrix3x3::from_translation(center)),
+    );
+    surface.canvas().draw_circle(center, 140.0, &paint);
 
-    // Multi-color gradient
-    println!("Creating multi-color gradient...");
-    let colors = vec![
+    // Sweep gradient through the rainbow
+    println!("Creating sweep gradient...");
+    let colors = [
         Color::rgb(255, 0, 0),   // Red
         Color::rgb(255, 127, 0), // Orange
         Color::rgb(255, 255, 0), // Yellow
@@ -54,15 +86,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Color::rgb(0, 0, 255),   // Blue
         Color::rgb(75, 0, 130),  // Indigo
         Color::rgb(148, 0, 211), // Violet
+        Color::rgb(255, 0, 0),   // Red
     ];
-
-    for (i, color) in colors.iter().enumerate() {
-        let paint = Paint::with_color(*color);
-        surface.canvas().draw_rect(
-            Rect::new(50.0 + i as f32 * 130.0, 500.0, 130.0, 60.0),
-            &paint,
-        );
-    }
+    let stops = colors
+        .iter()
+        .enumerate()
+        .map(|(i, color)| GradientStop::new(i as f32 / (colors.len() - 1) as f32, *color))
+        .collect();
+    let center = Point::new(800.0, 630.0);
+    let mut paint = Paint::default();
+    paint.set_shader(Gradient::sweep(center, 0.0, 360.0, stops));
+    surface.canvas().draw_circle(center, 140.0, &paint);
 
     let path = "examples/output/shapes/gradients.png";
     std::fs::create_dir_all("examples/output/shapes")?;
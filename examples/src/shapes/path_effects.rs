@@ -0,0 +1,83 @@
+//! Path effects example: dashing, rounding, jittering and trimming paths
+
+use sina::{
+    Color, CpuSurface, LineCap, LineJoin, Paint, Path, PathEffect, Point, Rect, StrokeStyle,
+    Surface,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("〰️ Path Effects Example\n");
+
+    let mut surface = CpuSurface::new(1000, 440);
+    let canvas = surface.canvas();
+    canvas.clear(Color::rgb(245, 245, 250));
+
+    let star = |center: Point| Path::builder().add_star(center, 5, 70.0, 30.0).build();
+    let mut guide = Paint::with_color(Color::rgba(0, 0, 0, 40));
+    guide.set_stroke(StrokeStyle::new(1.0));
+
+    // One effect per star, stroked
+    let effects = [
+        ("dash", PathEffect::dash(vec![14.0, 6.0, 2.0, 6.0], 0.0)),
+        ("corner rounding", PathEffect::corner_rounding(12.0)),
+        ("discrete", PathEffect::discrete(8.0, 3.0, 1)),
+        ("trim", PathEffect::trim(0.1, 0.65)),
+        (
+            "rounded dashes",
+            PathEffect::compose(
+                PathEffect::dash(vec![20.0, 8.0], 0.0),
+                PathEffect::corner_rounding(12.0),
+            ),
+        ),
+    ];
+    for (i, (name, effect)) in effects.into_iter().enumerate() {
+        println!("Drawing {}...", name);
+        let path = star(Point::new(100.0 + i as f32 * 200.0, 110.0));
+        let mut paint = Paint::with_color(Color::rgb(66, 133, 244));
+        let mut stroke = StrokeStyle::new(5.0);
+        stroke.cap = LineCap::Round;
+        paint.set_stroke(stroke);
+        paint.set_path_effect(effect);
+
+        canvas.draw_path(&path, &guide);
+        canvas.draw_path(&path, &paint);
+    }
+
+    // Effects on fills: a rounded and a jittered card
+    println!("Drawing filled shapes...");
+    let mut paint = Paint::with_color(Color::rgb(15, 157, 88));
+    paint.set_path_effect(PathEffect::corner_rounding(24.0));
+    canvas.draw_rect(Rect::new(40.0, 250.0, 200.0, 140.0), &paint);
+
+    let mut paint = Paint::with_color(Color::rgb(244, 180, 0));
+    paint.set_path_effect(PathEffect::discrete(10.0, 4.0, 7));
+    canvas.draw_circle(Point::new(380.0, 320.0), 70.0, &paint);
+
+    // A stroke turned into an outline, then outlined itself
+    println!("Drawing stroke outline...");
+    let wave = Path::builder()
+        .move_to(Point::new(520.0, 320.0))
+        .cubic_to(
+            Point::new(600.0, 220.0),
+            Point::new(680.0, 420.0),
+            Point::new(760.0, 320.0),
+        )
+        .quad_to(Point::new(840.0, 240.0), Point::new(940.0, 320.0))
+        .build();
+    let mut stroke = StrokeStyle::new(28.0);
+    stroke.cap = LineCap::Round;
+    stroke.join = LineJoin::Round;
+    let outline = wave.stroke_to_path(&stroke);
+    canvas.draw_path(&outline, &Paint::with_color(Color::rgb(219, 68, 55)));
+    let mut edge = Paint::with_color(Color::rgb(60, 20, 20));
+    edge.set_stroke(StrokeStyle::new(2.0));
+    edge.set_path_effect(PathEffect::dash(vec![6.0, 4.0], 0.0));
+    canvas.draw_path(&outline, &edge);
+
+    let path = "examples/output/shapes/path_effects.png";
+    std::fs::create_dir_all("examples/output/shapes")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Boolean path operations example: combining a circle and a star
+
+use sina::{Color, CpuSurface, FillRule, Paint, Path, PathOp, Point, Rect, StrokeStyle, Surface};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("➕ Path Operations Example\n");
+
+    let mut surface = CpuSurface::new(1200, 320);
+    let canvas = surface.canvas();
+    canvas.clear(Color::rgb(245, 245, 250));
+
+    let mut outline = Paint::with_color(Color::rgba(0, 0, 0, 90));
+    outline.set_stroke(StrokeStyle::new(1.5));
+    let fill = Paint::with_color(Color::rgb(66, 133, 244));
+
+    // The same pair of shapes, combined with each operation
+    let ops = [
+        ("union", PathOp::Union),
+        ("intersect", PathOp::Intersect),
+        ("difference", PathOp::Difference),
+        ("reverse difference", PathOp::ReverseDifference),
+        ("xor", PathOp::Xor),
+    ];
+    for (i, (name, op)) in ops.into_iter().enumerate() {
+        println!("Drawing {}...", name);
+        let center = Point::new(110.0 + i as f32 * 200.0, 110.0);
+        let circle = Path::builder()
+            .add_circle(center - Point::new(20.0, 0.0), 55.0)
+            .build();
+        let star = Path::builder()
+            .add_star(center + Point::new(30.0, 0.0), 5, 70.0, 30.0)
+            .build();
+
+        canvas.draw_path(&circle.op(&star, op), &fill);
+        canvas.draw_path(&circle, &outline);
+        canvas.draw_path(&star, &outline);
+    }
+
+    // A self-intersecting even-odd path, and its simplified outline
+    println!("Drawing simplified path...");
+    let rings = Path::builder()
+        .add_rect(Rect::new(40.0, 220.0, 120.0, 80.0))
+        .add_rect(Rect::new(100.0, 240.0, 120.0, 40.0))
+        .add_star(Point::new(330.0, 260.0), 7, 50.0, 20.0)
+        .build()
+        .with_fill_rule(FillRule::EvenOdd);
+    canvas.draw_path(&rings, &Paint::with_color(Color::rgb(15, 157, 88)));
+    canvas.save();
+    canvas.translate(420.0, 0.0);
+    let simplified = rings.simplify();
+    canvas.draw_path(&simplified, &Paint::with_color(Color::rgb(244, 180, 0)));
+    canvas.draw_path(&simplified, &outline);
+    canvas.restore();
+
+    let path = "examples/output/shapes/path_ops.png";
+    std::fs::create_dir_all("examples/output/shapes")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
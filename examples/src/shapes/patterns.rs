@@ -1,6 +1,6 @@
 //! Pattern generation examples
 
-use sina::{Color, CpuSurface, Paint, Path, Point, Rect, Surface};
+use sina::{Color, CpuSurface, Paint, Point, Rect, Surface};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔲 Pattern Generation Example\n");
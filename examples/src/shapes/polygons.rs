@@ -95,12 +95,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Diamond (4-sided polygon rotated)
     println!("Drawing diamond...");
     let center = Point::new(450.0, 750.0);
-    let diamond = Path::builder()
-        .move_to(Point::new(center.x, center.y - 90.0))
-        .line_to(Point::new(center.x + 60.0, center.y))
-        .line_to(Point::new(center.x, center.y + 90.0))
-        .line_to(Point::new(center.x - 60.0, center.y))
-        .close();
+    let diamond = Path::builder().add_polygon(
+        &[
+            Point::new(center.x, center.y - 90.0),
+            Point::new(center.x + 60.0, center.y),
+            Point::new(center.x, center.y + 90.0),
+            Point::new(center.x - 60.0, center.y),
+        ],
+        true,
+    );
 
     let paint = Paint::with_color(Color::rgb(255, 100, 255));
     surface.canvas().draw_path(&diamond.build(), &paint);
@@ -120,20 +123,13 @@ fn draw_regular_polygon(
     sides: u32,
     color: Color,
 ) {
-    let mut path = Path::builder();
-
-    for i in 0..sides {
-        let angle = (i as f32 * 2.0 * PI / sides as f32) - PI / 2.0;
-        let x = center.x + radius * angle.cos();
-        let y = center.y + radius * angle.sin();
-
-        if i == 0 {
-            path = path.move_to(Point::new(x, y));
-        } else {
-            path = path.line_to(Point::new(x, y));
-        }
-    }
-    path = path.close();
+    let points: Vec<Point> = (0..sides)
+        .map(|i| {
+            let angle = (i as f32 * 2.0 * PI / sides as f32) - PI / 2.0;
+            center + Point::from_angle(angle) * radius
+        })
+        .collect();
+    let path = Path::builder().add_polygon(&points, true);
 
     let paint = Paint::with_color(color);
     surface.canvas().draw_path(&path.build(), &paint);
@@ -147,25 +143,7 @@ fn draw_star(
     points: u32,
     color: Color,
 ) {
-    let mut path = Path::builder();
-
-    for i in 0..(points * 2) {
-        let angle = (i as f32 * PI / points as f32) - PI / 2.0;
-        let radius = if i % 2 == 0 {
-            outer_radius
-        } else {
-            inner_radius
-        };
-        let x = center.x + radius * angle.cos();
-        let y = center.y + radius * angle.sin();
-
-        if i == 0 {
-            path = path.move_to(Point::new(x, y));
-        } else {
-            path = path.line_to(Point::new(x, y));
-        }
-    }
-    path = path.close();
+    let path = Path::builder().add_star(center, points, outer_radius, inner_radius);
 
     let paint = Paint::with_color(color);
     surface.canvas().draw_path(&path.build(), &paint);
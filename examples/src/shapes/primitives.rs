@@ -0,0 +1,104 @@
+//! Canvas primitives example: rounded rectangles, rings, ovals, arcs and points
+
+use sina::{
+    Color, CpuSurface, LineCap, Paint, Point, PointMode, RRect, Rect, StrokeStyle, Surface,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧩 Canvas Primitives Example\n");
+
+    let mut surface = CpuSurface::new(900, 420);
+    let canvas = surface.canvas();
+    canvas.clear(Color::rgb(245, 245, 250));
+
+    // A card and a button
+    println!("Drawing rounded rectangles...");
+    let card = RRect::new(Rect::new(30.0, 30.0, 240.0, 160.0), 18.0, 18.0);
+    canvas.draw_rrect(card, &Paint::with_color(Color::WHITE));
+    let mut border = Paint::with_color(Color::rgb(200, 200, 215));
+    border.set_stroke(StrokeStyle::new(2.0));
+    canvas.draw_rrect(card, &border);
+    let button = RRect::new(Rect::new(60.0, 130.0, 180.0, 40.0), 20.0, 20.0);
+    canvas.draw_rrect(button, &Paint::with_color(Color::rgb(66, 133, 244)));
+
+    // A frame around an oval avatar
+    println!("Drawing ring and oval...");
+    let outer = RRect::new(Rect::new(300.0, 30.0, 160.0, 160.0), 40.0, 40.0);
+    let inner = RRect::new(Rect::new(316.0, 46.0, 128.0, 128.0), 28.0, 28.0);
+    canvas.draw_drrect(outer, inner, &Paint::with_color(Color::rgb(15, 157, 88)));
+    canvas.draw_oval(
+        Rect::new(330.0, 70.0, 100.0, 80.0),
+        &Paint::with_color(Color::rgb(244, 180, 0)),
+    );
+
+    // A pie chart of wedges and a progress ring
+    println!("Drawing arcs...");
+    let pie = Rect::new(500.0, 30.0, 160.0, 160.0);
+    let slices = [
+        (0.35, Color::rgb(66, 133, 244)),
+        (0.25, Color::rgb(219, 68, 55)),
+        (0.22, Color::rgb(244, 180, 0)),
+        (0.18, Color::rgb(15, 157, 88)),
+    ];
+    let mut start = -90.0;
+    for (share, color) in slices {
+        let sweep = share * 360.0;
+        canvas.draw_arc(pie, start, sweep, true, &Paint::with_color(color));
+        start += sweep;
+    }
+
+    let ring = Rect::new(700.0, 40.0, 140.0, 140.0);
+    let mut track = Paint::with_color(Color::rgb(225, 225, 235));
+    let mut style = StrokeStyle::new(16.0);
+    style.cap = LineCap::Round;
+    track.set_stroke(style.clone());
+    canvas.draw_oval(ring, &track);
+    let mut progress = Paint::with_color(Color::rgb(66, 133, 244));
+    progress.set_stroke(style);
+    canvas.draw_arc(ring, -90.0, 0.7 * 360.0, false, &progress);
+
+    // A chord segment
+    canvas.draw_arc(
+        Rect::new(30.0, 230.0, 240.0, 160.0),
+        200.0,
+        140.0,
+        false,
+        &Paint::with_color(Color::rgb(171, 71, 188)),
+    );
+
+    // Scatter points, paired lines and a polyline through a sine wave
+    println!("Drawing points...");
+    let wave: Vec<Point> = (0..=40)
+        .map(|i| {
+            let x = i as f32 / 40.0;
+            Point::new(
+                320.0 + x * 540.0,
+                310.0 - (x * std::f32::consts::TAU * 2.0).sin() * 60.0,
+            )
+        })
+        .collect();
+
+    let mut line = Paint::with_color(Color::rgb(120, 120, 140));
+    line.set_stroke(StrokeStyle::new(2.0));
+    canvas.draw_points(PointMode::Polygon, &wave, &line);
+
+    let ticks: Vec<Point> = wave
+        .iter()
+        .step_by(4)
+        .flat_map(|p| [Point::new(p.x, 390.0), Point::new(p.x, 400.0)])
+        .collect();
+    canvas.draw_points(PointMode::Lines, &ticks, &line);
+
+    let mut dots = Paint::with_color(Color::rgb(219, 68, 55));
+    let mut style = StrokeStyle::new(9.0);
+    style.cap = LineCap::Round;
+    dots.set_stroke(style);
+    canvas.draw_points(PointMode::Points, &wave, &dots);
+
+    let path = "examples/output/shapes/primitives.png";
+    std::fs::create_dir_all("examples/output/shapes")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
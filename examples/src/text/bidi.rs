@@ -0,0 +1,71 @@
+//! Bidirectional text example: mixed Hebrew, Arabic and Latin text
+
+use sina::{
+    Color, CpuSurface, Font, Paint, ParagraphStyle, Point, Rect, StrokeStyle, Surface, TextAlign,
+    TextDirection, TextLayout,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("↔️ Bidirectional Text Example\n");
+
+    let font = Font::from_file("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")?;
+    let layout = TextLayout::new(font, 22.0);
+
+    let mut surface = CpuSurface::new(900, 370);
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    let ink = Paint::with_color(Color::rgb(30, 30, 40));
+    let label = Paint::with_color(Color::rgb(120, 120, 140));
+    let mut frame = Paint::with_color(Color::rgb(200, 200, 215));
+    frame.set_stroke(StrokeStyle::new(1.0));
+
+    // The same line in each base direction
+    let line = "Shalom is שלום עולם and salaam is مرحبا (2024).";
+    let directions = [
+        ("Auto", TextDirection::Auto),
+        ("Left to right", TextDirection::Ltr),
+        ("Right to left", TextDirection::Rtl),
+    ];
+    for (i, (name, direction)) in directions.into_iter().enumerate() {
+        println!("Laying out {}...", name);
+        let y = 60.0 + i as f32 * 60.0;
+        canvas.draw_text(name, Point::new(30.0, y), layout.font(), 14.0, &label);
+
+        let origin = Point::new(180.0, y - 22.0);
+        let paragraph =
+            layout.paragraph(line, &ParagraphStyle::default().with_direction(direction));
+        canvas.draw_rect(
+            Rect::new(origin.x, origin.y, paragraph.width(), paragraph.height()),
+            &frame,
+        );
+        paragraph.paint(canvas, origin, &ink);
+    }
+
+    // Right-to-left paragraphs wrap and justify from the right
+    println!("Laying out paragraphs...");
+    let text = "עברית נכתבת מימין לשמאל, וגם العربية. Latin words such as Sina \
+keep their order inside the line.";
+    let style = ParagraphStyle::new(380.0)
+        .with_align(TextAlign::Justify)
+        .with_direction(TextDirection::Rtl);
+    for (i, style) in [style.clone(), style.with_align(TextAlign::Right)]
+        .into_iter()
+        .enumerate()
+    {
+        let origin = Point::new(30.0 + i as f32 * 450.0, 260.0);
+        let paragraph = layout.paragraph(text, &style);
+        canvas.draw_rect(
+            Rect::new(origin.x, origin.y, 380.0, paragraph.height()),
+            &frame,
+        );
+        paragraph.paint(canvas, origin, &ink);
+    }
+
+    let path = "examples/output/text/bidi.png";
+    std::fs::create_dir_all("examples/output/text")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
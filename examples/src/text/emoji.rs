@@ -1,4 +1,4 @@
-use sina::{Color, CpuSurface, Font, Paint, Point, Surface};
+use sina::{Color, CpuSurface, Font, FontCollection, Paint, Point, Surface};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🤡 Sina Emoji Rendering Test");
@@ -21,24 +21,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let text_font = Font::from_file(&text_path).expect("Failed to load text font");
         let emoji_font = Font::from_file(&emoji_path).expect("Failed to load emoji font");
 
-        // 3. Draw Standard Text
+        // 3. Draw text and emoji in one call, with the emoji font as fallback
+        let fonts = FontCollection::new(text_font).with_fallback(emoji_font);
         let paint = Paint::with_color(Color::rgb(20, 20, 20)); // Dark Gray
-        surface.canvas().draw_text(
-            "Hello World! Text + Emoji:",
+        surface.canvas().draw_text_with_fallback(
+            "Hello World! Text + Emoji: 😀🌍🦀🚀⚡📍🔎",
             Point::new(50.0, 200.0),
-            &text_font,
-            60.0,
-            &paint,
-        );
-
-        // 4. Draw Emojis (using Emoji Font)
-        // Positioned after the text (ap
-        // prox width)
-        let emoji_text = "😀🌍🦀🚀⚡📍🔎";
-        surface.canvas().draw_text(
-            emoji_text,
-            Point::new(850.0, 200.0), // Hardcoded relative position for simplicity
-            &emoji_font,
+            &fonts,
             60.0,
             &paint, // Color is ignored for RGBA bitmaps, but logic uses alpha
         );
@@ -87,10 +76,10 @@ fn find_font(name: &str) -> Option<String> {
 }
 
 fn find_emoji_font() -> Option<String> {
-    if cfg!(target_os = "macos") {
-        if std::path::Path::new("/System/Library/Fonts/Apple Color Emoji.ttc").exists() {
-            return Some("/System/Library/Fonts/Apple Color Emoji.ttc".to_string());
-        }
+    if cfg!(target_os = "macos")
+        && std::path::Path::new("/System/Library/Fonts/Apple Color Emoji.ttc").exists()
+    {
+        return Some("/System/Library/Fonts/Apple Color Emoji.ttc".to_string());
     }
 
     // Linux/Other
@@ -0,0 +1,70 @@
+//! OpenType features example: ligatures, kerning and localized forms
+
+use sina::{
+    Color, CpuSurface, Font, FontCollection, FontFeature, Paint, Point, Surface, TextStyle,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔠 OpenType Features Example\n");
+
+    let font = Font::from_file("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")?;
+    let fonts = FontCollection::new(font);
+
+    let mut surface = CpuSurface::new(760, 420);
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    let ink = Paint::with_color(Color::rgb(30, 30, 40));
+    let label = Paint::with_color(Color::rgb(120, 120, 140));
+
+    // Each sample is drawn with the font's defaults and then with a style
+    let samples = [
+        (
+            "Ligatures off",
+            "office affine fluffy",
+            TextStyle::new().with_feature(FontFeature::off("liga")),
+        ),
+        (
+            "Kerning off",
+            "AVATAR Ty Wo",
+            TextStyle::new().with_feature(FontFeature::off("kern")),
+        ),
+        (
+            "Ligatures off in \"fi\" only",
+            "fi fl fi fl",
+            TextStyle::new().with_feature(FontFeature::off("liga").with_range(0..2)),
+        ),
+        (
+            "Serbian forms",
+            "бгдпт",
+            TextStyle::new().with_language("sr").with_script("Cyrl"),
+        ),
+    ];
+    for (i, (name, text, style)) in samples.into_iter().enumerate() {
+        println!("Drawing {}...", name);
+        let y = 60.0 + i as f32 * 95.0;
+        canvas.draw_text(
+            name,
+            Point::new(30.0, y - 30.0),
+            fonts.primary(),
+            14.0,
+            &label,
+        );
+        canvas.draw_text_with_fallback(text, Point::new(30.0, y + 10.0), &fonts, 32.0, &ink);
+        canvas.draw_text_styled(
+            text,
+            Point::new(400.0, y + 10.0),
+            &fonts,
+            32.0,
+            &style,
+            &ink,
+        );
+    }
+
+    let path = "examples/output/text/font_features.png";
+    std::fs::create_dir_all("examples/output/text")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
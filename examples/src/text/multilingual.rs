@@ -1,6 +1,6 @@
 //! Multilingual text rendering example
 
-use sina::{Color, CpuSurface, Font, Paint, Point, Surface};
+use sina::{Color, CpuSurface, Font, FontCollection, Paint, Point, Surface};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🌍 Sina Multilingual Text Example\n");
@@ -81,13 +81,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         ),
     ];
 
+    // One collection covers every script: the default font first, then
+    // each script's font as a fallback
+    let mut fonts = FontCollection::new(default_font.clone());
+    let mut loaded = Vec::new();
+    for (_, _, font_name) in &samples {
+        if loaded.contains(font_name) {
+            continue;
+        }
+        loaded.push(*font_name);
+
+        let Some(path) = find_font(font_name) else {
+            println!("  ✗ Font not found: {}", font_name);
+            continue;
+        };
+        let font = if path.ends_with(".ttc") {
+            // For CJK collection, index 0 is usually fine for general use
+            Font::from_collection(&path, 0)
+        } else {
+            Font::from_file(&path)
+        };
+        match font {
+            Ok(font) => fonts.add_fallback(font),
+            Err(e) => println!("  ✗ Failed to load {}: {}", font_name, e),
+        }
+    }
+
     println!("Rendering {} languages...\n", samples.len());
 
-    for (i, (lang, text, font_name)) in samples.iter().enumerate() {
+    let label_paint = Paint::with_color(Color::rgb(100, 110, 130));
+    let text_paint = Paint::with_color(Color::rgb(30, 40, 60));
+    for (i, (lang, text, _)) in samples.iter().enumerate() {
         let y = 180.0 + (i as f32 * 50.0);
 
         // Language label (using default font)
-        let mut label_paint = Paint::with_color(Color::rgb(100, 110, 130));
         surface.canvas().draw_text(
             &format!("{}:", lang),
             Point::new(50.0, y),
@@ -96,34 +123,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             &label_paint,
         );
 
-        // Find the best font for this language
-        let font_path = find_font(font_name);
-
-        if let Some(path) = font_path {
-            let font = if path.ends_with(".ttc") {
-                // For CJK collection, index 0 is usually fine for general use
-                Font::from_collection(&path, 0).ok()
-            } else {
-                Font::from_file(&path).ok()
-            };
-
-            if let Some(font) = font {
-                // Text sample
-                let mut text_paint = Paint::with_color(Color::rgb(30, 40, 60));
-                surface
-                    .canvas()
-                    .draw_text(text, Point::new(200.0, y), &font, 22.0, &text_paint);
-                println!("  ✓ Rendered: {} ({})", lang, font_name);
-            } else {
-                println!("  ✗ Failed to load font for: {}", lang);
-            }
-        } else {
-            println!("  ✗ Font not found for: {} ({})", lang, font_name);
-            // Verify fallback? No, just warn for now to avoid tofu.
-        }
+        // Text sample, each character from the first font that has it
+        surface.canvas().draw_text_with_fallback(
+            text,
+            Point::new(200.0, y),
+            &fonts,
+            22.0,
+            &text_paint,
+        );
+        println!("  ✓ Rendered: {}", lang);
     }
 
-    // Save output
+    // Scripts mixed in a single line
+    surface.canvas().draw_text(
+        "Mixed:",
+        Point::new(50.0, 930.0),
+        &default_font,
+        16.0,
+        &label_paint,
+    );
+    surface.canvas().draw_text_with_fallback(
+        "Hello, Привет, নমস্কার, こんにちは, שלום!",
+        Point::new(200.0, 930.0),
+        &fonts,
+        22.0,
+        &text_paint,
+    );
+
     // Save output
     let path = "examples/output/text/multilingual.png";
     std::fs::create_dir_all("examples/output/text")?;
@@ -0,0 +1,85 @@
+//! Paragraph layout example: wrapping, alignment, line limits and spacing
+
+use sina::{
+    Color, CpuSurface, Font, Paint, ParagraphStyle, Point, Rect, StrokeStyle, Surface, TextAlign,
+    TextLayout,
+};
+
+const TEXT: &str = "Sina wraps paragraphs at Unicode line break opportunities, so \
+words, hyphen-separated compounds and long identifiers_like_this_one break where a reader \
+expects them.\nExplicit newlines always start a new line.";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("📄 Paragraph Layout Example\n");
+
+    let font = Font::from_file("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")?;
+    let layout = TextLayout::new(font, 15.0);
+
+    let mut surface = CpuSurface::new(1000, 430);
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+
+    let mut frame = Paint::with_color(Color::rgb(200, 200, 215));
+    frame.set_stroke(StrokeStyle::new(1.0));
+    let ink = Paint::with_color(Color::rgb(30, 30, 40));
+    let width = 290.0;
+
+    // The same text in each alignment, with its box and line baselines
+    let aligns = [
+        ("Left", TextAlign::Left),
+        ("Center", TextAlign::Center),
+        ("Right", TextAlign::Right),
+        ("Justify", TextAlign::Justify),
+    ];
+    for (i, (name, align)) in aligns.into_iter().enumerate() {
+        println!("Laying out {}...", name);
+        let origin = Point::new(30.0 + (i % 3) as f32 * 320.0, 30.0 + (i / 3) as f32 * 180.0);
+        let paragraph = layout.paragraph(TEXT, &ParagraphStyle::new(width).with_align(align));
+        canvas.draw_rect(
+            Rect::new(origin.x, origin.y, width, paragraph.height()),
+            &frame,
+        );
+        for line in paragraph.lines() {
+            let y = origin.y + line.baseline;
+            let left = origin.x + line.left;
+            canvas.draw_line(
+                Point::new(left, y),
+                Point::new(left + line.width, y),
+                &Paint::with_color(Color::rgba(66, 133, 244, 90)),
+            );
+        }
+        paragraph.paint(canvas, origin, &ink);
+    }
+
+    // Limited to three lines with an ellipsis, and loosely spaced
+    println!("Laying out truncated and spaced paragraphs...");
+    let origin = Point::new(350.0, 210.0);
+    let style = ParagraphStyle::new(width).with_max_lines(3, Some("…"));
+    let paragraph = layout.paragraph(TEXT, &style);
+    canvas.draw_rect(
+        Rect::new(origin.x, origin.y, width, paragraph.height()),
+        &frame,
+    );
+    paragraph.paint(canvas, origin, &ink);
+    println!(
+        "  {} lines, cut short: {}",
+        paragraph.line_count(),
+        paragraph.did_exceed_max_lines()
+    );
+
+    let origin = Point::new(670.0, 210.0);
+    let style = ParagraphStyle::new(width).with_line_spacing(1.6);
+    let paragraph = layout.paragraph(TEXT, &style);
+    canvas.draw_rect(
+        Rect::new(origin.x, origin.y, width, paragraph.height()),
+        &frame,
+    );
+    paragraph.paint(canvas, origin, &Paint::with_color(Color::rgb(15, 107, 68)));
+
+    let path = "examples/output/text/paragraph.png";
+    std::fs::create_dir_all("examples/output/text")?;
+    surface.save_png(path)?;
+    println!("\n✅ Saved to {}", path);
+
+    Ok(())
+}
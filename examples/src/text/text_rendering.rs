@@ -1,7 +1,5 @@
 //! Text rendering example demonstrating TrueType/OpenType font support
 
-use sina::{Color, CpuSurface, Font, Paint, Point, Surface};
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📝 Sina Text Rendering Example");
 
@@ -6,7 +6,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Text Rendering Showcase\n");
 
     // Find system font
-    let font_paths = vec![
+    let font_paths = [
         "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
         "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
         "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
@@ -55,7 +55,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &paint,
     );
 
-    let sizes = vec![12.0, 16.0, 20.0, 24.0, 32.0];
+    let sizes = [12.0, 16.0, 20.0, 24.0, 32.0];
     for (i, size) in sizes.iter().enumerate() {
         paint.set_color(Color::rgb(60, 70, 100));
         surface.canvas().draw_text(
@@ -81,7 +81,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &paint,
     );
 
-    let colors = vec![
+    let colors = [
         (Color::rgb(220, 50, 50), "Red"),
         (Color::rgb(50, 180, 50), "Green"),
         (Color::rgb(50, 120, 255), "Blue"),
@@ -0,0 +1,277 @@
+//! Per-pixel compositing for pixels written outside of raqote
+//!
+//! Pixels are premultiplied ARGB packed into a `u32` (raqote's format).
+//! Formulas follow the W3C Compositing and Blending specification.
+
+use crate::BlendMode;
+
+/// Premultiplied RGBA color with normalized channels
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgba {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+}
+
+impl Rgba {
+    fn unpack(pixel: u32) -> Self {
+        let channel = |shift: u32| ((pixel >> shift) & 0xFF) as f32 / 255.0;
+        Self {
+            a: channel(24),
+            r: channel(16),
+            g: channel(8),
+            b: channel(0),
+        }
+    }
+
+    fn pack(self) -> u32 {
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u32;
+        let a = channel(self.a);
+        // Keep the result a valid premultiplied color
+        let rgb = |v: f32| channel(v).min(a);
+        (a << 24) | (rgb(self.r) << 16) | (rgb(self.g) << 8) | rgb(self.b)
+    }
+
+    fn rgb(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    /// Color channels divided by alpha
+    fn unpremultiplied(self) -> [f32; 3] {
+        if self.a == 0.0 {
+            [0.0; 3]
+        } else {
+            [self.r / self.a, self.g / self.a, self.b / self.a]
+        }
+    }
+}
+
+/// Blend a premultiplied source pixel onto a premultiplied destination pixel
+pub(crate) fn blend_pixel(mode: BlendMode, src: u32, dst: u32) -> u32 {
+    if mode == BlendMode::SrcOver {
+        return src_over(src, dst);
+    }
+
+    let s = Rgba::unpack(src);
+    let d = Rgba::unpack(dst);
+
+    // Porter-Duff operators: result = src * fa + dst * fb
+    let porter_duff = |fa: f32, fb: f32| Rgba {
+        r: s.r * fa + d.r * fb,
+        g: s.g * fa + d.g * fb,
+        b: s.b * fa + d.b * fb,
+        a: s.a * fa + d.a * fb,
+    };
+
+    let result = match mode {
+        BlendMode::Clear => porter_duff(0.0, 0.0),
+        BlendMode::Src => porter_duff(1.0, 0.0),
+        BlendMode::Dst => porter_duff(0.0, 1.0),
+        BlendMode::SrcOver => porter_duff(1.0, 1.0 - s.a),
+        BlendMode::DstOver => porter_duff(1.0 - d.a, 1.0),
+        BlendMode::SrcIn => porter_duff(d.a, 0.0),
+        BlendMode::DstIn => porter_duff(0.0, s.a),
+        BlendMode::SrcOut => porter_duff(1.0 - d.a, 0.0),
+        BlendMode::DstOut => porter_duff(0.0, 1.0 - s.a),
+        BlendMode::SrcAtop => porter_duff(d.a, 1.0 - s.a),
+        BlendMode::DstAtop => porter_duff(1.0 - d.a, s.a),
+        BlendMode::Xor => porter_duff(1.0 - d.a, 1.0 - s.a),
+        BlendMode::Plus => porter_duff(1.0, 1.0),
+        _ => {
+            let cs = s.unpremultiplied();
+            let cb = d.unpremultiplied();
+            let mixed = mix(mode, cs, cb);
+            let channel = |i: usize| {
+                s.rgb()[i] * (1.0 - d.a) + d.rgb()[i] * (1.0 - s.a) + s.a * d.a * mixed[i]
+            };
+            Rgba {
+                r: channel(0),
+                g: channel(1),
+                b: channel(2),
+                a: s.a + d.a - s.a * d.a,
+            }
+        }
+    };
+
+    result.pack()
+}
+
+/// Integer fast path for the default source-over operator
+fn src_over(src: u32, dst: u32) -> u32 {
+    let inv_alpha = 255 - (src >> 24);
+    let mut out = 0;
+    for shift in [0, 8, 16, 24] {
+        let s = (src >> shift) & 0xFF;
+        let d = (dst >> shift) & 0xFF;
+        out |= (s + (d * inv_alpha + 127) / 255).min(255) << shift;
+    }
+    out
+}
+
+/// Interpolate between two premultiplied ARGB pixels by `t` (0-255)
+pub(crate) fn lerp_pixel(from: u32, to: u32, t: u8) -> u32 {
+    match t {
+        0 => from,
+        255 => to,
+        _ => {
+            let t = t as u32;
+            let mut out = 0;
+            for shift in [0, 8, 16, 24] {
+                let a = (from >> shift) & 0xFF;
+                let b = (to >> shift) & 0xFF;
+                let c = (a * (255 - t) + b * t + 127) / 255;
+                out |= c << shift;
+            }
+            out
+        }
+    }
+}
+
+/// The blend function B(Cb, Cs) for separable and non-separable modes
+fn mix(mode: BlendMode, cs: [f32; 3], cb: [f32; 3]) -> [f32; 3] {
+    let separable = |f: fn(f32, f32) -> f32| [f(cb[0], cs[0]), f(cb[1], cs[1]), f(cb[2], cs[2])];
+
+    match mode {
+        BlendMode::Multiply => separable(|b, s| b * s),
+        BlendMode::Screen => separable(screen),
+        BlendMode::Overlay => separable(|b, s| hard_light(s, b)),
+        BlendMode::Darken => separable(f32::min),
+        BlendMode::Lighten => separable(f32::max),
+        BlendMode::ColorDodge => separable(|b, s| {
+            if b == 0.0 {
+                0.0
+            } else if s >= 1.0 {
+                1.0
+            } else {
+                (b / (1.0 - s)).min(1.0)
+            }
+        }),
+        BlendMode::ColorBurn => separable(|b, s| {
+            if b >= 1.0 {
+                1.0
+            } else if s <= 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - b) / s).min(1.0)
+            }
+        }),
+        BlendMode::HardLight => separable(hard_light),
+        BlendMode::SoftLight => separable(|b, s| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 {
+                    ((16.0 * b - 12.0) * b + 4.0) * b
+                } else {
+                    b.sqrt()
+                };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        BlendMode::Difference => separable(|b, s| (b - s).abs()),
+        BlendMode::Exclusion => separable(|b, s| b + s - 2.0 * b * s),
+        BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+        BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+        BlendMode::Color => set_lum(cs, lum(cb)),
+        BlendMode::Luminosity => set_lum(cb, lum(cs)),
+        // Porter-Duff operators are handled by the caller
+        _ => cs,
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        b * 2.0 * s
+    } else {
+        screen(b, 2.0 * s - 1.0)
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn clip_color(c: [f32; 3]) -> [f32; 3] {
+    let l = lum(c);
+    let n = c[0].min(c[1]).min(c[2]);
+    let x = c[0].max(c[1]).max(c[2]);
+    let mut out = c;
+    for v in &mut out {
+        if n < 0.0 {
+            *v = l + (*v - l) * l / (l - n);
+        }
+        if x > 1.0 {
+            *v = l + (*v - l) * (1.0 - l) / (x - l);
+        }
+    }
+    out
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    clip_color([c[0] + d, c[1] + d, c[2] + d])
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let max = c[0].max(c[1]).max(c[2]);
+    let min = c[0].min(c[1]).min(c[2]);
+    if max <= min {
+        return [0.0; 3];
+    }
+    c.map(|v| (v - min) * s / (max - min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u32 = 0xFFFF0000;
+    const WHITE: u32 = 0xFFFFFFFF;
+    const HALF_BLUE: u32 = 0x80000080;
+
+    #[test]
+    fn test_porter_duff() {
+        assert_eq!(blend_pixel(BlendMode::SrcOver, RED, WHITE), RED);
+        assert_eq!(blend_pixel(BlendMode::Clear, RED, WHITE), 0);
+        assert_eq!(blend_pixel(BlendMode::DstOver, RED, WHITE), WHITE);
+        assert_eq!(blend_pixel(BlendMode::SrcIn, RED, 0), 0);
+        assert_eq!(blend_pixel(BlendMode::SrcOver, HALF_BLUE, RED), 0xFF7F0080);
+    }
+
+    #[test]
+    fn test_separable_modes() {
+        assert_eq!(blend_pixel(BlendMode::Multiply, RED, WHITE), RED);
+        assert_eq!(blend_pixel(BlendMode::Screen, RED, WHITE), WHITE);
+        assert_eq!(blend_pixel(BlendMode::Difference, RED, WHITE), 0xFF00FFFF);
+        assert_eq!(blend_pixel(BlendMode::Darken, RED, WHITE), RED);
+    }
+
+    #[test]
+    fn test_non_separable_modes() {
+        // Luminosity of white onto red takes white's full luminosity, which
+        // clips red's hue all the way to white
+        let out = blend_pixel(BlendMode::Luminosity, WHITE, RED);
+        assert_eq!(out, WHITE);
+        // Gray has no saturation, so Saturation mode desaturates the destination
+        let gray = 0xFF808080;
+        let out = blend_pixel(BlendMode::Saturation, gray, RED);
+        let (r, g, b) = ((out >> 16) & 0xFF, (out >> 8) & 0xFF, out & 0xFF);
+        assert_eq!((r, g), (g, b));
+    }
+
+    #[test]
+    fn test_lerp_pixel() {
+        assert_eq!(lerp_pixel(0, RED, 0), 0);
+        assert_eq!(lerp_pixel(0, RED, 255), RED);
+        assert_eq!(lerp_pixel(0, RED, 128), 0x80800000);
+    }
+}
@@ -1,5 +1,7 @@
 //! CPU-based software rasterizer
 
+mod blend;
+mod shadow;
 mod surface;
 
 pub use surface::CpuSurface;
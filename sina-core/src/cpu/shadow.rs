@@ -0,0 +1,157 @@
+//! Shadow rasterization from a shape's coverage mask
+
+use crate::effects::BlurFilter;
+use crate::Color;
+use raqote::SolidSource;
+
+/// A shadow with its geometry resolved to device pixels
+pub(crate) struct DeviceShadow {
+    /// Offset in whole pixels
+    pub offset: (i32, i32),
+
+    /// Dilation (positive) or erosion (negative) radius in pixels
+    pub spread: i32,
+
+    pub blur: BlurFilter,
+    pub color: Color,
+    pub inset: bool,
+}
+
+/// Render a shadow as premultiplied ARGB pixels from a shape's coverage
+pub(crate) fn render_shadow(
+    coverage: &[u8],
+    width: usize,
+    height: usize,
+    shadow: &DeviceShadow,
+) -> Vec<u32> {
+    // Inner shadows are cast by everything outside the shape
+    let (mask, outside) = if shadow.inset {
+        (coverage.iter().map(|&c| 255 - c).collect(), 255)
+    } else {
+        (coverage.to_vec(), 0)
+    };
+
+    let mask = shift(&mask, width, height, shadow.offset, outside);
+    let mask = morphology(&mask, width, height, shadow.spread);
+
+    let color = SolidSource::from_unpremultiplied_argb(
+        shadow.color.a,
+        shadow.color.r,
+        shadow.color.g,
+        shadow.color.b,
+    )
+    .to_u32();
+    let mut pixels: Vec<u32> = mask.iter().map(|&m| scale_pixel(color, m)).collect();
+    shadow.blur.apply(&mut pixels, width, height);
+
+    if shadow.inset {
+        // Keep the shadow within the shape
+        for (pixel, &c) in pixels.iter_mut().zip(coverage) {
+            *pixel = scale_pixel(*pixel, c);
+        }
+    }
+
+    pixels
+}
+
+/// Scale a premultiplied ARGB pixel by a coverage value
+fn scale_pixel(pixel: u32, coverage: u8) -> u32 {
+    let mut out = 0;
+    for shift in [0, 8, 16, 24] {
+        let c = (pixel >> shift) & 0xFF;
+        out |= ((c * coverage as u32 + 127) / 255) << shift;
+    }
+    out
+}
+
+/// Move a mask by `offset`, filling uncovered pixels with `fill`
+fn shift(mask: &[u8], width: usize, height: usize, offset: (i32, i32), fill: u8) -> Vec<u8> {
+    let (dx, dy) = offset;
+    let mut out = vec![fill; mask.len()];
+    for y in 0..height as i32 {
+        let sy = y - dy;
+        if sy < 0 || sy >= height as i32 {
+            continue;
+        }
+        for x in 0..width as i32 {
+            let sx = x - dx;
+            if sx >= 0 && sx < width as i32 {
+                out[(y as usize) * width + x as usize] = mask[(sy as usize) * width + sx as usize];
+            }
+        }
+    }
+    out
+}
+
+/// Grow (positive radius) or shrink (negative radius) a mask by a square
+/// structuring element
+fn morphology(mask: &[u8], width: usize, height: usize, radius: i32) -> Vec<u8> {
+    if radius == 0 {
+        return mask.to_vec();
+    }
+
+    let r = radius.unsigned_abs() as usize;
+    let pick = |a: u8, b: u8| if radius > 0 { a.max(b) } else { a.min(b) };
+    let extreme = |values: &mut dyn Iterator<Item = u8>| values.reduce(pick).unwrap_or(0);
+
+    let mut rows = vec![0; mask.len()];
+    for y in 0..height {
+        let row = &mask[y * width..(y + 1) * width];
+        for x in 0..width {
+            let window = &row[x.saturating_sub(r)..(x + r + 1).min(width)];
+            rows[y * width + x] = extreme(&mut window.iter().copied());
+        }
+    }
+
+    let mut out = vec![0; mask.len()];
+    for x in 0..width {
+        for y in 0..height {
+            let window = y.saturating_sub(r)..(y + r + 1).min(height);
+            out[y * width + x] = extreme(&mut window.map(|wy| rows[wy * width + x]));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift_and_morphology() {
+        let mut mask = vec![0u8; 25];
+        mask[12] = 255;
+
+        let shifted = shift(&mask, 5, 5, (1, -1), 0);
+        assert_eq!(shifted[5 + 3], 255);
+        assert_eq!(shifted.iter().filter(|&&m| m > 0).count(), 1);
+
+        let grown = morphology(&mask, 5, 5, 1);
+        assert_eq!(grown.iter().filter(|&&m| m > 0).count(), 9);
+        assert_eq!(morphology(&grown, 5, 5, -1), mask);
+    }
+
+    #[test]
+    fn test_inner_shadow_stays_inside() {
+        // A 3x3 opaque square in the middle of a 7x7 area
+        let mut coverage = vec![0u8; 49];
+        for y in 2..5 {
+            for x in 2..5 {
+                coverage[y * 7 + x] = 255;
+            }
+        }
+        let shadow = DeviceShadow {
+            offset: (1, 0),
+            spread: 0,
+            blur: BlurFilter::uniform(0.0),
+            color: Color::BLACK,
+            inset: true,
+        };
+
+        let pixels = render_shadow(&coverage, 7, 7, &shadow);
+        // Only the left column of the square is uncovered by the shifted shape
+        assert_eq!(pixels[3 * 7 + 2], 0xFF000000);
+        assert_eq!(pixels[3 * 7 + 3], 0);
+        assert_eq!(pixels[3 * 7 + 1], 0);
+    }
+}
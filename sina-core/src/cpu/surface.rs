@@ -1,195 +1,501 @@
 //! CPU-based surface implementation using raqote
 
-use crate::{Canvas, Color, Paint, Path, Point, Rect, Surface};
-use raqote::{DrawOptions, DrawTarget, PathBuilder as RaqotePathBuilder, SolidSource, Source};
+use super::blend::{blend_pixel, lerp_pixel};
+use super::shadow::{render_shadow, DeviceShadow};
+use crate::effects::{BlurFilter, Gradient, GradientKind, ImageFilter, Shader, Shadow, TileMode};
+use crate::geometry::is_translate_only;
+use crate::image::{nine_patch_cells, FilterQuality, Image};
+use crate::path::{to_raqote_path, to_raqote_stroke};
+use crate::pixels::{check_access, PixelError, PixelFormat};
+use crate::{
+    BlendMode, Canvas, ClipOp, Color, IRect, Matrix3x3, Paint, Path, Point, Rect, Surface,
+};
+use raqote::{
+    AntialiasMode, DrawOptions, DrawTarget, ExtendMode, FilterMode, IntRect,
+    PathBuilder as RaqotePathBuilder, SolidSource, Source,
+};
+use std::sync::Arc;
+
+/// Device-space coverage mask accumulated from `clip_rect`/`clip_path` calls
+#[derive(Debug)]
+struct ClipMask {
+    /// Per-pixel coverage (0 = clipped out, 255 = fully visible)
+    coverage: Vec<u8>,
+
+    /// Bounding box of the non-zero coverage, as `(x0, y0, x1, y1)`
+    bounds: (i32, i32, i32, i32),
+}
+
+impl ClipMask {
+    fn is_empty(&self) -> bool {
+        self.bounds.0 >= self.bounds.2 || self.bounds.1 >= self.bounds.3
+    }
+}
+
+/// Drawing state captured by `save` and rolled back by `restore`
+#[derive(Debug, Clone)]
+struct DrawState {
+    /// Current transformation matrix
+    matrix: Matrix3x3,
+
+    /// Opacity multiplied into every draw (0.0-1.0)
+    global_alpha: f32,
+
+    /// Blend mode overriding the paint's own, if set
+    blend_mode: Option<BlendMode>,
+
+    /// Clip coverage, or `None` when nothing is clipped
+    clip: Option<Arc<ClipMask>>,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            matrix: Matrix3x3::IDENTITY,
+            global_alpha: 1.0,
+            blend_mode: None,
+            clip: None,
+        }
+    }
+}
 
 pub struct CpuSurface {
     draw_target: DrawTarget,
+    state: DrawState,
+    state_stack: Vec<DrawState>,
 }
 
 impl CpuSurface {
     pub fn new(width: i32, height: i32) -> Self {
         Self {
             draw_target: DrawTarget::new(width, height),
+            state: DrawState::default(),
+            state_stack: Vec::new(),
         }
     }
-}
 
-impl Surface for CpuSurface {
-    fn width(&self) -> u32 {
-        self.draw_target.width() as u32
+    /// Blur a device-space region of the surface in place
+    ///
+    /// The region is rounded out to whole pixels and clipped to the surface;
+    /// the canvas matrix and clip do not apply. Pixels outside the region are
+    /// sampled according to the filter's edge mode.
+    pub fn apply_blur(&mut self, region: Rect, blur: &BlurFilter) {
+        let x0 = (region.left().floor() as i32).max(0);
+        let y0 = (region.top().floor() as i32).max(0);
+        let x1 = (region.right().ceil() as i32).min(self.draw_target.width());
+        let y1 = (region.bottom().ceil() as i32).min(self.draw_target.height());
+        if x0 < x1 && y0 < y1 {
+            blur_pixels(&mut self.draw_target, (x0, y0, x1, y1), blur);
+        }
     }
 
-    fn height(&self) -> u32 {
-        self.draw_target.height() as u32
+    /// Copy the surface's pixels into an image
+    pub(crate) fn to_image(&self) -> Image {
+        let data = self.draw_target.get_data().to_vec();
+        Image::from_argb(self.width(), self.height(), data)
     }
 
-    fn canvas(&mut self) -> &mut dyn Canvas {
-        self
+    /// Push the current state's matrix down to raqote
+    fn sync_transform(&mut self) {
+        let transform = to_raqote_transform(&self.state.matrix);
+        self.draw_target.set_transform(&transform);
     }
 
-    fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.draw_target.write_png(path)?;
-        Ok(())
-    }
-}
+    /// Intersect or subtract a user-space path from the clip mask
+    fn apply_clip(&mut self, path: &raqote::Path, op: ClipOp, anti_alias: bool) {
+        let width = self.draw_target.width();
+        let height = self.draw_target.height();
 
-impl Canvas for CpuSurface {
-    fn clear(&mut self, color: Color) {
-        let source = SolidSource {
-            r: color.r,
-            g: color.g,
-            b: color.b,
-            a: color.a,
+        // Rasterize the clip shape's coverage under the current matrix
+        let mut scratch = DrawTarget::new(width, height);
+        scratch.set_transform(&to_raqote_transform(&self.state.matrix));
+        let options = DrawOptions {
+            antialias: if anti_alias {
+                AntialiasMode::Gray
+            } else {
+                AntialiasMode::None
+            },
+            ..Default::default()
         };
-        self.draw_target.clear(source);
-    }
+        scratch.fill(path, &solid_source(Color::WHITE), &options);
 
-    fn save(&mut self) {
-        // TODO: Implement state stack
-    }
+        let previous = self.state.clip.as_deref().map(|clip| &clip.coverage);
+        let mut coverage = Vec::with_capacity((width * height) as usize);
+        let mut bounds = (width, height, 0, 0);
 
-    fn restore(&mut self) {
-        // TODO: Implement state stack
+        for (i, pixel) in scratch.get_data().iter().enumerate() {
+            let shape = (pixel >> 24) as u16;
+            let shape = match op {
+                ClipOp::Intersect => shape,
+                ClipOp::Difference => 255 - shape,
+            };
+            let prev = previous.map_or(255, |coverage| coverage[i] as u16);
+            let value = ((shape * prev + 127) / 255) as u8;
+
+            if value > 0 {
+                let (x, y) = (i as i32 % width, i as i32 / width);
+                bounds.0 = bounds.0.min(x);
+                bounds.1 = bounds.1.min(y);
+                bounds.2 = bounds.2.max(x + 1);
+                bounds.3 = bounds.3.max(y + 1);
+            }
+            coverage.push(value);
+        }
+
+        self.state.clip = Some(Arc::new(ClipMask { coverage, bounds }));
     }
 
-    fn draw_path(&mut self, path: &Path, paint: &Paint) {
-        // Convert lyon path to raqote path
-        let mut raqote_path = RaqotePathBuilder::new();
+    /// Run a drawing operation through the clip mask
+    ///
+    /// The operation draws normally inside the clip's bounding box; the
+    /// touched region is then blended back towards its previous contents
+    /// by the clip coverage, so every blend mode is clipped correctly.
+    fn with_clip(&mut self, draw: impl FnOnce(&mut Self)) {
+        let Some(clip) = self.state.clip.clone() else {
+            draw(self);
+            return;
+        };
+        if clip.is_empty() {
+            return;
+        }
 
-        for event in path.lyon_path().iter() {
-            match event {
-                lyon_path::Event::Begin { at } => {
-                    raqote_path.move_to(at.x, at.y);
-                }
-                lyon_path::Event::Line { to, .. } => {
-                    raqote_path.line_to(to.x, to.y);
-                }
-                lyon_path::Event::Quadratic { ctrl, to, .. } => {
-                    raqote_path.quad_to(ctrl.x, ctrl.y, to.x, to.y);
-                }
-                lyon_path::Event::Cubic {
-                    ctrl1, ctrl2, to, ..
-                } => {
-                    raqote_path.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
-                }
-                lyon_path::Event::End { close, .. } => {
-                    if close {
-                        raqote_path.close();
-                    }
-                }
-            }
+        let width = self.draw_target.width() as usize;
+        let (x0, y0, x1, y1) = clip.bounds;
+        let (x0, x1) = (x0 as usize, x1 as usize);
+
+        let mut backup = Vec::with_capacity((x1 - x0) * (y1 - y0) as usize);
+        for y in y0 as usize..y1 as usize {
+            backup.extend_from_slice(&self.draw_target.get_data()[y * width + x0..y * width + x1]);
         }
 
-        let raqote_path = raqote_path.finish();
-        let source = Source::Solid(SolidSource {
-            r: paint.color.r,
-            g: paint.color.g,
-            b: paint.color.b,
-            a: paint.color.a,
-        });
+        self.draw_target
+            .push_clip_rect(IntRect::new((x0 as i32, y0).into(), (x1 as i32, y1).into()));
+        draw(self);
+        self.draw_target.pop_clip();
 
-        let draw_options = DrawOptions::default();
+        let data = self.draw_target.get_data_mut();
+        let mut saved = backup.iter();
+        for y in y0 as usize..y1 as usize {
+            for x in x0..x1 {
+                let i = y * width + x;
+                let before = *saved.next().unwrap();
+                data[i] = lerp_pixel(before, data[i], clip.coverage[i]);
+            }
+        }
+    }
 
-        if paint.is_fill() {
-            self.draw_target.fill(&raqote_path, &source, &draw_options);
-        } else if let Some(stroke) = &paint.stroke {
-            let stroke_style = raqote::StrokeStyle {
-                width: stroke.width,
-                ..Default::default()
-            };
-            self.draw_target
-                .stroke(&raqote_path, &source, &stroke_style, &draw_options);
+    /// Get the device-space rectangle drawing may touch, as `(x0, y0, x1, y1)`
+    fn device_bounds(&self) -> (i32, i32, i32, i32) {
+        match &self.state.clip {
+            Some(clip) => clip.bounds,
+            None => (0, 0, self.draw_target.width(), self.draw_target.height()),
         }
     }
 
-    fn draw_rect(&mut self, rect: Rect, paint: &Paint) {
-        let mut path = RaqotePathBuilder::new();
-        path.rect(rect.x, rect.y, rect.width, rect.height);
-        let raqote_path = path.finish();
+    /// Draw a shape with the paint's shadows and image filter
+    ///
+    /// `draw` renders the shape with the paint it is given; it runs once per
+    /// shadow to build the shape's coverage and once more for the shape itself.
+    fn draw_with_effects(&mut self, paint: &Paint, draw: impl Fn(&mut Self, &Paint)) {
+        if paint.shadows.is_empty() {
+            self.draw_filtered(paint, draw);
+            return;
+        }
 
-        let source = Source::Solid(SolidSource {
-            r: paint.color.r,
-            g: paint.color.g,
-            b: paint.color.b,
-            a: paint.color.a,
-        });
+        let mut shape_paint = paint.clone();
+        shape_paint.clear_shadows();
 
-        let draw_options = DrawOptions::default();
+        // Render the shape's coverage once for all shadows
+        let mut mask_paint = shape_paint.clone();
+        mask_paint.color.a = 255;
+        mask_paint.shader = None;
+        mask_paint.image_filter = None;
+        let mask = self.render_layer(&mask_paint, &draw);
 
-        if paint.is_fill() {
-            self.draw_target.fill(&raqote_path, &source, &draw_options);
-        } else if let Some(stroke) = &paint.stroke {
-            let stroke_style = raqote::StrokeStyle {
-                width: stroke.width,
-                ..Default::default()
-            };
-            self.draw_target
-                .stroke(&raqote_path, &source, &stroke_style, &draw_options);
+        for shadow in paint.shadows.iter().filter(|shadow| !shadow.inset) {
+            self.draw_shadow(&mask, shadow, paint);
+        }
+        self.draw_filtered(&shape_paint, &draw);
+        for shadow in paint.shadows.iter().filter(|shadow| shadow.inset) {
+            self.draw_shadow(&mask, shadow, paint);
         }
     }
 
-    fn draw_circle(&mut self, center: Point, radius: f32, paint: &Paint) {
-        let mut path = RaqotePathBuilder::new();
-        path.arc(center.x, center.y, radius, 0.0, 2.0 * std::f32::consts::PI);
-        let raqote_path = path.finish();
+    /// Rasterize and composite one shadow of a shape's coverage layer
+    fn draw_shadow(&mut self, mask: &DrawTarget, shadow: &Shadow, paint: &Paint) {
+        let Some((x0, y0, x1, y1)) = content_bounds(mask) else {
+            return;
+        };
+
+        // Shadow geometry is in user space, so map it through the matrix
+        let matrix = self.state.matrix;
+        let scale = (matrix.x_axis.truncate().length() + matrix.y_axis.truncate().length()) / 2.0;
+        let offset = matrix.transform_vector2(shadow.offset);
+        let blur = shadow.blur_filter();
+        let device_shadow = DeviceShadow {
+            offset: (offset.x.round() as i32, offset.y.round() as i32),
+            spread: (shadow.spread * scale).round() as i32,
+            blur: BlurFilter {
+                sigma_x: blur.sigma_x * scale,
+                sigma_y: blur.sigma_y * scale,
+                ..blur
+            },
+            color: shadow.color,
+            inset: shadow.inset,
+        };
+
+        // Work on the shape's bounds grown by everything that can move the shadow
+        let (extent_x, extent_y) = device_shadow.blur.extent();
+        let (dx, dy) = device_shadow.offset;
+        let spread = device_shadow.spread.max(0);
+        let margin_x = dx.abs() + spread + extent_x as i32 + 1;
+        let margin_y = dy.abs() + spread + extent_y as i32 + 1;
+        let region = (
+            (x0 - margin_x).max(0),
+            (y0 - margin_y).max(0),
+            (x1 + margin_x).min(mask.width()),
+            (y1 + margin_y).min(mask.height()),
+        );
+
+        let coverage: Vec<u8> = read_region(mask, region)
+            .iter()
+            .map(|pixel| (pixel >> 24) as u8)
+            .collect();
+        let (x0, y0, x1, y1) = region;
+        let pixels = render_shadow(
+            &coverage,
+            (x1 - x0) as usize,
+            (y1 - y0) as usize,
+            &device_shadow,
+        );
+        self.composite_layer(&pixels, region, paint.blend_mode);
+    }
+
+    /// Run a drawing operation through the paint's image filter, if any
+    ///
+    /// Filtered draws render into a transparent layer under the current
+    /// matrix; the filtered layer is then composited with the paint's blend
+    /// mode, the global alpha and the clip.
+    fn draw_filtered(&mut self, paint: &Paint, draw: impl FnOnce(&mut Self, &Paint)) {
+        let Some(ImageFilter::Blur(blur)) = paint.image_filter else {
+            draw(self, paint);
+            return;
+        };
+
+        let mut layer_paint = paint.clone();
+        layer_paint.image_filter = None;
+        let mut layer = self.render_layer(&layer_paint, draw);
+        let Some((x0, y0, x1, y1)) = content_bounds(&layer) else {
+            return;
+        };
+
+        // Sigmas are in user space, so scale them by the matrix
+        let matrix = self.state.matrix;
+        let blur = BlurFilter {
+            sigma_x: blur.sigma_x * matrix.x_axis.truncate().length(),
+            sigma_y: blur.sigma_y * matrix.y_axis.truncate().length(),
+            ..blur
+        };
+        let (extent_x, extent_y) = blur.extent();
+        let region = (
+            (x0 - extent_x as i32).max(0),
+            (y0 - extent_y as i32).max(0),
+            (x1 + extent_x as i32).min(layer.width()),
+            (y1 + extent_y as i32).min(layer.height()),
+        );
+        blur_pixels(&mut layer, region, &blur);
+
+        let pixels = read_region(&layer, region);
+        self.composite_layer(&pixels, region, paint.blend_mode);
+    }
 
-        let source = Source::Solid(SolidSource {
-            r: paint.color.r,
-            g: paint.color.g,
-            b: paint.color.b,
-            a: paint.color.a,
+    /// Draw into a fresh transparent layer under the current matrix
+    ///
+    /// The layer ignores the clip, global alpha and blend mode, which apply
+    /// when it is composited.
+    fn render_layer(&mut self, paint: &Paint, draw: impl FnOnce(&mut Self, &Paint)) -> DrawTarget {
+        let width = self.draw_target.width();
+        let height = self.draw_target.height();
+        let mut layer_paint = paint.clone();
+        layer_paint.blend_mode = BlendMode::SrcOver;
+
+        let state = self.state.clone();
+        self.state.global_alpha = 1.0;
+        self.state.blend_mode = None;
+        self.state.clip = None;
+        let target = std::mem::replace(&mut self.draw_target, DrawTarget::new(width, height));
+        self.sync_transform();
+        draw(self, &layer_paint);
+        let layer = std::mem::replace(&mut self.draw_target, target);
+        self.state = state;
+        layer
+    }
+
+    /// Composite device-space pixels covering `region` onto the surface
+    fn composite_layer(
+        &mut self,
+        pixels: &[u32],
+        region: (i32, i32, i32, i32),
+        blend_mode: BlendMode,
+    ) {
+        let (x0, y0, x1, y1) = region;
+        let image = raqote::Image {
+            width: x1 - x0,
+            height: y1 - y0,
+            data: pixels,
+        };
+        let options = DrawOptions {
+            blend_mode: to_raqote_blend(self.state.blend_mode.unwrap_or(blend_mode)),
+            alpha: self.state.global_alpha,
+            antialias: AntialiasMode::None,
+        };
+
+        self.with_clip(|this| {
+            this.draw_target
+                .set_transform(&raqote::Transform::identity());
+            this.draw_target
+                .draw_image_at(x0 as f32, y0 as f32, &image, &options);
+            this.sync_transform();
         });
+    }
 
-        let draw_options = DrawOptions::default();
+    /// Build raqote draw options from a paint and the current state
+    fn draw_options(&self, paint: &Paint) -> DrawOptions {
+        // Shaded paints use the color's alpha as an overall opacity
+        let shader_alpha = match paint.shader {
+            Some(_) => paint.color.a as f32 / 255.0,
+            None => 1.0,
+        };
 
-        if paint.is_fill() {
-            self.draw_target.fill(&raqote_path, &source, &draw_options);
-        } else if let Some(stroke) = &paint.stroke {
-            let stroke_style = raqote::StrokeStyle {
-                width: stroke.width,
-                ..Default::default()
-            };
-            self.draw_target
-                .stroke(&raqote_path, &source, &stroke_style, &draw_options);
+        DrawOptions {
+            blend_mode: to_raqote_blend(self.state.blend_mode.unwrap_or(paint.blend_mode)),
+            alpha: self.state.global_alpha * shader_alpha,
+            antialias: if paint.anti_alias {
+                AntialiasMode::Gray
+            } else {
+                AntialiasMode::None
+            },
         }
     }
 
-    fn draw_line(&mut self, from: Point, to: Point, paint: &Paint) {
-        let mut path = RaqotePathBuilder::new();
-        path.move_to(from.x, from.y);
-        path.line_to(to.x, to.y);
-        let raqote_path = path.finish();
+    /// Build draw options for an image, which takes its opacity from the paint color
+    fn image_options(&self, paint: &Paint) -> DrawOptions {
+        DrawOptions {
+            alpha: self.state.global_alpha * paint.color.a as f32 / 255.0,
+            ..self.draw_options(paint)
+        }
+    }
 
-        let source = Source::Solid(SolidSource {
-            r: paint.color.r,
-            g: paint.color.g,
-            b: paint.color.b,
-            a: paint.color.a,
-        });
+    /// Fill `dst` with the `src` part of an image, ignoring the clip mask
+    fn fill_image_rect(
+        &mut self,
+        image: &Image,
+        src: Rect,
+        dst: Rect,
+        quality: FilterQuality,
+        options: &DrawOptions,
+    ) {
+        if src.is_empty() || dst.is_empty() {
+            return;
+        }
+        let Some(clipped) = src.intersect(&image.bounds()) else {
+            return;
+        };
 
-        let stroke_style = raqote::StrokeStyle {
-            width: paint.stroke.as_ref().map(|s| s.width).unwrap_or(1.0),
-            ..Default::default()
+        // Only draw where `src` overlaps the image
+        let (scale_x, scale_y) = (dst.width / src.width, dst.height / src.height);
+        let dst = Rect::new(
+            dst.x + (clipped.x - src.x) * scale_x,
+            dst.y + (clipped.y - src.y) * scale_y,
+            clipped.width * scale_x,
+            clipped.height * scale_y,
+        );
+
+        // Copy the whole pixels under `src`; padding at their edges keeps
+        // sampling from reaching outside it
+        let x0 = clipped.left().floor() as u32;
+        let y0 = clipped.top().floor() as u32;
+        let x1 = (clipped.right().ceil() as u32).min(image.width());
+        let y1 = (clipped.bottom().ceil() as u32).min(image.height());
+        let Some(cropped) = image.subset(IRect::new(x0, y0, x1 - x0, y1 - y0)) else {
+            return;
+        };
+
+        let matrix = self.state.matrix;
+        let device_x = scale_x * matrix.x_axis.truncate().length();
+        let device_y = scale_y * matrix.y_axis.truncate().length();
+        let (sampled, filter) = match quality {
+            FilterQuality::Nearest => (cropped, FilterMode::Nearest),
+            FilterQuality::Bilinear => (cropped, FilterMode::Bilinear),
+            FilterQuality::Bicubic => {
+                // Resample to the device size, leaving bilinear to place it
+                let width = (cropped.width() as f32 * device_x).round().max(1.0);
+                let height = (cropped.height() as f32 * device_y).round().max(1.0);
+                // Images scaled far past the surface are mostly off it, so
+                // they are filtered bilinearly rather than resampled whole
+                let surface_area =
+                    self.draw_target.width() as f32 * self.draw_target.height() as f32;
+                let resized = if width * height > 4.0 * surface_area
+                    || (width as u32, height as u32) == (cropped.width(), cropped.height())
+                {
+                    cropped
+                } else {
+                    cropped.resize_bicubic(width as u32, height as u32)
+                };
+                (resized, FilterMode::Bilinear)
+            }
+            FilterQuality::Mipmap => {
+                // Use the smallest level that is still at least the device size
+                let mut level = cropped;
+                let mut scale = device_x.max(device_y);
+                while scale < 0.5 && (level.width() > 1 || level.height() > 1) {
+                    level = level.downsample();
+                    scale *= 2.0;
+                }
+                (level, FilterMode::Bilinear)
+            }
         };
 
-        let draw_options = DrawOptions::default();
+        // Map user space onto the sampled pixels
+        let transform = raqote::Transform::translation(-dst.x, -dst.y)
+            .then_scale(1.0 / scale_x, 1.0 / scale_y)
+            .then_translate(raqote::Vector::new(
+                clipped.x - x0 as f32,
+                clipped.y - y0 as f32,
+            ))
+            .then_scale(
+                sampled.width() as f32 / (x1 - x0) as f32,
+                sampled.height() as f32 / (y1 - y0) as f32,
+            );
+        let source = Source::Image(
+            raqote::Image {
+                width: sampled.width() as i32,
+                height: sampled.height() as i32,
+                data: sampled.argb(),
+            },
+            ExtendMode::Pad,
+            filter,
+            transform,
+        );
         self.draw_target
-            .stroke(&raqote_path, &source, &stroke_style, &draw_options);
+            .fill_rect(dst.x, dst.y, dst.width, dst.height, &source, options);
     }
 
-    fn draw_text(
+    /// Lay out and blit text, ignoring the clip mask
+    fn render_text(
         &mut self,
         text: &str,
         position: Point,
-        font: &crate::text::Font,
+        fonts: &crate::text::FontCollection,
         font_size: f32,
+        style: &crate::text::TextStyle,
         paint: &Paint,
     ) {
         use crate::text::{GlyphCache, TextAlign, TextLayout};
 
         // Create layout engine
-        let layout = TextLayout::new(font.clone(), font_size);
+        let mut layout = TextLayout::with_fonts(fonts.clone(), font_size);
+        layout.set_style(style.clone());
 
         // Layout the text
         let positioned_glyphs = layout.layout(text, position, TextAlign::Left);
@@ -197,19 +503,36 @@ impl Canvas for CpuSurface {
             // println!("DEBUG: Layout '{}' -> {} glyphs", text, positioned_glyphs.len());
         }
 
-        // Create glyph cache
-        let mut glyph_cache = GlyphCache::default();
+        // Create a glyph cache per font, as cached glyphs are keyed by index
+        let mut glyph_caches: Vec<GlyphCache> = fonts
+            .fonts()
+            .iter()
+            .map(|_| GlyphCache::default())
+            .collect();
 
         // Get the paint color and target dimensions
         let color = paint.color;
+        let draw_options = self.draw_options(paint);
+        // Paint opacity with the canvas' global alpha folded in
+        let paint_alpha = (color.a as f32 * draw_options.alpha).round() as u16;
         let target_width = self.draw_target.width();
-        let target_height = self.draw_target.height();
+        let (min_x, min_y, max_x, max_y) = self.device_bounds();
+        let matrix = self.state.matrix;
+        let translate_only = is_translate_only(&matrix);
 
         // Render each glyph
         for (glyph_pos, shaped_glyph) in positioned_glyphs {
+            let font = &fonts.fonts()[shaped_glyph.font_index];
+
             // Rasterize glyph using its index (important for ligatures/complex scripts)
-            let rasterized_opt =
-                glyph_cache.get_or_rasterize_indexed(font, shaped_glyph.glyph_index, font_size);
+            // Color glyphs draw their foreground layers in the opaque paint
+            // color, as the paint's opacity is applied to the whole bitmap
+            let rasterized_opt = glyph_caches[shaped_glyph.font_index].get_or_rasterize_with_color(
+                font,
+                shaped_glyph.glyph_index,
+                font_size,
+                Color::rgb(color.r, color.g, color.b),
+            );
 
             if rasterized_opt.is_none() {
                 if cfg!(debug_assertions) {
@@ -226,9 +549,9 @@ impl Canvas for CpuSurface {
                 continue;
             }
 
-            // Calculate position with bearings
-            let x = (glyph_pos.x + rasterized.bearing_x).round() as i32;
-            let y = (glyph_pos.y - rasterized.bearing_y - rasterized.height as f32).round() as i32;
+            // Calculate position with bearings (in user space)
+            let x = (glyph_pos.x + rasterized.bearing_x).round();
+            let y = (glyph_pos.y - rasterized.bearing_y - rasterized.height as f32).round();
 
             if cfg!(debug_assertions)
                 && text.starts_with("Hello")
@@ -237,14 +560,6 @@ impl Canvas for CpuSurface {
                 // println!("DEBUG: DrawGlyph {} at ({},{}) size={}x{} fmt={:?}", shaped_glyph.glyph_index, x, y, rasterized.width, rasterized.height, rasterized.format);
             }
 
-            // Skip if completely off screen
-            if x + (rasterized.width as i32) < 0 || y + (rasterized.height as i32) < 0 {
-                continue;
-            }
-            if x >= target_width || y >= target_height {
-                continue;
-            }
-
             // For RGBA glyphs (Emojis), use raqote's native image drawing for correct blending
             if rasterized.format == crate::text::GlyphFormat::Rgba {
                 let mut argb_pixels = Vec::with_capacity(rasterized.width * rasterized.height);
@@ -276,23 +591,49 @@ impl Canvas for CpuSurface {
                     data: &argb_pixels,
                 };
 
-                self.draw_target
-                    .draw_image_at(x as f32, y as f32, &image, &DrawOptions::default());
+                // raqote applies the current matrix to the image
+                self.draw_target.draw_image_at(x, y, &image, &draw_options);
                 continue;
             } else if rasterized.format == crate::text::GlyphFormat::Alpha {
+                // A transformed alpha mask would be blurry or skewed, and the blitter
+                // only handles solid colors, so fill the glyph outline instead
+                if !translate_only || paint.shader.is_some() {
+                    if let Some(outline) = font.glyph_outline(shaped_glyph.glyph_index, font_size) {
+                        self.fill_glyph_outline(&outline, glyph_pos, paint);
+                    }
+                    continue;
+                }
+
+                // Move to device space
+                let x = (x + matrix.z_axis.x).round() as i32;
+                let y = (y + matrix.z_axis.y).round() as i32;
+
+                // Skip if completely off screen (or outside the clip)
+                if x + (rasterized.width as i32) < min_x || y + (rasterized.height as i32) < min_y {
+                    continue;
+                }
+                if x >= max_x || y >= max_y {
+                    continue;
+                }
+
                 // Manual optimized path for standard text to ensure reliability
                 // This avoids Raqote image creation overhead and potential stride bugs for simple masks
 
                 let target_data = self.draw_target.get_data_mut();
                 let target_width_usize = target_width as usize;
 
-                let src_r = color.r;
-                let src_g = color.g;
-                let src_b = color.b;
+                let src_pixel = SolidSource::from_unpremultiplied_argb(
+                    paint_alpha as u8,
+                    color.r,
+                    color.g,
+                    color.b,
+                )
+                .to_u32();
+                let blend_mode = self.state.blend_mode.unwrap_or(paint.blend_mode);
 
                 for gy in 0..rasterized.height {
                     let target_y = y + (gy as i32);
-                    if target_y < 0 || target_y >= target_height {
+                    if target_y < min_y || target_y >= max_y {
                         continue;
                     }
 
@@ -301,7 +642,7 @@ impl Canvas for CpuSurface {
 
                     for gx in 0..rasterized.width {
                         let target_x = x + (gx as i32);
-                        if target_x < 0 || target_x >= target_width {
+                        if target_x < min_x || target_x >= max_x {
                             continue;
                         }
 
@@ -310,52 +651,973 @@ impl Canvas for CpuSurface {
                             continue;
                         }
 
-                        // Blend paint alpha with glyph alpha
-                        let src_a = ((glyph_alpha as u16 * color.a as u16) / 255) as u8;
-                        if src_a == 0 {
-                            continue;
-                        }
-
                         // Calculate target pixel index
                         let target_idx = row_start_idx + (target_x as usize);
 
-                        // DEBUG: Log first pixel of first glyph
-                        // if gy == rasterized.height / 2 && gx == rasterized.width / 2 && x < 100 {
-                        //    println!("DEBUG: AlphaPixel at ({},{}) src_a={} color={:?}", target_x, target_y, src_a, color);
-                        // }
-
-                        // Get existing pixel (ARGB format in raqote)
+                        // Composite the paint color through the glyph coverage
                         let existing = target_data[target_idx];
-                        let dst_a = ((existing >> 24) & 0xFF) as u8;
-                        let dst_r = ((existing >> 16) & 0xFF) as u8;
-                        let dst_g = ((existing >> 8) & 0xFF) as u8;
-                        let dst_b = (existing & 0xFF) as u8;
-
-                        // Blend using standard alpha compositing
-                        // dst' = src * alpha + dst * (1 - alpha)
-                        let inv_alpha = 255 - src_a;
-
-                        let out_r = ((src_r as u16 * src_a as u16
-                            + dst_r as u16 * inv_alpha as u16)
-                            / 255) as u8;
-                        let out_g = ((src_g as u16 * src_a as u16
-                            + dst_g as u16 * inv_alpha as u16)
-                            / 255) as u8;
-                        let out_b = ((src_b as u16 * src_a as u16
-                            + dst_b as u16 * inv_alpha as u16)
-                            / 255) as u8;
-                        let out_a = dst_a.saturating_add(src_a)
-                            - ((dst_a as u16 * src_a as u16) / 255) as u8;
-
-                        // Write back in ARGB format
-                        target_data[target_idx] = ((out_a as u32) << 24)
-                            | ((out_r as u32) << 16)
-                            | ((out_g as u32) << 8)
-                            | (out_b as u32);
+                        let blended = blend_pixel(blend_mode, src_pixel, existing);
+                        target_data[target_idx] = lerp_pixel(existing, blended, glyph_alpha);
                     }
                 }
                 continue;
             }
         }
     }
+
+    /// Fill a glyph outline placed at `origin` under the current matrix
+    fn fill_glyph_outline(&mut self, outline: &Path, origin: Point, paint: &Paint) {
+        let raqote_path = to_raqote_path(outline);
+        let glyph_transform = self.state.matrix * Matrix3x3::from_translation(origin);
+        self.draw_target
+            .set_transform(&to_raqote_transform(&glyph_transform));
+        self.draw_target.fill(
+            &raqote_path,
+            &paint_source(paint),
+            &self.draw_options(paint),
+        );
+        self.sync_transform();
+    }
+
+    /// Fill or stroke a raqote path according to the paint
+    fn paint_path(&mut self, path: &raqote::Path, paint: &Paint) {
+        self.draw_with_effects(paint, |this, paint| {
+            let source = paint_source(paint);
+            let draw_options = this.draw_options(paint);
+
+            this.with_clip(|this| {
+                if paint.is_fill() {
+                    this.draw_target.fill(path, &source, &draw_options);
+                } else if let Some(stroke) = &paint.stroke {
+                    let stroke_style = to_raqote_stroke(stroke);
+                    this.draw_target
+                        .stroke(path, &source, &stroke_style, &draw_options);
+                }
+            });
+        });
+    }
+}
+
+/// Copy the pixels of a device-space region, as `(x0, y0, x1, y1)`
+fn read_region(target: &DrawTarget, region: (i32, i32, i32, i32)) -> Vec<u32> {
+    let (x0, y0, x1, y1) = region;
+    let width = target.width() as usize;
+    let data = target.get_data();
+    (y0 as usize..y1 as usize)
+        .flat_map(|y| &data[y * width + x0 as usize..y * width + x1 as usize])
+        .copied()
+        .collect()
+}
+
+/// Blur a device-space region of a draw target in place
+fn blur_pixels(target: &mut DrawTarget, region: (i32, i32, i32, i32), blur: &BlurFilter) {
+    if blur.is_noop() {
+        return;
+    }
+
+    let (x0, y0, x1, y1) = region;
+    let region_width = (x1 - x0) as usize;
+    let mut pixels = read_region(target, region);
+    blur.apply(&mut pixels, region_width, (y1 - y0) as usize);
+
+    let width = target.width() as usize;
+    let data = target.get_data_mut();
+    for (row, y) in pixels.chunks(region_width).zip(y0 as usize..) {
+        data[y * width + x0 as usize..y * width + x1 as usize].copy_from_slice(row);
+    }
+}
+
+/// Get the bounding box of a draw target's non-transparent pixels
+fn content_bounds(target: &DrawTarget) -> Option<(i32, i32, i32, i32)> {
+    let width = target.width();
+    let mut bounds: Option<(i32, i32, i32, i32)> = None;
+
+    for (i, _) in target
+        .get_data()
+        .iter()
+        .enumerate()
+        .filter(|(_, &pixel)| pixel != 0)
+    {
+        let (x, y) = (i as i32 % width, i as i32 / width);
+        let (x0, y0, x1, y1) = bounds.unwrap_or((x, y, x + 1, y + 1));
+        bounds = Some((x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)));
+    }
+
+    bounds
+}
+
+/// Convert an affine matrix to a raqote transform
+fn to_raqote_transform(m: &Matrix3x3) -> raqote::Transform {
+    raqote::Transform::new(
+        m.x_axis.x, m.x_axis.y, m.y_axis.x, m.y_axis.y, m.z_axis.x, m.z_axis.y,
+    )
+}
+
+/// Convert a color to a raqote solid source
+fn solid_source(color: Color) -> Source<'static> {
+    Source::Solid(premultiplied(color))
+}
+
+/// Get the raqote source for a paint's shader or color
+fn paint_source(paint: &Paint) -> Source<'static> {
+    match &paint.shader {
+        Some(Shader::Gradient(gradient)) => gradient_source(gradient),
+        None => solid_source(paint.color),
+    }
+}
+
+/// Convert a gradient to a raqote gradient source
+fn gradient_source(gradient: &Gradient) -> Source<'static> {
+    let stops = gradient.normalized_stops();
+    match stops.as_slice() {
+        [] => return solid_source(Color::TRANSPARENT),
+        [stop] => return solid_source(stop.color),
+        _ => {}
+    }
+
+    // raqote sources map user space into gradient space
+    let local = gradient.local_matrix;
+    if local.determinant() == 0.0 {
+        return solid_source(Color::TRANSPARENT);
+    }
+    let to_local = to_raqote_transform(&local.inverse());
+
+    let raqote_gradient = raqote::Gradient {
+        stops: stops
+            .iter()
+            .map(|stop| raqote::GradientStop {
+                position: stop.offset,
+                color: raqote::Color::new(stop.color.a, stop.color.r, stop.color.g, stop.color.b),
+            })
+            .collect(),
+    };
+    let spread = match gradient.tile_mode {
+        TileMode::Pad => raqote::Spread::Pad,
+        TileMode::Repeat => raqote::Spread::Repeat,
+        TileMode::Mirror => raqote::Spread::Reflect,
+    };
+    let point = |p: Point| raqote::Point::new(p.x, p.y);
+
+    let source = match gradient.kind {
+        GradientKind::Linear { start, end } => {
+            Source::new_linear_gradient(raqote_gradient, point(start), point(end), spread)
+        }
+        GradientKind::Radial { center, radius } => {
+            if radius <= 0.0 {
+                return solid_source(Color::TRANSPARENT);
+            }
+            Source::new_radial_gradient(raqote_gradient, point(center), radius, spread)
+        }
+        GradientKind::TwoPointConical {
+            start,
+            start_radius,
+            end,
+            end_radius,
+        } => Source::new_two_circle_radial_gradient(
+            raqote_gradient,
+            point(start),
+            start_radius,
+            point(end),
+            end_radius,
+            spread,
+        ),
+        GradientKind::Sweep {
+            center,
+            start_angle,
+            end_angle,
+        } => Source::new_sweep_gradient(
+            raqote_gradient,
+            point(center),
+            start_angle,
+            end_angle,
+            spread,
+        ),
+    };
+
+    match source {
+        Source::LinearGradient(g, spread, t) => {
+            Source::LinearGradient(g, spread, to_local.then(&t))
+        }
+        Source::RadialGradient(g, spread, t) => {
+            Source::RadialGradient(g, spread, to_local.then(&t))
+        }
+        Source::TwoCircleRadialGradient(g, spread, c1, r1, c2, r2, t) => {
+            Source::TwoCircleRadialGradient(g, spread, c1, r1, c2, r2, to_local.then(&t))
+        }
+        Source::SweepGradient(g, spread, a0, a1, t) => {
+            Source::SweepGradient(g, spread, a0, a1, to_local.then(&t))
+        }
+        other => other,
+    }
+}
+
+/// Convert a color to raqote's premultiplied representation
+fn premultiplied(color: Color) -> SolidSource {
+    SolidSource::from_unpremultiplied_argb(color.a, color.r, color.g, color.b)
+}
+
+/// Map a blend mode onto raqote's compositing operators
+fn to_raqote_blend(mode: BlendMode) -> raqote::BlendMode {
+    match mode {
+        BlendMode::SrcOver => raqote::BlendMode::SrcOver,
+        BlendMode::Src => raqote::BlendMode::Src,
+        BlendMode::Dst => raqote::BlendMode::Dst,
+        BlendMode::Clear => raqote::BlendMode::Clear,
+        BlendMode::DstOver => raqote::BlendMode::DstOver,
+        BlendMode::SrcIn => raqote::BlendMode::SrcIn,
+        BlendMode::DstIn => raqote::BlendMode::DstIn,
+        BlendMode::SrcOut => raqote::BlendMode::SrcOut,
+        BlendMode::DstOut => raqote::BlendMode::DstOut,
+        BlendMode::SrcAtop => raqote::BlendMode::SrcAtop,
+        BlendMode::DstAtop => raqote::BlendMode::DstAtop,
+        BlendMode::Xor => raqote::BlendMode::Xor,
+        BlendMode::Plus => raqote::BlendMode::Add,
+        BlendMode::Multiply => raqote::BlendMode::Multiply,
+        BlendMode::Screen => raqote::BlendMode::Screen,
+        BlendMode::Overlay => raqote::BlendMode::Overlay,
+        BlendMode::Darken => raqote::BlendMode::Darken,
+        BlendMode::Lighten => raqote::BlendMode::Lighten,
+        BlendMode::ColorDodge => raqote::BlendMode::ColorDodge,
+        BlendMode::ColorBurn => raqote::BlendMode::ColorBurn,
+        BlendMode::HardLight => raqote::BlendMode::HardLight,
+        BlendMode::SoftLight => raqote::BlendMode::SoftLight,
+        BlendMode::Difference => raqote::BlendMode::Difference,
+        BlendMode::Exclusion => raqote::BlendMode::Exclusion,
+        BlendMode::Hue => raqote::BlendMode::Hue,
+        BlendMode::Saturation => raqote::BlendMode::Saturation,
+        BlendMode::Color => raqote::BlendMode::Color,
+        BlendMode::Luminosity => raqote::BlendMode::Luminosity,
+    }
+}
+
+impl Surface for CpuSurface {
+    fn width(&self) -> u32 {
+        self.draw_target.width() as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.draw_target.height() as u32
+    }
+
+    fn canvas(&mut self) -> &mut dyn Canvas {
+        self
+    }
+
+    fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.draw_target.write_png(path)?;
+        Ok(())
+    }
+
+    fn read_pixels(
+        &self,
+        rect: IRect,
+        format: PixelFormat,
+        dst: &mut [u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError> {
+        check_access(rect, self.width(), self.height(), dst.len(), row_bytes)?;
+        if rect.is_empty() {
+            return Ok(());
+        }
+
+        let width = self.width() as usize;
+        let data = self.draw_target.get_data();
+        for (row, y) in dst.chunks_mut(row_bytes).zip(rect.y..rect.y + rect.height) {
+            let start = y as usize * width + rect.x as usize;
+            let source = &data[start..start + rect.width as usize];
+            for (out, &pixel) in row.chunks_exact_mut(4).zip(source) {
+                let [a, r, g, b] = pixel.to_be_bytes();
+                out.copy_from_slice(&format.encode([r, g, b, a]));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_pixels(
+        &mut self,
+        rect: IRect,
+        format: PixelFormat,
+        src: &[u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError> {
+        check_access(rect, self.width(), self.height(), src.len(), row_bytes)?;
+        if rect.is_empty() {
+            return Ok(());
+        }
+
+        let width = self.width() as usize;
+        let data = self.draw_target.get_data_mut();
+        for (row, y) in src.chunks(row_bytes).zip(rect.y..rect.y + rect.height) {
+            let start = y as usize * width + rect.x as usize;
+            let target = &mut data[start..start + rect.width as usize];
+            for (pixel, input) in target.iter_mut().zip(row.chunks_exact(4)) {
+                let [r, g, b, a] = format.decode(input.try_into().unwrap());
+                *pixel = u32::from_be_bytes([a, r, g, b]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Canvas for CpuSurface {
+    fn clear(&mut self, color: Color) {
+        let source = premultiplied(color);
+        self.with_clip(|this| this.draw_target.clear(source));
+    }
+
+    fn save(&mut self) {
+        self.state_stack.push(self.state.clone());
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.state_stack.pop() {
+            self.state = state;
+            self.sync_transform();
+        }
+    }
+
+    fn save_count(&self) -> usize {
+        self.state_stack.len() + 1
+    }
+
+    fn restore_to_count(&mut self, count: usize) {
+        while self.save_count() > count.max(1) {
+            self.restore();
+        }
+    }
+
+    fn set_global_alpha(&mut self, alpha: f32) {
+        self.state.global_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    fn global_alpha(&self) -> f32 {
+        self.state.global_alpha
+    }
+
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.state.blend_mode = mode;
+    }
+
+    fn concat(&mut self, matrix: Matrix3x3) {
+        self.state.matrix *= matrix;
+        self.sync_transform();
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix3x3) {
+        self.state.matrix = matrix;
+        self.sync_transform();
+    }
+
+    fn get_matrix(&self) -> Matrix3x3 {
+        self.state.matrix
+    }
+
+    fn clip_rect(&mut self, rect: Rect, op: ClipOp, anti_alias: bool) {
+        let mut path = RaqotePathBuilder::new();
+        path.rect(rect.x, rect.y, rect.width, rect.height);
+        self.apply_clip(&path.finish(), op, anti_alias);
+    }
+
+    fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) {
+        self.apply_clip(&to_raqote_path(path), op, anti_alias);
+    }
+
+    fn draw_path(&mut self, path: &Path, paint: &Paint) {
+        let raqote_path = match &paint.path_effect {
+            Some(effect) => to_raqote_path(&effect.apply(path)),
+            None => to_raqote_path(path),
+        };
+        self.paint_path(&raqote_path, paint);
+    }
+
+    fn draw_rect(&mut self, rect: Rect, paint: &Paint) {
+        if paint.path_effect.is_some() {
+            return self.draw_path(&Path::builder().add_rect(rect).build(), paint);
+        }
+        let mut path = RaqotePathBuilder::new();
+        path.rect(rect.x, rect.y, rect.width, rect.height);
+        let raqote_path = path.finish();
+        self.paint_path(&raqote_path, paint);
+    }
+
+    fn draw_circle(&mut self, center: Point, radius: f32, paint: &Paint) {
+        if paint.path_effect.is_some() {
+            let circle = Path::builder().add_circle(center, radius).build();
+            return self.draw_path(&circle, paint);
+        }
+        let mut path = RaqotePathBuilder::new();
+        path.arc(center.x, center.y, radius, 0.0, 2.0 * std::f32::consts::PI);
+        let raqote_path = path.finish();
+        self.paint_path(&raqote_path, paint);
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, paint: &Paint) {
+        let raqote_path = match &paint.path_effect {
+            Some(effect) => {
+                let line = Path::builder().move_to(from).line_to(to).build();
+                to_raqote_path(&effect.apply(&line))
+            }
+            None => {
+                let mut path = RaqotePathBuilder::new();
+                path.move_to(from.x, from.y);
+                path.line_to(to.x, to.y);
+                path.finish()
+            }
+        };
+
+        self.draw_with_effects(paint, |this, paint| {
+            let source = paint_source(paint);
+
+            // Lines are always stroked; fill paints get a default 1px stroke
+            let stroke_style = to_raqote_stroke(&paint.stroke.clone().unwrap_or_default());
+
+            let draw_options = this.draw_options(paint);
+            this.with_clip(|this| {
+                this.draw_target
+                    .stroke(&raqote_path, &source, &stroke_style, &draw_options)
+            });
+        });
+    }
+
+    fn draw_text_styled(
+        &mut self,
+        text: &str,
+        position: Point,
+        fonts: &crate::text::FontCollection,
+        font_size: f32,
+        style: &crate::text::TextStyle,
+        paint: &Paint,
+    ) {
+        self.draw_with_effects(paint, |this, paint| {
+            this.with_clip(|this| this.render_text(text, position, fonts, font_size, style, paint))
+        });
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: Rect,
+        dst: Rect,
+        quality: FilterQuality,
+        paint: &Paint,
+    ) {
+        self.draw_with_effects(paint, |this, paint| {
+            let options = this.image_options(paint);
+            this.with_clip(|this| this.fill_image_rect(image, src, dst, quality, &options));
+        });
+    }
+
+    fn draw_image_nine(
+        &mut self,
+        image: &Image,
+        center: IRect,
+        dst: Rect,
+        quality: FilterQuality,
+        paint: &Paint,
+    ) {
+        let cells = nine_patch_cells(image, center, dst);
+        self.draw_with_effects(paint, |this, paint| {
+            // Cells share edges, which anti-aliasing would leave as faint seams
+            let options = DrawOptions {
+                antialias: AntialiasMode::None,
+                ..this.image_options(paint)
+            };
+            this.with_clip(|this| {
+                for &(src, dst) in &cells {
+                    this.fill_image_rect(image, src, dst, quality, &options);
+                }
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paint::LineCap;
+    use crate::{EdgeMode, FillRule, GradientStop, PathEffect, PointMode, RRect, StrokeStyle};
+
+    #[test]
+    fn test_save_restore_state() {
+        let mut surface = CpuSurface::new(10, 10);
+        surface.set_global_alpha(0.5);
+        surface.save();
+        surface.set_global_alpha(0.25);
+        surface.set_blend_mode(Some(BlendMode::Multiply));
+        assert_eq!(surface.save_count(), 2);
+
+        surface.restore();
+        assert_eq!(surface.save_count(), 1);
+        assert_eq!(surface.global_alpha(), 0.5);
+        assert_eq!(surface.state.blend_mode, None);
+
+        // Unbalanced restore is a no-op
+        surface.restore();
+        assert_eq!(surface.global_alpha(), 0.5);
+    }
+
+    fn pixel(surface: &CpuSurface, x: i32, y: i32) -> u32 {
+        surface.draw_target.get_data()[(y * surface.draw_target.width() + x) as usize]
+    }
+
+    #[test]
+    fn test_transform_applies_to_draws() {
+        let mut surface = CpuSurface::new(20, 20);
+        let paint = Paint::with_color(Color::RED);
+
+        surface.save();
+        surface.translate(10.0, 10.0);
+        surface.draw_rect(Rect::new(0.0, 0.0, 5.0, 5.0), &paint);
+        surface.restore();
+        assert_eq!(surface.get_matrix(), Matrix3x3::IDENTITY);
+
+        assert_eq!(pixel(&surface, 12, 12), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 2, 2), 0);
+
+        // A quarter turn maps +x onto +y
+        surface.rotate(90.0);
+        surface.draw_rect(Rect::new(0.0, -4.0, 20.0, 4.0), &paint);
+        assert_eq!(pixel(&surface, 1, 15), 0xFFFF0000);
+    }
+
+    #[test]
+    fn test_clip_rect_intersect_and_difference() {
+        let mut surface = CpuSurface::new(20, 20);
+        let paint = Paint::with_color(Color::RED);
+        let full = Rect::new(0.0, 0.0, 20.0, 20.0);
+
+        surface.save();
+        surface.clip_rect(Rect::new(5.0, 5.0, 10.0, 10.0), ClipOp::Intersect, false);
+        surface.clip_rect(Rect::new(8.0, 8.0, 2.0, 2.0), ClipOp::Difference, false);
+        surface.draw_rect(full, &paint);
+        surface.restore();
+
+        assert_eq!(pixel(&surface, 6, 6), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 2, 2), 0);
+        assert_eq!(pixel(&surface, 8, 8), 0);
+
+        // Restoring drops the clip
+        surface.draw_rect(full, &Paint::with_color(Color::BLUE));
+        assert_eq!(pixel(&surface, 2, 2), 0xFF0000FF);
+    }
+
+    #[test]
+    fn test_clip_path_anti_aliased() {
+        let mut surface = CpuSurface::new(20, 20);
+        let triangle = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(20.0, 0.0))
+            .line_to(Point::new(0.0, 20.0))
+            .close()
+            .build();
+
+        surface.clip_path(&triangle, ClipOp::Intersect, true);
+        surface.clear(Color::BLACK);
+
+        assert_eq!(pixel(&surface, 2, 2), 0xFF000000);
+        assert_eq!(pixel(&surface, 18, 18), 0);
+        // Pixels on the diagonal are partially covered
+        let edge = pixel(&surface, 10, 9) >> 24;
+        assert!(edge > 0 && edge < 255);
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        let mut surface = CpuSurface::new(30, 30);
+        let rings = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 30.0, 30.0))
+            .add_rect(Rect::new(10.0, 10.0, 10.0, 10.0))
+            .build();
+
+        surface.draw_path(&rings, &Paint::with_color(Color::BLACK));
+        assert_eq!(pixel(&surface, 15, 15), 0xFF000000);
+
+        // Even-odd leaves a hole in the middle, for fills and clips alike
+        surface.clear(Color::TRANSPARENT);
+        let rings = rings.with_fill_rule(FillRule::EvenOdd);
+        surface.draw_path(&rings, &Paint::with_color(Color::BLACK));
+        assert_eq!(pixel(&surface, 15, 15), 0);
+        assert_eq!(pixel(&surface, 5, 5), 0xFF000000);
+
+        surface.clear(Color::TRANSPARENT);
+        surface.clip_path(&rings, ClipOp::Intersect, false);
+        surface.clear(Color::BLACK);
+        assert_eq!(pixel(&surface, 15, 15), 0);
+        assert_eq!(pixel(&surface, 25, 25), 0xFF000000);
+    }
+
+    #[test]
+    fn test_shape_primitives() {
+        let red = Paint::with_color(Color::RED);
+        let mut surface = CpuSurface::new(40, 40);
+
+        surface.draw_rrect(
+            RRect::new(Rect::new(0.0, 0.0, 40.0, 40.0), 10.0, 10.0),
+            &red,
+        );
+        assert_eq!(pixel(&surface, 0, 0), 0);
+        assert_eq!(pixel(&surface, 20, 0), 0xFFFF0000);
+
+        surface.clear(Color::TRANSPARENT);
+        let outer = RRect::new(Rect::new(0.0, 0.0, 40.0, 40.0), 8.0, 8.0);
+        let inner = RRect::new(Rect::new(10.0, 10.0, 20.0, 20.0), 4.0, 4.0);
+        surface.draw_drrect(outer, inner, &red);
+        assert_eq!(pixel(&surface, 5, 20), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 20, 20), 0);
+        // An inner rectangle poking out draws nothing
+        surface.clear(Color::TRANSPARENT);
+        surface.draw_drrect(inner, outer, &red);
+        assert_eq!(pixel(&surface, 5, 20), 0);
+
+        surface.clear(Color::TRANSPARENT);
+        surface.draw_oval(Rect::new(0.0, 10.0, 40.0, 20.0), &red);
+        assert_eq!(pixel(&surface, 2, 20), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 20, 5), 0);
+        assert_eq!(pixel(&surface, 3, 12), 0);
+
+        // A quarter wedge clockwise from the x axis covers the bottom right
+        surface.clear(Color::TRANSPARENT);
+        let oval = Rect::new(0.0, 0.0, 40.0, 40.0);
+        surface.draw_arc(oval, 0.0, 90.0, true, &red);
+        assert_eq!(pixel(&surface, 28, 28), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 28, 12), 0);
+        assert_eq!(pixel(&surface, 12, 28), 0);
+        // Without the center, the fill stops at the chord
+        surface.clear(Color::TRANSPARENT);
+        surface.draw_arc(oval, 0.0, 90.0, false, &red);
+        assert_eq!(pixel(&surface, 23, 23), 0);
+        assert_eq!(pixel(&surface, 32, 32), 0xFFFF0000);
+
+        let mut stroke = Paint::with_color(Color::RED);
+        let mut style = StrokeStyle::new(4.0);
+        style.cap = LineCap::Round;
+        stroke.set_stroke(style);
+        surface.clear(Color::TRANSPARENT);
+        let points = [
+            Point::new(5.0, 5.0),
+            Point::new(35.0, 5.0),
+            Point::new(35.0, 35.0),
+        ];
+        surface.draw_points(PointMode::Points, &points, &stroke);
+        assert_eq!(pixel(&surface, 5, 5), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 20, 5), 0);
+
+        surface.clear(Color::TRANSPARENT);
+        surface.draw_points(PointMode::Lines, &points, &stroke);
+        assert_eq!(pixel(&surface, 20, 5), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 35, 20), 0);
+
+        surface.clear(Color::TRANSPARENT);
+        surface.draw_points(PointMode::Polygon, &points, &red);
+        assert!(pixel(&surface, 20, 5) >> 24 > 0);
+        assert!(pixel(&surface, 35, 20) >> 24 > 0);
+        assert_eq!(pixel(&surface, 20, 20), 0);
+    }
+
+    #[test]
+    fn test_path_effects() {
+        let mut surface = CpuSurface::new(40, 20);
+        let mut paint = Paint::with_color(Color::RED);
+        paint.set_stroke(StrokeStyle::new(4.0));
+        paint.set_path_effect(PathEffect::trim(0.0, 0.5));
+        surface.draw_line(Point::new(0.0, 10.0), Point::new(40.0, 10.0), &paint);
+        assert_eq!(pixel(&surface, 10, 10), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 30, 10), 0);
+
+        // Effects apply to fills and to the built-in shapes too
+        surface.clear(Color::TRANSPARENT);
+        let mut paint = Paint::with_color(Color::RED);
+        paint.set_path_effect(PathEffect::corner_rounding(8.0));
+        surface.draw_rect(Rect::new(0.0, 0.0, 20.0, 20.0), &paint);
+        assert_eq!(pixel(&surface, 0, 0), 0);
+        assert_eq!(pixel(&surface, 10, 10), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 10, 0), 0xFFFF0000);
+
+        // The effect's result matches stroking its own outline
+        surface.clear(Color::TRANSPARENT);
+        let mut paint = Paint::with_color(Color::RED);
+        let mut stroke = StrokeStyle::new(2.0);
+        stroke.set_dash(vec![6.0, 4.0], 0.0);
+        let outline = Path::builder()
+            .move_to(Point::new(2.0, 10.0))
+            .line_to(Point::new(38.0, 10.0))
+            .build()
+            .stroke_to_path(&stroke);
+        surface.draw_path(&outline, &paint);
+        let filled: Vec<u32> = (0..40).map(|x| pixel(&surface, x, 10)).collect();
+        surface.clear(Color::TRANSPARENT);
+        paint.set_stroke(StrokeStyle::new(2.0));
+        paint.set_path_effect(PathEffect::dash(vec![6.0, 4.0], 0.0));
+        surface.draw_line(Point::new(2.0, 10.0), Point::new(38.0, 10.0), &paint);
+        assert_eq!(
+            (0..40).map(|x| pixel(&surface, x, 10)).collect::<Vec<_>>(),
+            filled
+        );
+        assert_eq!(pixel(&surface, 5, 10), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 10, 10), 0);
+    }
+
+    #[test]
+    fn test_stroke_caps_and_dashes() {
+        let mut surface = CpuSurface::new(40, 20);
+        let mut stroke = StrokeStyle::new(4.0);
+        stroke.cap = LineCap::Square;
+        stroke.set_dash(vec![10.0, 10.0], 0.0);
+        let mut paint = Paint::with_color(Color::RED);
+        paint.set_stroke(stroke);
+
+        surface.draw_line(Point::new(5.0, 10.0), Point::new(35.0, 10.0), &paint);
+
+        // Square caps extend each dash by half the width
+        assert_eq!(pixel(&surface, 4, 10), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 16, 10), 0xFFFF0000);
+        // Middle of the first gap
+        assert_eq!(pixel(&surface, 20, 10), 0);
+        assert_eq!(pixel(&surface, 26, 10), 0xFFFF0000);
+    }
+
+    #[test]
+    fn test_paint_blend_mode() {
+        let mut surface = CpuSurface::new(10, 10);
+        let full = Rect::new(0.0, 0.0, 10.0, 10.0);
+        surface.clear(Color::rgb(255, 255, 0));
+
+        let mut paint = Paint::with_color(Color::rgb(0, 255, 255));
+        paint.set_blend_mode(BlendMode::Multiply);
+        surface.draw_rect(full, &paint);
+        assert_eq!(pixel(&surface, 5, 5), 0xFF00FF00);
+
+        // The canvas override wins over the paint
+        surface.set_blend_mode(Some(BlendMode::Clear));
+        surface.draw_rect(full, &paint);
+        assert_eq!(pixel(&surface, 5, 5), 0);
+    }
+
+    #[test]
+    fn test_translucent_colors_are_premultiplied() {
+        let mut surface = CpuSurface::new(10, 10);
+        surface.clear(Color::rgba(255, 0, 0, 128));
+        assert_eq!(pixel(&surface, 0, 0), 0x80800000);
+    }
+
+    #[test]
+    fn test_global_alpha_applies_to_fills() {
+        let mut surface = CpuSurface::new(10, 10);
+        surface.set_global_alpha(0.5);
+        surface.draw_rect(
+            Rect::new(0.0, 0.0, 10.0, 10.0),
+            &Paint::with_color(Color::RED),
+        );
+
+        let alpha = surface.draw_target.get_data()[0] >> 24;
+        assert!((126..=129).contains(&alpha));
+    }
+
+    #[test]
+    fn test_gradient_shader() {
+        let mut surface = CpuSurface::new(100, 10);
+        let stops = vec![
+            GradientStop::new(0.0, Color::RED),
+            GradientStop::new(1.0, Color::BLUE),
+        ];
+        let mut paint = Paint::default();
+        paint.set_shader(Gradient::linear(
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+            stops.clone(),
+        ));
+        surface.draw_rect(Rect::new(0.0, 0.0, 100.0, 10.0), &paint);
+
+        let left = pixel(&surface, 0, 5);
+        let right = pixel(&surface, 99, 5);
+        assert!((left >> 16) & 0xFF > 240 && left & 0xFF < 16);
+        assert!((right >> 16) & 0xFF < 16 && right & 0xFF > 240);
+
+        // The local matrix moves the gradient; padding extends the end colors
+        let mut paint = Paint::default();
+        paint.set_shader(
+            Gradient::linear(Point::new(0.0, 0.0), Point::new(10.0, 0.0), stops)
+                .with_local_matrix(Matrix3x3::from_translation(Point::new(50.0, 0.0))),
+        );
+        surface.draw_rect(Rect::new(0.0, 0.0, 100.0, 10.0), &paint);
+        assert_eq!(pixel(&surface, 20, 5), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 80, 5), 0xFF0000FF);
+    }
+
+    #[test]
+    fn test_blur_image_filter() {
+        let mut surface = CpuSurface::new(40, 40);
+        let mut paint = Paint::with_color(Color::BLACK);
+        paint.set_image_filter(BlurFilter::uniform(2.0));
+        surface.draw_rect(Rect::new(10.0, 10.0, 20.0, 20.0), &paint);
+
+        // The center is untouched, the edges fade out past the rect
+        assert_eq!(pixel(&surface, 20, 20), 0xFF000000);
+        let outside = pixel(&surface, 8, 20) >> 24;
+        assert!(outside > 0 && outside < 128);
+        assert_eq!(pixel(&surface, 2, 20), 0);
+
+        // The blur is scaled by the matrix
+        let mut surface = CpuSurface::new(40, 40);
+        surface.scale(2.0, 2.0);
+        surface.draw_rect(Rect::new(5.0, 5.0, 10.0, 10.0), &paint);
+        assert!(pixel(&surface, 5, 20) >> 24 > 0);
+    }
+
+    #[test]
+    fn test_apply_blur_region() {
+        let mut surface = CpuSurface::new(20, 10);
+        let paint = Paint::with_color(Color::WHITE);
+        surface.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), &paint);
+
+        let blur = BlurFilter::new(2.0, 0.0).with_edge_mode(EdgeMode::Clamp);
+        surface.apply_blur(Rect::new(5.0, 0.0, 15.0, 5.0), &blur);
+
+        // Rows outside the region keep their hard edge
+        assert_eq!(pixel(&surface, 10, 7), 0);
+        let blurred = pixel(&surface, 10, 2) >> 24;
+        assert!(blurred > 0 && blurred < 255);
+    }
+
+    #[test]
+    fn test_drop_and_inner_shadows() {
+        let mut surface = CpuSurface::new(40, 40);
+        let mut paint = Paint::with_color(Color::WHITE);
+        paint.add_shadow(Shadow::drop_shadow(Point::new(5.0, 5.0), 0.0, Color::BLACK));
+        surface.draw_rect(Rect::new(10.0, 10.0, 10.0, 10.0), &paint);
+
+        // The shadow is offset beneath the shape
+        assert_eq!(pixel(&surface, 15, 15), 0xFFFFFFFF);
+        assert_eq!(pixel(&surface, 22, 22), 0xFF000000);
+        assert_eq!(pixel(&surface, 8, 8), 0);
+
+        let mut surface = CpuSurface::new(40, 40);
+        let mut paint = Paint::with_color(Color::WHITE);
+        paint.add_shadow(
+            Shadow::inner_shadow(Point::new(0.0, 0.0), 0.0, Color::BLACK).with_spread(2.0),
+        );
+        surface.draw_rect(Rect::new(10.0, 10.0, 20.0, 20.0), &paint);
+
+        // Spread pushes the inset shadow in from every edge
+        assert_eq!(pixel(&surface, 11, 20), 0xFF000000);
+        assert_eq!(pixel(&surface, 20, 20), 0xFFFFFFFF);
+        assert_eq!(pixel(&surface, 5, 20), 0);
+    }
+
+    #[test]
+    fn test_read_and_write_pixels() {
+        let mut surface = CpuSurface::new(8, 8);
+        surface.draw_rect(
+            Rect::new(2.0, 2.0, 4.0, 4.0),
+            &Paint::with_color(Color::rgba(255, 0, 0, 128)),
+        );
+
+        // A 2x2 sub-rect straddling the square, with padded rows
+        let mut pixels = vec![0xAAu8; 2 * 12];
+        let format = PixelFormat::BGRA8_PREMULTIPLIED;
+        surface
+            .read_pixels(IRect::new(1, 1, 2, 2), format, &mut pixels, 12)
+            .unwrap();
+        assert_eq!(pixels[..8], [0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(pixels[12..20], [0, 0, 0, 0, 0, 0, 128, 128]);
+        assert_eq!(pixels[8..12], [0xAA; 4]);
+
+        let white = [255u8; 4 * 4];
+        surface
+            .write_pixels(IRect::new(6, 6, 2, 2), PixelFormat::RGBA8, &white, 8)
+            .unwrap();
+        assert_eq!(pixel(&surface, 7, 7), 0xFFFFFFFF);
+        assert_eq!(pixel(&surface, 5, 5), 0x80800000);
+
+        let result = surface.read_pixels(IRect::new(7, 7, 2, 2), format, &mut pixels, 12);
+        assert!(matches!(result, Err(PixelError::OutOfBounds(..))));
+    }
+
+    #[test]
+    fn test_draw_image_rect() {
+        // Red and blue halves
+        let image = Image::from_argb(2, 1, vec![0xFFFF0000, 0xFF0000FF]);
+        let mut surface = CpuSurface::new(20, 10);
+        surface.draw_image_rect(
+            &image,
+            image.bounds(),
+            Rect::new(0.0, 0.0, 20.0, 10.0),
+            FilterQuality::Nearest,
+            &Paint::default(),
+        );
+        assert_eq!(pixel(&surface, 9, 5), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 10, 5), 0xFF0000FF);
+
+        // Sampling stays inside `src`, even when filtering
+        for quality in [FilterQuality::Bilinear, FilterQuality::Bicubic] {
+            surface.draw_image_rect(
+                &image,
+                Rect::new(0.0, 0.0, 1.0, 1.0),
+                Rect::new(0.0, 0.0, 20.0, 10.0),
+                quality,
+                &Paint::default(),
+            );
+            assert_eq!(pixel(&surface, 19, 5), 0xFFFF0000);
+        }
+
+        // Scaling far past the surface doesn't resample the whole image
+        surface.draw_image_rect(
+            &image,
+            image.bounds(),
+            Rect::new(0.0, 0.0, 100_000.0, 100_000.0),
+            FilterQuality::Bicubic,
+            &Paint::default(),
+        );
+        assert_eq!(pixel(&surface, 5, 5), 0xFFFF0000);
+
+        // The paint's alpha sets the opacity
+        let mut surface = CpuSurface::new(2, 1);
+        surface.draw_image(
+            &image,
+            Point::new(0.0, 0.0),
+            &Paint::with_color(Color::rgba(0, 0, 0, 128)),
+        );
+        assert_eq!(pixel(&surface, 0, 0) >> 24, 0x80);
+    }
+
+    #[test]
+    fn test_draw_image_mipmap() {
+        // Thin stripes average to gray when shrunk
+        let data = (0..64 * 64)
+            .map(|i| if i % 2 == 0 { 0xFFFFFFFF } else { 0xFF000000 })
+            .collect();
+        let image = Image::from_argb(64, 64, data);
+        let mut surface = CpuSurface::new(8, 8);
+        surface.draw_image_rect(
+            &image,
+            image.bounds(),
+            Rect::new(0.0, 0.0, 8.0, 8.0),
+            FilterQuality::Mipmap,
+            &Paint::default(),
+        );
+        let gray = (pixel(&surface, 4, 4) >> 16) & 0xFF;
+        assert!((120..=136).contains(&gray), "{gray}");
+    }
+
+    #[test]
+    fn test_draw_image_nine() {
+        // A 3x3 image with a distinct center pixel
+        let mut data = vec![0xFFFF0000; 9];
+        data[4] = 0xFF00FF00;
+        let image = Image::from_argb(3, 3, data);
+
+        let mut surface = CpuSurface::new(20, 20);
+        surface.draw_image_nine(
+            &image,
+            IRect::new(1, 1, 1, 1),
+            Rect::new(0.0, 0.0, 20.0, 20.0),
+            FilterQuality::Nearest,
+            &Paint::default(),
+        );
+        // The border stays one pixel wide; the center fills the rest
+        assert_eq!(pixel(&surface, 0, 10), 0xFFFF0000);
+        assert_eq!(pixel(&surface, 1, 10), 0xFF00FF00);
+        assert_eq!(pixel(&surface, 18, 18), 0xFF00FF00);
+        assert_eq!(pixel(&surface, 19, 19), 0xFFFF0000);
+    }
 }
@@ -0,0 +1,224 @@
+//! Gaussian blur filter
+//!
+//! A separable Gaussian blur over premultiplied ARGB pixels (the layout
+//! `CpuSurface` stores), with rows and columns processed in parallel.
+
+use rayon::prelude::*;
+
+/// How a filter samples pixels outside the area it is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    /// Treat pixels outside the area as transparent
+    #[default]
+    Transparent,
+    /// Extend the edge pixels
+    Clamp,
+    /// Wrap around to the opposite edge
+    Wrap,
+    /// Reflect the area at its edges
+    Mirror,
+}
+
+/// A separable Gaussian blur
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlurFilter {
+    /// Standard deviation along x, in pixels
+    pub sigma_x: f32,
+
+    /// Standard deviation along y, in pixels
+    pub sigma_y: f32,
+
+    /// Sampling behavior at the edges of the blurred area
+    pub edge_mode: EdgeMode,
+}
+
+impl BlurFilter {
+    /// Create a blur with separate horizontal and vertical sigmas
+    pub fn new(sigma_x: f32, sigma_y: f32) -> Self {
+        Self {
+            sigma_x,
+            sigma_y,
+            edge_mode: EdgeMode::Transparent,
+        }
+    }
+
+    /// Create a blur with the same sigma in both directions
+    pub fn uniform(sigma: f32) -> Self {
+        Self::new(sigma, sigma)
+    }
+
+    /// Set the edge mode
+    pub fn with_edge_mode(mut self, edge_mode: EdgeMode) -> Self {
+        self.edge_mode = edge_mode;
+        self
+    }
+
+    /// Check whether the blur leaves pixels unchanged
+    pub fn is_noop(&self) -> bool {
+        kernel_radius(self.sigma_x) == 0 && kernel_radius(self.sigma_y) == 0
+    }
+
+    /// Get how far, in pixels, the blur spreads content along x and y
+    pub fn extent(&self) -> (usize, usize) {
+        (kernel_radius(self.sigma_x), kernel_radius(self.sigma_y))
+    }
+
+    /// Blur a `width` x `height` buffer of premultiplied ARGB pixels in place
+    pub fn apply(&self, pixels: &mut [u32], width: usize, height: usize) {
+        assert_eq!(pixels.len(), width * height, "buffer size mismatch");
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        if let Some(kernel) = gaussian_kernel(self.sigma_x) {
+            pixels
+                .par_chunks_mut(width)
+                .for_each(|row| blur_line(row, &kernel, self.edge_mode));
+        }
+
+        if let Some(kernel) = gaussian_kernel(self.sigma_y) {
+            // Blur columns as rows of the transposed buffer
+            let mut columns = transpose(pixels, width, height);
+            columns
+                .par_chunks_mut(height)
+                .for_each(|column| blur_line(column, &kernel, self.edge_mode));
+            pixels.copy_from_slice(&transpose(&columns, height, width));
+        }
+    }
+}
+
+/// Filter applied to everything a paint draws before it is composited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFilter {
+    /// Gaussian blur, with sigmas in user space
+    Blur(BlurFilter),
+}
+
+impl From<BlurFilter> for ImageFilter {
+    fn from(blur: BlurFilter) -> Self {
+        Self::Blur(blur)
+    }
+}
+
+/// Kernel half-width covering three standard deviations
+fn kernel_radius(sigma: f32) -> usize {
+    if sigma.is_finite() && sigma > 0.0 {
+        (sigma * 3.0).ceil() as usize
+    } else {
+        0
+    }
+}
+
+/// Normalized Gaussian weights for offsets `-radius..=radius`
+fn gaussian_kernel(sigma: f32) -> Option<Vec<f32>> {
+    let radius = kernel_radius(sigma) as isize;
+    if radius == 0 {
+        return None;
+    }
+
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|x| (-((x * x) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    Some(weights.into_iter().map(|w| w / sum).collect())
+}
+
+/// Map an index outside `0..len` according to the edge mode
+fn edge_index(i: isize, len: usize, mode: EdgeMode) -> Option<usize> {
+    let n = len as isize;
+    if (0..n).contains(&i) {
+        return Some(i as usize);
+    }
+
+    match mode {
+        EdgeMode::Transparent => None,
+        EdgeMode::Clamp => Some(i.clamp(0, n - 1) as usize),
+        EdgeMode::Wrap => Some(i.rem_euclid(n) as usize),
+        EdgeMode::Mirror => {
+            let period = 2 * n;
+            let i = i.rem_euclid(period);
+            Some(if i < n { i } else { period - 1 - i } as usize)
+        }
+    }
+}
+
+/// Convolve one row or column with a kernel
+fn blur_line(line: &mut [u32], kernel: &[f32], mode: EdgeMode) {
+    let source: Vec<[f32; 4]> = line.iter().map(|&p| unpack(p)).collect();
+    let radius = (kernel.len() / 2) as isize;
+
+    for (x, pixel) in line.iter_mut().enumerate() {
+        let mut sum = [0.0f32; 4];
+        for (k, weight) in kernel.iter().enumerate() {
+            let Some(i) = edge_index(x as isize + k as isize - radius, source.len(), mode) else {
+                continue;
+            };
+            for (acc, channel) in sum.iter_mut().zip(source[i]) {
+                *acc += channel * weight;
+            }
+        }
+        *pixel = pack(sum);
+    }
+}
+
+fn unpack(pixel: u32) -> [f32; 4] {
+    [24, 16, 8, 0].map(|shift| ((pixel >> shift) & 0xFF) as f32)
+}
+
+fn pack(channels: [f32; 4]) -> u32 {
+    let [a, r, g, b] = channels.map(|c| c.round().clamp(0.0, 255.0) as u32);
+    // Rounding must not leave a color brighter than its alpha
+    (a << 24) | (r.min(a) << 16) | (g.min(a) << 8) | b.min(a)
+}
+
+fn transpose(pixels: &[u32], width: usize, height: usize) -> Vec<u32> {
+    let mut out = vec![0; pixels.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[x * height + y] = pixels[y * width + x];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u32 = 0xFFFF0000;
+
+    #[test]
+    fn test_blur_preserves_flat_color() {
+        let mut pixels = vec![RED; 16 * 8];
+        BlurFilter::uniform(2.0)
+            .with_edge_mode(EdgeMode::Clamp)
+            .apply(&mut pixels, 16, 8);
+        assert!(pixels.iter().all(|&p| p == RED));
+    }
+
+    #[test]
+    fn test_blur_spreads_and_conserves() {
+        let (width, height) = (21, 21);
+        let mut pixels = vec![0u32; width * height];
+        pixels[10 * width + 10] = 0xFF000000;
+
+        BlurFilter::new(1.0, 0.0).apply(&mut pixels, width, height);
+
+        // Only the center row is touched by a horizontal blur
+        assert_eq!(pixels[9 * width + 10], 0);
+        let alpha = |x: usize| pixels[10 * width + x] >> 24;
+        assert!(alpha(10) > alpha(11) && alpha(11) > alpha(12) && alpha(12) > 0);
+        assert_eq!(alpha(9), alpha(11));
+        let total: u32 = (0..width).map(alpha).sum();
+        assert!((250..=260).contains(&total));
+    }
+
+    #[test]
+    fn test_edge_modes() {
+        assert_eq!(edge_index(-1, 4, EdgeMode::Transparent), None);
+        assert_eq!(edge_index(-1, 4, EdgeMode::Clamp), Some(0));
+        assert_eq!(edge_index(-1, 4, EdgeMode::Wrap), Some(3));
+        assert_eq!(edge_index(-1, 4, EdgeMode::Mirror), Some(0));
+        assert_eq!(edge_index(5, 4, EdgeMode::Mirror), Some(2));
+    }
+}
@@ -0,0 +1,196 @@
+//! Gradient shaders
+//!
+//! Linear, radial, two-point conical and sweep gradients with arbitrary
+//! color stops, attached to a `Paint` through `Shader`.
+
+use crate::{Color, Matrix3x3, Point};
+
+/// How a shader fills the area outside its natural range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileMode {
+    /// Extend the edge colors
+    #[default]
+    Pad,
+    /// Repeat the pattern
+    Repeat,
+    /// Repeat the pattern, mirroring every other copy
+    Mirror,
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient (0.0-1.0)
+    pub offset: f32,
+
+    /// Color at this position
+    pub color: Color,
+}
+
+impl GradientStop {
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// Geometry of a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// Colors vary along the line from `start` to `end`
+    Linear { start: Point, end: Point },
+
+    /// Colors vary with distance from `center`, reaching the last stop at `radius`
+    Radial { center: Point, radius: f32 },
+
+    /// Colors vary across the circles interpolated between two circles
+    TwoPointConical {
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+    },
+
+    /// Colors vary with angle around `center`, in degrees clockwise from +x
+    Sweep {
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+    },
+}
+
+/// A gradient shader
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// Gradient geometry
+    pub kind: GradientKind,
+
+    /// Color stops, ordered by offset
+    pub stops: Vec<GradientStop>,
+
+    /// Behavior outside the 0.0-1.0 range
+    pub tile_mode: TileMode,
+
+    /// Transform from gradient space to the canvas' user space
+    pub local_matrix: Matrix3x3,
+}
+
+impl Gradient {
+    /// Create a gradient with the given geometry and stops
+    pub fn new(kind: GradientKind, stops: Vec<GradientStop>) -> Self {
+        Self {
+            kind,
+            stops,
+            tile_mode: TileMode::Pad,
+            local_matrix: Matrix3x3::IDENTITY,
+        }
+    }
+
+    /// Create a linear gradient from `start` to `end`
+    pub fn linear(start: Point, end: Point, stops: Vec<GradientStop>) -> Self {
+        Self::new(GradientKind::Linear { start, end }, stops)
+    }
+
+    /// Create a radial gradient around `center`
+    pub fn radial(center: Point, radius: f32, stops: Vec<GradientStop>) -> Self {
+        Self::new(GradientKind::Radial { center, radius }, stops)
+    }
+
+    /// Create a two-point conical gradient between two circles
+    pub fn two_point_conical(
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        stops: Vec<GradientStop>,
+    ) -> Self {
+        Self::new(
+            GradientKind::TwoPointConical {
+                start,
+                start_radius,
+                end,
+                end_radius,
+            },
+            stops,
+        )
+    }
+
+    /// Create a sweep (conic) gradient around `center`, angles in degrees
+    pub fn sweep(
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+        stops: Vec<GradientStop>,
+    ) -> Self {
+        Self::new(
+            GradientKind::Sweep {
+                center,
+                start_angle,
+                end_angle,
+            },
+            stops,
+        )
+    }
+
+    /// Set the tile mode
+    pub fn with_tile_mode(mut self, tile_mode: TileMode) -> Self {
+        self.tile_mode = tile_mode;
+        self
+    }
+
+    /// Set the local matrix
+    pub fn with_local_matrix(mut self, local_matrix: Matrix3x3) -> Self {
+        self.local_matrix = local_matrix;
+        self
+    }
+
+    /// Get the stops with offsets clamped to 0.0-1.0 and forced into order
+    pub fn normalized_stops(&self) -> Vec<GradientStop> {
+        let mut last = 0.0f32;
+        self.stops
+            .iter()
+            .map(|stop| {
+                last = stop.offset.clamp(last, 1.0);
+                GradientStop::new(last, stop.color)
+            })
+            .collect()
+    }
+}
+
+/// Source of color for a paint, used in place of its solid color
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shader {
+    /// A color gradient
+    Gradient(Gradient),
+}
+
+impl From<Gradient> for Shader {
+    fn from(gradient: Gradient) -> Self {
+        Self::Gradient(gradient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_stops() {
+        let gradient = Gradient::linear(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            vec![
+                GradientStop::new(-0.5, Color::RED),
+                GradientStop::new(0.6, Color::GREEN),
+                GradientStop::new(0.4, Color::BLUE),
+                GradientStop::new(2.0, Color::WHITE),
+            ],
+        );
+
+        let offsets: Vec<f32> = gradient
+            .normalized_stops()
+            .iter()
+            .map(|s| s.offset)
+            .collect();
+        assert_eq!(offsets, vec![0.0, 0.6, 0.6, 1.0]);
+    }
+}
@@ -1,5 +1,9 @@
 //! Image effects and filters
 
-// Placeholder for effects
-pub struct Gradient;
-pub struct BlurFilter;
+mod blur;
+mod gradient;
+mod shadow;
+
+pub use blur::{BlurFilter, EdgeMode, ImageFilter};
+pub use gradient::{Gradient, GradientKind, GradientStop, Shader, TileMode};
+pub use shadow::Shadow;
@@ -0,0 +1,55 @@
+//! Drop and inner shadows
+
+use super::blur::{BlurFilter, EdgeMode};
+use crate::{Color, Point};
+
+/// A shadow cast by a shape, drawn beneath it or inset within it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    /// Offset of the shadow from the shape, in user space
+    pub offset: Point,
+
+    /// Blur radius; the Gaussian sigma is half of it, as in CSS
+    pub blur_radius: f32,
+
+    /// Distance the shadow grows (or shrinks, if negative) before blurring
+    pub spread: f32,
+
+    /// Shadow color
+    pub color: Color,
+
+    /// Draw the shadow inside the shape instead of beneath it
+    pub inset: bool,
+}
+
+impl Shadow {
+    /// Create a shadow drawn beneath the shape
+    pub fn drop_shadow(offset: Point, blur_radius: f32, color: Color) -> Self {
+        Self {
+            offset,
+            blur_radius,
+            spread: 0.0,
+            color,
+            inset: false,
+        }
+    }
+
+    /// Create a shadow drawn inside the shape, along its edges
+    pub fn inner_shadow(offset: Point, blur_radius: f32, color: Color) -> Self {
+        Self {
+            inset: true,
+            ..Self::drop_shadow(offset, blur_radius, color)
+        }
+    }
+
+    /// Set the spread
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Get the Gaussian blur for this shadow's blur radius
+    pub fn blur_filter(&self) -> BlurFilter {
+        BlurFilter::uniform(self.blur_radius.max(0.0) / 2.0).with_edge_mode(EdgeMode::Clamp)
+    }
+}
@@ -1,6 +1,6 @@
 //! Geometric primitives and transformations
 
-use glam::{Mat3, Vec2};
+use glam::{Mat3, Vec2, Vec3};
 
 /// 2D point
 pub type Point = Vec2;
@@ -18,6 +18,45 @@ impl Size {
     }
 }
 
+/// Axis-aligned rectangle of whole pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl IRect {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Create a rectangle at the origin
+    pub const fn from_size(width: u32, height: u32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    /// Get the right edge, or `None` if it is past `u32::MAX`
+    pub fn right(&self) -> Option<u32> {
+        self.x.checked_add(self.width)
+    }
+
+    /// Get the bottom edge, or `None` if it is past `u32::MAX`
+    pub fn bottom(&self) -> Option<u32> {
+        self.y.checked_add(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
 /// Axis-aligned rectangle
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
@@ -83,11 +122,111 @@ impl Rect {
             && self.top() < other.bottom()
             && self.bottom() > other.top()
     }
+
+    /// Check whether the rectangle has no area
+    pub fn is_empty(&self) -> bool {
+        !(self.width > 0.0 && self.height > 0.0)
+    }
+
+    /// Get the overlap of two rectangles, if they overlap
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        self.intersects(other).then(|| {
+            Rect::from_ltrb(
+                self.left().max(other.left()),
+                self.top().max(other.top()),
+                self.right().min(other.right()),
+                self.bottom().min(other.bottom()),
+            )
+        })
+    }
+}
+
+/// Rectangle with an elliptical radius at each corner
+///
+/// Radii are stored clockwise from the top-left corner. They are scaled down
+/// together when adjacent corners would overlap, as in CSS and Skia.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RRect {
+    rect: Rect,
+    radii: [Point; 4],
+}
+
+impl RRect {
+    /// Create a rounded rectangle with the same corner radii all around
+    pub fn new(rect: Rect, rx: f32, ry: f32) -> Self {
+        Self::from_radii(rect, [Point::new(rx, ry); 4])
+    }
+
+    /// Create a rounded rectangle with square corners
+    pub fn from_rect(rect: Rect) -> Self {
+        Self::new(rect, 0.0, 0.0)
+    }
+
+    /// Create a rounded rectangle with per-corner `(x, y)` radii
+    ///
+    /// The order is top-left, top-right, bottom-right, bottom-left. A corner
+    /// with a zero or negative radius on either axis is square.
+    pub fn from_radii(rect: Rect, mut radii: [Point; 4]) -> Self {
+        for radius in &mut radii {
+            if !(radius.x > 0.0 && radius.y > 0.0) {
+                *radius = Point::ZERO;
+            }
+        }
+
+        // Each side must fit the radii of both of its corners
+        let fit = |side: f32, a: f32, b: f32| {
+            if a + b > side {
+                side.max(0.0) / (a + b)
+            } else {
+                1.0
+            }
+        };
+        let scale = fit(rect.width, radii[0].x, radii[1].x)
+            .min(fit(rect.height, radii[1].y, radii[2].y))
+            .min(fit(rect.width, radii[2].x, radii[3].x))
+            .min(fit(rect.height, radii[3].y, radii[0].y));
+        if scale < 1.0 {
+            for radius in &mut radii {
+                *radius *= scale;
+            }
+        }
+
+        Self { rect, radii }
+    }
+
+    /// Get the bounding rectangle
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Get the corner radii, clockwise from the top-left
+    pub fn radii(&self) -> [Point; 4] {
+        self.radii
+    }
+
+    /// Check whether every corner is square
+    pub fn is_rect(&self) -> bool {
+        self.radii.iter().all(|radius| *radius == Point::ZERO)
+    }
 }
 
 /// 3x3 transformation matrix for 2D affine transforms
 pub type Matrix3x3 = Mat3;
 
+/// Create a skew matrix mapping `(x, y)` to `(x + sx * y, y + sy * x)`
+pub fn skew_matrix(sx: f32, sy: f32) -> Matrix3x3 {
+    Mat3::from_cols(
+        Vec3::new(1.0, sy, 0.0),
+        Vec3::new(sx, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    )
+}
+
+/// Check whether a matrix only translates (no scale, rotation or skew)
+pub fn is_translate_only(matrix: &Matrix3x3) -> bool {
+    matrix.x_axis.truncate() == Vec2::X && matrix.y_axis.truncate() == Vec2::Y
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,10 +240,63 @@ mod tests {
         assert_eq!(rect.bottom(), 70.0);
     }
 
+    #[test]
+    fn test_irect_edges() {
+        let rect = IRect::new(2, 3, 4, 5);
+        assert_eq!((rect.right(), rect.bottom()), (Some(6), Some(8)));
+        let rect = IRect::new(u32::MAX, 1, 1, u32::MAX);
+        assert_eq!((rect.right(), rect.bottom()), (None, None));
+    }
+
+    #[test]
+    fn test_skew_matrix() {
+        let m = skew_matrix(0.5, 0.0);
+        assert_eq!(
+            m.transform_point2(Point::new(0.0, 10.0)),
+            Point::new(5.0, 10.0)
+        );
+        assert!(!is_translate_only(&m));
+        assert!(is_translate_only(&Mat3::from_translation(Vec2::new(
+            3.0, 4.0
+        ))));
+    }
+
     #[test]
     fn test_rect_contains() {
         let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
         assert!(rect.contains(Point::new(50.0, 50.0)));
         assert!(!rect.contains(Point::new(150.0, 50.0)));
     }
+
+    #[test]
+    fn test_rect_intersect() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::new(5.0, -5.0, 10.0, 10.0);
+        assert_eq!(a.intersect(&b), Some(Rect::new(5.0, 0.0, 5.0, 5.0)));
+        assert_eq!(a.intersect(&Rect::new(20.0, 0.0, 5.0, 5.0)), None);
+        assert!(Rect::new(0.0, 0.0, 0.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_rrect_radii() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 40.0);
+        let rrect = RRect::new(rect, 10.0, 5.0);
+        assert_eq!(rrect.radii(), [Point::new(10.0, 5.0); 4]);
+        assert!(RRect::from_rect(rect).is_rect());
+
+        // Radii that overlap along the height are scaled down together
+        let rrect = RRect::from_radii(
+            rect,
+            [
+                Point::new(10.0, 30.0),
+                Point::new(10.0, 10.0),
+                Point::new(-1.0, 5.0),
+                Point::new(20.0, 30.0),
+            ],
+        );
+        let radii = rrect.radii();
+        assert_eq!(radii[0], Point::new(10.0, 30.0) * (40.0 / 60.0));
+        assert_eq!(radii[2], Point::ZERO);
+        assert_eq!(radii[3].y + radii[0].y, 40.0);
+    }
 }
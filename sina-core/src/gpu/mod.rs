@@ -1,10 +1,6 @@
 //! GPU-accelerated backend using WebGPU
 
-// Placeholder for future GPU implementation
-pub struct GpuSurface;
+mod surface;
+mod tessellate;
 
-impl GpuSurface {
-    pub fn new(_width: u32, _height: u32) -> Self {
-        todo!("GPU backend not yet implemented")
-    }
-}
+pub use surface::{GpuError, GpuSurface};
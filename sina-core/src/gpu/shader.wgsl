@@ -0,0 +1,37 @@
+// Triangles in device pixel coordinates, either solid-colored or copying
+// pixels staged in a texture the size of the target
+
+struct Viewport {
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> viewport: Viewport;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    let ndc = position / viewport.size * 2.0 - 1.0;
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+
+@group(1) @binding(0)
+var image: texture_2d<f32>;
+
+// Copy the staged pixel under each fragment
+@fragment
+fn fs_image(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureLoad(image, vec2<i32>(in.position.xy), 0);
+}
@@ -0,0 +1,1135 @@
+//! Offscreen wgpu surface
+
+use super::tessellate::{self, Mesh, Vertex};
+use crate::image::{FilterQuality, Image};
+use crate::pixels::{check_access, PixelError, PixelFormat};
+use crate::{
+    BlendMode, Canvas, ClipOp, Color, FillRule, IRect, Matrix3x3, Paint, Path, Point, Rect, Surface,
+};
+use lyon_path::math::{point, Box2D};
+use lyon_path::{Path as LyonPath, Winding};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use wgpu::util::DeviceExt;
+
+/// Color format of the render target
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Format holding the clip stencil
+const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+/// Samples per pixel used for anti-aliasing, when the adapter supports it
+const SAMPLE_COUNT: u32 = 4;
+
+/// GPU backend errors
+#[derive(Debug, Error)]
+pub enum GpuError {
+    #[error("No suitable GPU adapter found")]
+    NoAdapter,
+
+    #[error("Failed to create device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+
+    #[error("Failed to read back pixels: {0}")]
+    Readback(#[from] wgpu::BufferAsyncError),
+
+    #[error("Invalid surface size: {0}x{1}")]
+    InvalidSize(u32, u32),
+}
+
+/// How a draw interacts with the clip stencil
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StencilMode {
+    /// Draw color where the stencil equals the clip depth
+    Draw,
+    /// Draw color everywhere, ignoring the clip
+    Ignore,
+    /// Increment the stencil where it equals the reference, without drawing color
+    Increment,
+    /// Decrement the stencil where it equals the reference, without drawing color
+    Decrement,
+}
+
+/// Fragment program used by a pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Program {
+    /// Interpolated vertex colors
+    Solid,
+    /// Pixels copied from the upload texture
+    Image,
+}
+
+/// Everything that selects a render pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    program: Program,
+    blend_mode: BlendMode,
+    stencil_mode: StencilMode,
+}
+
+/// A clip shape recorded so the stencil can be rebuilt on `restore`
+#[derive(Debug)]
+struct ClipRecord {
+    /// Device-space triangles of the clip shape
+    mesh: Mesh,
+    op: ClipOp,
+}
+
+/// Drawing state captured by `save` and rolled back by `restore`
+#[derive(Debug, Clone)]
+struct DrawState {
+    matrix: Matrix3x3,
+    global_alpha: f32,
+    blend_mode: Option<BlendMode>,
+    /// Clips applied so far; the stencil holds `clips.len()` inside the clip
+    clips: Vec<Arc<ClipRecord>>,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            matrix: Matrix3x3::IDENTITY,
+            global_alpha: 1.0,
+            blend_mode: None,
+            clips: Vec::new(),
+        }
+    }
+}
+
+/// Offscreen surface rendered with wgpu
+///
+/// Paths are tessellated with lyon and drawn as solid-color triangles with
+/// multisample anti-aliasing; clips are kept in a stencil buffer. Shaders,
+/// image filters, shadows and color (bitmap) glyphs are not supported yet and
+/// fall back to the paint's solid color or are skipped. Blend modes that
+/// fixed-function blending cannot express fall back to `SrcOver`.
+pub struct GpuSurface {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    width: u32,
+    height: u32,
+
+    /// Single-sample texture holding the rendered image
+    target: wgpu::Texture,
+    /// Multisampled color attachment resolved into `target`, if supported
+    msaa: Option<wgpu::TextureView>,
+    stencil: wgpu::TextureView,
+    sample_count: u32,
+
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    image_pipeline_layout: wgpu::PipelineLayout,
+    image_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+
+    /// Texture staging `write_pixels` data, with its bind group
+    upload: Option<(wgpu::Texture, wgpu::BindGroup)>,
+
+    state: DrawState,
+    state_stack: Vec<DrawState>,
+}
+
+impl GpuSurface {
+    /// Create a transparent offscreen surface on the default adapter
+    ///
+    /// Falls back to a software adapter (such as lavapipe or llvmpipe) when
+    /// no hardware adapter is available.
+    pub fn new(width: u32, height: u32) -> Result<Self, GpuError> {
+        pollster::block_on(Self::new_async(width, height))
+    }
+
+    async fn new_async(width: u32, height: u32) -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+            ..Default::default()
+        });
+
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::util::power_preference_from_env().unwrap_or_default(),
+                    force_fallback_adapter,
+                    compatible_surface: None,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(GpuError::NoAdapter)?;
+        log::info!("Using GPU adapter: {:?}", adapter.get_info());
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("sina"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                        .using_resolution(adapter.limits()),
+                    memory_hints: Default::default(),
+                },
+                None,
+            )
+            .await?;
+
+        let max_size = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(GpuError::InvalidSize(width, height));
+        }
+
+        let multisampled = [FORMAT, STENCIL_FORMAT].iter().all(|&format| {
+            adapter
+                .get_texture_format_features(format)
+                .flags
+                .sample_count_supported(SAMPLE_COUNT)
+        });
+        let sample_count = if multisampled { SAMPLE_COUNT } else { 1 };
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let attachment = |label, format, sample_count, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+
+        let target = attachment(
+            "sina target",
+            FORMAT,
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+        );
+        let msaa = multisampled.then(|| {
+            attachment(
+                "sina msaa",
+                FORMAT,
+                sample_count,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+            .create_view(&Default::default())
+        });
+        let stencil = attachment(
+            "sina stencil",
+            STENCIL_FORMAT,
+            sample_count,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        )
+        .create_view(&Default::default());
+
+        let viewport = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sina viewport"),
+            contents: bytemuck::cast_slice(&[width as f32, height as f32, 0.0, 0.0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sina viewport"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sina viewport"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: viewport.as_entire_binding(),
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sina"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let image_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sina image"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let image_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("sina image"),
+                bind_group_layouts: &[&bind_group_layout, &image_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+
+        Ok(Self {
+            device,
+            queue,
+            width,
+            height,
+            target,
+            msaa,
+            stencil,
+            sample_count,
+            shader,
+            pipeline_layout,
+            image_pipeline_layout,
+            image_layout,
+            bind_group,
+            pipelines: HashMap::new(),
+            upload: None,
+            state: DrawState::default(),
+            state_stack: Vec::new(),
+        })
+    }
+
+    /// Read the whole surface as unpremultiplied RGBA8 rows
+    pub fn read_rgba(&self) -> Result<Vec<u8>, GpuError> {
+        let mut pixels = self.read_region(IRect::from_size(self.width, self.height))?;
+        for pixel in pixels.chunks_exact_mut(4) {
+            let rgba = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            pixel.copy_from_slice(&PixelFormat::RGBA8.encode(rgba));
+        }
+        Ok(pixels)
+    }
+
+    /// Copy a region of the target texture back as premultiplied RGBA8 rows
+    fn read_region(&self, rect: IRect) -> Result<Vec<u8>, GpuError> {
+        if rect.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let row_bytes = rect.width * 4;
+        let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sina readback"),
+            size: (padded_row_bytes * rect.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let origin = wgpu::Origin3d {
+            x: rect.x,
+            y: rect.y,
+            z: 0,
+        };
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            texture_copy(&self.target, origin),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(rect.height),
+                },
+            },
+            extent(rect),
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().expect("readback callback was dropped")?;
+
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((row_bytes * rect.height) as usize);
+        for row in data.chunks(padded_row_bytes as usize) {
+            pixels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        drop(data);
+        buffer.unmap();
+        Ok(pixels)
+    }
+
+    /// Copy premultiplied RGBA8 rows into a region of the image
+    fn write_region(&mut self, rect: IRect, pixels: &[u8]) {
+        let layout = wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(rect.width * 4),
+            rows_per_image: Some(rect.height),
+        };
+        let origin = wgpu::Origin3d {
+            x: rect.x,
+            y: rect.y,
+            z: 0,
+        };
+
+        if self.msaa.is_none() {
+            self.queue.write_texture(
+                texture_copy(&self.target, origin),
+                pixels,
+                layout,
+                extent(rect),
+            );
+            return;
+        }
+
+        // Draws load the multisampled attachment, so the pixels must reach it
+        // too: stage them in a texture and copy them over with a quad
+        if self.upload.is_none() {
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("sina upload"),
+                size: self.target.size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("sina upload"),
+                layout: &self.image_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&Default::default()),
+                    ),
+                }],
+            });
+            self.upload = Some((texture, bind_group));
+        }
+        let (texture, _) = self.upload.as_ref().unwrap();
+        self.queue
+            .write_texture(texture_copy(texture, origin), pixels, layout, extent(rect));
+
+        let quad = tessellate::quad(
+            rect.x as f32,
+            rect.y as f32,
+            (rect.x + rect.width) as f32,
+            (rect.y + rect.height) as f32,
+            [0.0; 4],
+        );
+        let key = PipelineKey {
+            program: Program::Image,
+            blend_mode: BlendMode::Src,
+            stencil_mode: StencilMode::Ignore,
+        };
+        self.draw_mesh_with(&quad, key, 0);
+    }
+
+    /// Get (creating on first use) the pipeline for a key
+    fn pipeline(&mut self, key: PipelineKey) -> &wgpu::RenderPipeline {
+        if !self.pipelines.contains_key(&key) {
+            let pipeline = self.create_pipeline(key);
+            self.pipelines.insert(key, pipeline);
+        }
+        &self.pipelines[&key]
+    }
+
+    fn create_pipeline(&self, key: PipelineKey) -> wgpu::RenderPipeline {
+        let (compare, pass_op, write_mask) = match key.stencil_mode {
+            StencilMode::Draw => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::Keep,
+                wgpu::ColorWrites::ALL,
+            ),
+            StencilMode::Ignore => (
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Keep,
+                wgpu::ColorWrites::ALL,
+            ),
+            StencilMode::Increment => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::IncrementClamp,
+                wgpu::ColorWrites::empty(),
+            ),
+            StencilMode::Decrement => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::DecrementClamp,
+                wgpu::ColorWrites::empty(),
+            ),
+        };
+        let (layout, fragment_entry) = match key.program {
+            Program::Solid => (&self.pipeline_layout, "fs_main"),
+            Program::Image => (&self.image_pipeline_layout, "fs_image"),
+        };
+        let stencil_face = wgpu::StencilFaceState {
+            compare,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        };
+
+        self.device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("sina"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &self.shader,
+                    entry_point: "vs_main",
+                    compilation_options: Default::default(),
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Vertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+                    }],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.shader,
+                    entry_point: fragment_entry,
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: FORMAT,
+                        blend: Some(blend_state(key.blend_mode)),
+                        write_mask,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: STENCIL_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState {
+                        front: stencil_face,
+                        back: stencil_face,
+                        read_mask: 0xFF,
+                        write_mask: 0xFF,
+                    },
+                    bias: Default::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: self.sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+                cache: None,
+            })
+    }
+
+    /// Render a solid-color mesh in its own pass
+    fn draw_mesh(
+        &mut self,
+        mesh: &Mesh,
+        blend_mode: BlendMode,
+        stencil_mode: StencilMode,
+        stencil_reference: u32,
+    ) {
+        let key = PipelineKey {
+            program: Program::Solid,
+            blend_mode,
+            stencil_mode,
+        };
+        self.draw_mesh_with(mesh, key, stencil_reference);
+    }
+
+    /// Render a mesh with any pipeline in its own pass
+    fn draw_mesh_with(&mut self, mesh: &Mesh, key: PipelineKey, stencil_reference: u32) {
+        if mesh.indices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("sina vertices"),
+                contents: bytemuck::cast_slice(&mesh.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        let index_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("sina indices"),
+                contents: bytemuck::cast_slice(&mesh.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+        self.pipeline(key);
+        let pipeline = &self.pipelines[&key];
+        let target_view = self.target.create_view(&Default::default());
+        let (view, resolve_target) = match &self.msaa {
+            Some(msaa) => (msaa, Some(&target_view)),
+            None => (&target_view, None),
+        };
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("sina draw"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.stencil,
+                    depth_ops: None,
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            if let (Program::Image, Some((_, upload))) = (key.program, &self.upload) {
+                pass.set_bind_group(1, upload, &[]);
+            }
+            pass.set_stencil_reference(stencil_reference);
+            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Current clip depth, which the stencil holds inside the clip
+    fn clip_depth(&self) -> u32 {
+        self.state.clips.len() as u32
+    }
+
+    /// Draw a mesh through the clip with the paint's blend mode
+    fn draw_clipped(&mut self, mesh: &Mesh, paint: &Paint) {
+        let blend_mode = supported_blend(self.state.blend_mode.unwrap_or(paint.blend_mode));
+        self.draw_mesh(mesh, blend_mode, StencilMode::Draw, self.clip_depth());
+    }
+
+    /// Premultiplied vertex color for a paint under the current state
+    fn paint_color(&self, paint: &Paint) -> [f32; 4] {
+        premultiplied(paint.color, self.state.global_alpha)
+    }
+
+    /// Fill or stroke a lyon path according to the paint
+    fn paint_path(&mut self, path: &LyonPath, fill_rule: FillRule, paint: &Paint) {
+        if let Some(effect) = &paint.path_effect {
+            let path = Path::from_lyon(path.clone()).with_fill_rule(fill_rule);
+            let reshaped = effect.apply(&path);
+            return self.paint_path_unaffected(reshaped.lyon_path(), fill_rule, paint);
+        }
+        self.paint_path_unaffected(path, fill_rule, paint);
+    }
+
+    /// Fill or stroke a path, ignoring the paint's path effect
+    fn paint_path_unaffected(&mut self, path: &LyonPath, fill_rule: FillRule, paint: &Paint) {
+        let color = self.paint_color(paint);
+        let mesh = if paint.is_fill() {
+            tessellate::fill(path, fill_rule, &self.state.matrix, color)
+        } else if let Some(stroke) = &paint.stroke {
+            tessellate::stroke(path, stroke, &self.state.matrix, color)
+        } else {
+            return;
+        };
+        self.draw_clipped(&mesh, paint);
+    }
+
+    /// Apply one clip shape on top of the stencil at its current depth
+    fn apply_clip_record(&mut self, record: &ClipRecord, depth: u32) {
+        match record.op {
+            ClipOp::Intersect => {
+                self.draw_mesh(
+                    &record.mesh,
+                    BlendMode::SrcOver,
+                    StencilMode::Increment,
+                    depth,
+                );
+            }
+            ClipOp::Difference => {
+                let everything = self.full_quad([0.0; 4]);
+                self.draw_mesh(
+                    &everything,
+                    BlendMode::SrcOver,
+                    StencilMode::Increment,
+                    depth,
+                );
+                self.draw_mesh(
+                    &record.mesh,
+                    BlendMode::SrcOver,
+                    StencilMode::Decrement,
+                    depth + 1,
+                );
+            }
+        }
+    }
+
+    /// Reset the stencil and replay the current state's clips
+    fn rebuild_clip(&mut self) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("sina clear stencil"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.stencil,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Store,
+                }),
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.queue.submit(Some(encoder.finish()));
+
+        for (depth, record) in self.state.clips.clone().iter().enumerate() {
+            self.apply_clip_record(record, depth as u32);
+        }
+    }
+
+    fn push_clip(&mut self, path: &LyonPath, fill_rule: FillRule, op: ClipOp) {
+        let record = ClipRecord {
+            mesh: tessellate::fill(path, fill_rule, &self.state.matrix, [0.0; 4]),
+            op,
+        };
+        self.apply_clip_record(&record, self.clip_depth());
+        self.state.clips.push(Arc::new(record));
+    }
+
+    fn full_quad(&self, color: [f32; 4]) -> Mesh {
+        tessellate::quad(0.0, 0.0, self.width as f32, self.height as f32, color)
+    }
+}
+
+/// Size of a region as a texture extent
+fn extent(rect: IRect) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: rect.width,
+        height: rect.height,
+        depth_or_array_layers: 1,
+    }
+}
+
+fn texture_copy(texture: &wgpu::Texture, origin: wgpu::Origin3d) -> wgpu::ImageCopyTexture<'_> {
+    wgpu::ImageCopyTexture {
+        texture,
+        mip_level: 0,
+        origin,
+        aspect: wgpu::TextureAspect::All,
+    }
+}
+
+/// Convert a color to premultiplied normalized channels, scaled by `alpha`
+fn premultiplied(color: Color, alpha: f32) -> [f32; 4] {
+    let a = color.a as f32 / 255.0 * alpha;
+    [
+        color.r as f32 / 255.0 * a,
+        color.g as f32 / 255.0 * a,
+        color.b as f32 / 255.0 * a,
+        a,
+    ]
+}
+
+/// Map blend modes without a fixed-function equivalent to `SrcOver`
+fn supported_blend(mode: BlendMode) -> BlendMode {
+    match mode {
+        BlendMode::SrcOver
+        | BlendMode::Src
+        | BlendMode::Dst
+        | BlendMode::Clear
+        | BlendMode::DstOver
+        | BlendMode::SrcIn
+        | BlendMode::DstIn
+        | BlendMode::SrcOut
+        | BlendMode::DstOut
+        | BlendMode::SrcAtop
+        | BlendMode::DstAtop
+        | BlendMode::Xor
+        | BlendMode::Plus
+        | BlendMode::Screen => mode,
+        _ => {
+            log::warn!(
+                "Blend mode {:?} is not supported on the GPU, using SrcOver",
+                mode
+            );
+            BlendMode::SrcOver
+        }
+    }
+}
+
+/// Fixed-function blending for premultiplied colors
+fn blend_state(mode: BlendMode) -> wgpu::BlendState {
+    use wgpu::BlendFactor::*;
+
+    let (src, dst) = match mode {
+        BlendMode::Src => (One, Zero),
+        BlendMode::Dst => (Zero, One),
+        BlendMode::Clear => (Zero, Zero),
+        BlendMode::DstOver => (OneMinusDstAlpha, One),
+        BlendMode::SrcIn => (DstAlpha, Zero),
+        BlendMode::DstIn => (Zero, SrcAlpha),
+        BlendMode::SrcOut => (OneMinusDstAlpha, Zero),
+        BlendMode::DstOut => (Zero, OneMinusSrcAlpha),
+        BlendMode::SrcAtop => (DstAlpha, OneMinusSrcAlpha),
+        BlendMode::DstAtop => (OneMinusDstAlpha, SrcAlpha),
+        BlendMode::Xor => (OneMinusDstAlpha, OneMinusSrcAlpha),
+        BlendMode::Plus => (One, One),
+        BlendMode::Screen => (One, OneMinusSrc),
+        _ => (One, OneMinusSrcAlpha),
+    };
+    let component = wgpu::BlendComponent {
+        src_factor: src,
+        dst_factor: dst,
+        operation: wgpu::BlendOperation::Add,
+    };
+    wgpu::BlendState {
+        color: component,
+        alpha: component,
+    }
+}
+
+impl Surface for GpuSurface {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn canvas(&mut self) -> &mut dyn Canvas {
+        self
+    }
+
+    fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let pixels = self.read_rgba()?;
+        image::save_buffer(
+            path,
+            &pixels,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+        )?;
+        Ok(())
+    }
+
+    fn read_pixels(
+        &self,
+        rect: IRect,
+        format: PixelFormat,
+        dst: &mut [u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError> {
+        check_access(rect, self.width, self.height, dst.len(), row_bytes)?;
+        if rect.is_empty() {
+            return Ok(());
+        }
+
+        let pixels = self.read_region(rect)?;
+        let source_rows = pixels.chunks(rect.width as usize * 4);
+        for (row, source) in dst.chunks_mut(row_bytes).zip(source_rows) {
+            for (out, pixel) in row.chunks_exact_mut(4).zip(source.chunks_exact(4)) {
+                out.copy_from_slice(&format.encode(pixel.try_into().unwrap()));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_pixels(
+        &mut self,
+        rect: IRect,
+        format: PixelFormat,
+        src: &[u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError> {
+        check_access(rect, self.width, self.height, src.len(), row_bytes)?;
+        if rect.is_empty() {
+            return Ok(());
+        }
+
+        let mut pixels = Vec::with_capacity(rect.width as usize * rect.height as usize * 4);
+        for row in src.chunks(row_bytes).take(rect.height as usize) {
+            for pixel in row[..rect.width as usize * 4].chunks_exact(4) {
+                pixels.extend_from_slice(&format.decode(pixel.try_into().unwrap()));
+            }
+        }
+        self.write_region(rect, &pixels);
+        Ok(())
+    }
+}
+
+impl Canvas for GpuSurface {
+    fn clear(&mut self, color: Color) {
+        let quad = self.full_quad(premultiplied(color, 1.0));
+        self.draw_mesh(&quad, BlendMode::Src, StencilMode::Draw, self.clip_depth());
+    }
+
+    fn save(&mut self) {
+        self.state_stack.push(self.state.clone());
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.state_stack.pop() {
+            let clip_changed = state.clips.len() != self.state.clips.len();
+            self.state = state;
+            if clip_changed {
+                self.rebuild_clip();
+            }
+        }
+    }
+
+    fn save_count(&self) -> usize {
+        self.state_stack.len() + 1
+    }
+
+    fn restore_to_count(&mut self, count: usize) {
+        while self.save_count() > count.max(1) {
+            self.restore();
+        }
+    }
+
+    fn set_global_alpha(&mut self, alpha: f32) {
+        self.state.global_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    fn global_alpha(&self) -> f32 {
+        self.state.global_alpha
+    }
+
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.state.blend_mode = mode;
+    }
+
+    fn concat(&mut self, matrix: Matrix3x3) {
+        self.state.matrix *= matrix;
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix3x3) {
+        self.state.matrix = matrix;
+    }
+
+    fn get_matrix(&self) -> Matrix3x3 {
+        self.state.matrix
+    }
+
+    fn clip_rect(&mut self, rect: Rect, op: ClipOp, _anti_alias: bool) {
+        self.push_clip(&rect_path(rect), FillRule::NonZero, op);
+    }
+
+    fn clip_path(&mut self, path: &Path, op: ClipOp, _anti_alias: bool) {
+        self.push_clip(path.lyon_path(), path.fill_rule(), op);
+    }
+
+    fn draw_path(&mut self, path: &Path, paint: &Paint) {
+        self.paint_path(path.lyon_path(), path.fill_rule(), paint);
+    }
+
+    fn draw_rect(&mut self, rect: Rect, paint: &Paint) {
+        self.paint_path(&rect_path(rect), FillRule::NonZero, paint);
+    }
+
+    fn draw_circle(&mut self, center: Point, radius: f32, paint: &Paint) {
+        let mut builder = LyonPath::builder();
+        builder.add_circle(point(center.x, center.y), radius, Winding::Positive);
+        self.paint_path(&builder.build(), FillRule::NonZero, paint);
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, paint: &Paint) {
+        let mut builder = LyonPath::builder();
+        builder.begin(point(from.x, from.y));
+        builder.line_to(point(to.x, to.y));
+        builder.end(false);
+        let mut line = builder.build();
+        if let Some(effect) = &paint.path_effect {
+            line = effect.apply(&Path::from_lyon(line)).lyon_path().clone();
+        }
+
+        // Lines are always stroked; fill paints get a default 1px stroke
+        let stroke = paint.stroke.clone().unwrap_or_default();
+        let color = self.paint_color(paint);
+        let mesh = tessellate::stroke(&line, &stroke, &self.state.matrix, color);
+        self.draw_clipped(&mesh, paint);
+    }
+
+    fn draw_text_styled(
+        &mut self,
+        text: &str,
+        position: Point,
+        fonts: &crate::text::FontCollection,
+        font_size: f32,
+        style: &crate::text::TextStyle,
+        paint: &Paint,
+    ) {
+        use crate::text::{TextAlign, TextLayout};
+
+        let mut layout = TextLayout::with_fonts(fonts.clone(), font_size);
+        layout.set_style(style.clone());
+        let color = self.paint_color(paint);
+
+        // Glyphs are filled from their outlines in a single mesh
+        let mut mesh = Mesh::new();
+        for (glyph_pos, glyph) in layout.layout(text, position, TextAlign::Left) {
+            let font = &fonts.fonts()[glyph.font_index];
+            let Some(outline) = font.glyph_outline(glyph.glyph_index, font_size) else {
+                continue;
+            };
+            let matrix = self.state.matrix * Matrix3x3::from_translation(glyph_pos);
+            let glyph_mesh =
+                tessellate::fill(outline.lyon_path(), FillRule::NonZero, &matrix, color);
+
+            let base = mesh.vertices.len() as u32;
+            mesh.vertices.extend(glyph_mesh.vertices);
+            mesh.indices
+                .extend(glyph_mesh.indices.iter().map(|index| index + base));
+        }
+        self.draw_clipped(&mesh, paint);
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        _image: &Image,
+        _src: Rect,
+        _dst: Rect,
+        _quality: FilterQuality,
+        _paint: &Paint,
+    ) {
+        log::warn!("GpuSurface does not draw images yet; skipping draw_image_rect");
+    }
+
+    fn draw_image_nine(
+        &mut self,
+        _image: &Image,
+        _center: IRect,
+        _dst: Rect,
+        _quality: FilterQuality,
+        _paint: &Paint,
+    ) {
+        log::warn!("GpuSurface does not draw images yet; skipping draw_image_nine");
+    }
+}
+
+/// Build a closed rectangle path
+fn rect_path(rect: Rect) -> LyonPath {
+    let mut builder = LyonPath::builder();
+    builder.add_rectangle(
+        &Box2D::new(
+            point(rect.left(), rect.top()),
+            point(rect.right(), rect.bottom()),
+        ),
+        Winding::Positive,
+    );
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a surface, failing the test when the machine has no adapter
+    fn surface(width: u32, height: u32) -> GpuSurface {
+        GpuSurface::new(width, height).unwrap_or_else(|error| {
+            panic!(
+                "GPU tests need an adapter, such as a software one: {}",
+                error
+            )
+        })
+    }
+
+    fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * width + x) * 4) as usize;
+        pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_draw_and_read_back() {
+        let mut surface = surface(32, 32);
+        surface.clear(Color::WHITE);
+        surface.draw_rect(
+            Rect::new(8.0, 8.0, 16.0, 16.0),
+            &Paint::with_color(Color::RED),
+        );
+
+        let pixels = surface.read_rgba().unwrap();
+        assert_eq!(pixels.len(), 32 * 32 * 4);
+        assert_eq!(pixel(&pixels, 32, 16, 16), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 2, 2), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_clip_and_restore() {
+        let mut surface = surface(32, 32);
+        let full = Rect::new(0.0, 0.0, 32.0, 32.0);
+
+        surface.save();
+        surface.clip_rect(Rect::new(0.0, 0.0, 16.0, 32.0), ClipOp::Intersect, true);
+        surface.clip_rect(Rect::new(0.0, 0.0, 8.0, 8.0), ClipOp::Difference, true);
+        surface.draw_rect(full, &Paint::with_color(Color::RED));
+        surface.restore();
+        surface.draw_rect(
+            Rect::new(24.0, 0.0, 8.0, 32.0),
+            &Paint::with_color(Color::BLUE),
+        );
+
+        let pixels = surface.read_rgba().unwrap();
+        assert_eq!(pixel(&pixels, 32, 12, 20), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 4, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(&pixels, 32, 20, 20), [0, 0, 0, 0]);
+        // The clip is gone after restore
+        assert_eq!(pixel(&pixels, 32, 28, 4), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_even_odd_fill() {
+        let mut surface = surface(32, 32);
+        let rings = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 32.0, 32.0))
+            .add_rect(Rect::new(8.0, 8.0, 16.0, 16.0))
+            .build()
+            .with_fill_rule(FillRule::EvenOdd);
+        surface.draw_path(&rings, &Paint::with_color(Color::RED));
+
+        let pixels = surface.read_rgba().unwrap();
+        assert_eq!(pixel(&pixels, 32, 16, 16), [0, 0, 0, 0]);
+        assert_eq!(pixel(&pixels, 32, 4, 4), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_write_and_read_pixels() {
+        let mut surface = surface(32, 32);
+        surface.clear(Color::WHITE);
+
+        let green = [0u8, 255, 0, 255].repeat(4 * 4);
+        surface
+            .write_pixels(IRect::new(4, 4, 4, 4), PixelFormat::RGBA8, &green, 16)
+            .unwrap();
+        // Later draws blend over the written pixels
+        surface.draw_rect(
+            Rect::new(6.0, 0.0, 26.0, 32.0),
+            &Paint::with_color(Color::BLUE),
+        );
+
+        let mut pixels = vec![0u8; 4 * 4 * 4];
+        surface
+            .read_pixels(
+                IRect::new(3, 4, 4, 4),
+                PixelFormat::BGRA8_PREMULTIPLIED,
+                &mut pixels,
+                16,
+            )
+            .unwrap();
+        assert_eq!(pixel(&pixels, 4, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 4, 2, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 4, 3, 0), [255, 0, 0, 255]);
+    }
+}
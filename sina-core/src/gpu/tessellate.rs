@@ -0,0 +1,239 @@
+//! Path tessellation into device-space triangles using lyon
+
+use crate::paint::{LineCap, LineJoin};
+use crate::{FillRule, Matrix3x3, StrokeStyle};
+use bytemuck::{Pod, Zeroable};
+use lyon_path::iterator::PathIterator;
+use lyon_path::math::point;
+use lyon_path::{Event, Path as LyonPath};
+use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+
+/// Flattening tolerance in device pixels
+const TOLERANCE: f32 = 0.1;
+
+/// A device-space vertex with a premultiplied color
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct Vertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+pub(crate) type Mesh = VertexBuffers<Vertex, u32>;
+
+/// Tessellate the interior of a path, mapped through `matrix`
+pub(crate) fn fill(
+    path: &LyonPath,
+    fill_rule: FillRule,
+    matrix: &Matrix3x3,
+    color: [f32; 4],
+) -> Mesh {
+    let mut mesh = Mesh::new();
+    let options = match fill_rule {
+        FillRule::NonZero => FillOptions::non_zero(),
+        FillRule::EvenOdd => FillOptions::even_odd(),
+    }
+    .with_tolerance(user_tolerance(matrix));
+    let result = FillTessellator::new().tessellate_path(
+        path,
+        &options,
+        &mut BuffersBuilder::new(&mut mesh, |v: FillVertex| {
+            vertex(matrix, v.position().x, v.position().y, color)
+        }),
+    );
+    if let Err(err) = result {
+        log::warn!("Failed to tessellate fill: {:?}", err);
+    }
+    mesh
+}
+
+/// Tessellate the outline of a path, mapped through `matrix`
+pub(crate) fn stroke(
+    path: &LyonPath,
+    style: &StrokeStyle,
+    matrix: &Matrix3x3,
+    color: [f32; 4],
+) -> Mesh {
+    let tolerance = user_tolerance(matrix);
+    let dashed;
+    let path = if style.is_dashed() {
+        dashed = dash(path, &style.dash_array, style.dash_offset, tolerance);
+        &dashed
+    } else {
+        path
+    };
+
+    let options = StrokeOptions::default()
+        .with_tolerance(tolerance)
+        .with_line_width(style.width)
+        .with_line_cap(match style.cap {
+            LineCap::Butt => lyon_tessellation::LineCap::Butt,
+            LineCap::Round => lyon_tessellation::LineCap::Round,
+            LineCap::Square => lyon_tessellation::LineCap::Square,
+        })
+        .with_line_join(match style.join {
+            LineJoin::Miter => lyon_tessellation::LineJoin::Miter,
+            LineJoin::Round => lyon_tessellation::LineJoin::Round,
+            LineJoin::Bevel => lyon_tessellation::LineJoin::Bevel,
+        })
+        // lyon rejects miter limits below 1
+        .with_miter_limit(style.miter_limit.max(StrokeOptions::MINIMUM_MITER_LIMIT));
+
+    let mut mesh = Mesh::new();
+    let result = StrokeTessellator::new().tessellate_path(
+        path,
+        &options,
+        &mut BuffersBuilder::new(&mut mesh, |v: StrokeVertex| {
+            vertex(matrix, v.position().x, v.position().y, color)
+        }),
+    );
+    if let Err(err) = result {
+        log::warn!("Failed to tessellate stroke: {:?}", err);
+    }
+    mesh
+}
+
+/// Two triangles covering a device-space rectangle
+pub(crate) fn quad(x0: f32, y0: f32, x1: f32, y1: f32, color: [f32; 4]) -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.vertices = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+        .map(|(x, y)| Vertex {
+            position: [x, y],
+            color,
+        })
+        .to_vec();
+    mesh.indices = vec![0, 1, 2, 0, 2, 3];
+    mesh
+}
+
+fn vertex(matrix: &Matrix3x3, x: f32, y: f32, color: [f32; 4]) -> Vertex {
+    let p = matrix.transform_point2(crate::Point::new(x, y));
+    Vertex {
+        position: [p.x, p.y],
+        color,
+    }
+}
+
+/// Tolerance in user space that keeps curves within `TOLERANCE` on the device
+fn user_tolerance(matrix: &Matrix3x3) -> f32 {
+    let scale = matrix
+        .x_axis
+        .truncate()
+        .length()
+        .max(matrix.y_axis.truncate().length());
+    if scale > 0.0 {
+        TOLERANCE / scale
+    } else {
+        TOLERANCE
+    }
+}
+
+/// Split a path into dashes, restarting the pattern on every subpath
+fn dash(path: &LyonPath, pattern: &[f32], offset: f32, tolerance: f32) -> LyonPath {
+    // Odd patterns repeat to get alternating on/off pairs, as in SVG
+    let pattern: Vec<f32> = if pattern.len() % 2 == 1 {
+        pattern.iter().chain(pattern).copied().collect()
+    } else {
+        pattern.to_vec()
+    };
+    let total: f32 = pattern.iter().sum();
+
+    let mut polylines: Vec<Vec<lyon_path::math::Point>> = Vec::new();
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            Event::Begin { at } => polylines.push(vec![at]),
+            Event::Line { to, .. } => polylines.last_mut().unwrap().push(to),
+            Event::End { last, first, close } if close && last != first => {
+                polylines.last_mut().unwrap().push(first);
+            }
+            _ => {}
+        }
+    }
+
+    let mut builder = LyonPath::builder();
+    for polyline in polylines {
+        let mut index = 0;
+        let mut phase = offset.rem_euclid(total);
+        while phase >= pattern[index] {
+            phase -= pattern[index];
+            index = (index + 1) % pattern.len();
+        }
+        let mut remaining = pattern[index] - phase;
+        let mut pen_down = index % 2 == 0;
+        if pen_down {
+            builder.begin(polyline[0]);
+        }
+
+        for segment in polyline.windows(2) {
+            let (mut from, to) = (segment[0], segment[1]);
+            let mut length = (to - from).length();
+
+            // Toggle the pen at every dash boundary inside the segment
+            while length > remaining {
+                let t = remaining / length;
+                from = point(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+                length -= remaining;
+                if pen_down {
+                    builder.line_to(from);
+                    builder.end(false);
+                } else {
+                    builder.begin(from);
+                }
+                pen_down = !pen_down;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+
+            remaining -= length;
+            if pen_down {
+                builder.line_to(to);
+            }
+        }
+
+        if pen_down {
+            builder.end(false);
+        }
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_splits_lines() {
+        let mut builder = LyonPath::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(100.0, 0.0));
+        builder.end(false);
+
+        let dashed = dash(&builder.build(), &[10.0, 15.0], 5.0, TOLERANCE);
+        let starts: Vec<f32> = dashed
+            .iter()
+            .filter_map(|event| match event {
+                Event::Begin { at } => Some(at.x),
+                _ => None,
+            })
+            .collect();
+        // The offset cuts the first dash to 5 units
+        assert_eq!(starts, vec![0.0, 20.0, 45.0, 70.0, 95.0]);
+    }
+
+    #[test]
+    fn test_fill_maps_through_matrix() {
+        let mut builder = LyonPath::builder();
+        builder.add_rectangle(
+            &lyon_path::math::Box2D::new(point(0.0, 0.0), point(1.0, 1.0)),
+            lyon_path::Winding::Positive,
+        );
+        let matrix = Matrix3x3::from_scale(crate::Point::new(10.0, 20.0));
+
+        let mesh = fill(&builder.build(), FillRule::NonZero, &matrix, [1.0; 4]);
+        assert_eq!(mesh.indices.len(), 6);
+        assert!(mesh.vertices.iter().any(|v| v.position == [10.0, 20.0]));
+    }
+}
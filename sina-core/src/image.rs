@@ -0,0 +1,409 @@
+//! Raster images and the filtering used to draw them
+
+use crate::pixels::{check_access, PixelError, PixelFormat};
+use crate::{IRect, Rect};
+use rayon::prelude::*;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// How pixels are sampled when an image is scaled or transformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterQuality {
+    /// Use the nearest pixel, keeping hard pixel edges
+    Nearest,
+    /// Interpolate between the four nearest pixels
+    #[default]
+    Bilinear,
+    /// Resample with a Catmull-Rom cubic, sharper than bilinear
+    Bicubic,
+    /// Sample a pre-shrunk copy of the image, avoiding aliasing when shrinking
+    Mipmap,
+}
+
+/// Errors from loading an image
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error("Failed to decode image: {0}")]
+    Decode(#[from] ::image::ImageError),
+}
+
+/// An immutable raster image
+///
+/// Pixels are stored as premultiplied ARGB, the layout `CpuSurface` draws
+/// from. Clones share the pixel data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    data: Arc<[u32]>,
+}
+
+impl Image {
+    /// Decode an image from the contents of a PNG, JPEG or other image file
+    pub fn from_encoded(data: &[u8]) -> Result<Self, ImageError> {
+        Ok(Self::from_dynamic(::image::load_from_memory(data)?))
+    }
+
+    /// Load and decode an image file
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, ImageError> {
+        Ok(Self::from_dynamic(::image::open(path)?))
+    }
+
+    /// Create an image from pixel rows `row_bytes` apart
+    pub fn from_pixels(
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+        row_bytes: usize,
+    ) -> Result<Self, PixelError> {
+        let rect = IRect::from_size(width, height);
+        check_access(rect, width, height, data.len(), row_bytes)?;
+        if rect.is_empty() {
+            return Ok(Self::from_argb(width, height, Vec::new()));
+        }
+
+        let pixels = data
+            .chunks(row_bytes)
+            .take(height as usize)
+            .flat_map(|row| row[..width as usize * 4].chunks_exact(4))
+            .map(|pixel| {
+                let [r, g, b, a] = format.decode(pixel.try_into().unwrap());
+                u32::from_be_bytes([a, r, g, b])
+            })
+            .collect();
+        Ok(Self::from_argb(width, height, pixels))
+    }
+
+    fn from_dynamic(image: ::image::DynamicImage) -> Self {
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let pixels = rgba
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = PixelFormat::RGBA8.decode(pixel.0);
+                u32::from_be_bytes([a, r, g, b])
+            })
+            .collect();
+        Self::from_argb(width, height, pixels)
+    }
+
+    /// Wrap premultiplied ARGB pixels
+    pub(crate) fn from_argb(width: u32, height: u32, data: Vec<u32>) -> Self {
+        debug_assert_eq!(data.len(), width as usize * height as usize);
+        Self {
+            width,
+            height,
+            data: data.into(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the image's area, at the origin
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.width as f32, self.height as f32)
+    }
+
+    /// Read the pixels as tightly packed rows
+    pub fn pixels(&self, format: PixelFormat) -> Vec<u8> {
+        self.data
+            .iter()
+            .flat_map(|pixel| {
+                let [a, r, g, b] = pixel.to_be_bytes();
+                format.encode([r, g, b, a])
+            })
+            .collect()
+    }
+
+    /// Copy part of the image, or get `None` if `rect` is not inside it
+    pub fn subset(&self, rect: IRect) -> Option<Image> {
+        let (right, bottom) = (rect.right()?, rect.bottom()?);
+        if right > self.width || bottom > self.height {
+            return None;
+        }
+
+        let data = (rect.y..bottom)
+            .flat_map(|y| {
+                let start = (y * self.width + rect.x) as usize;
+                &self.data[start..start + rect.width as usize]
+            })
+            .copied()
+            .collect();
+        Some(Self::from_argb(rect.width, rect.height, data))
+    }
+
+    /// Get the premultiplied ARGB pixels
+    pub(crate) fn argb(&self) -> &[u32] {
+        &self.data
+    }
+
+    /// Halve the image with a box filter, giving the next mipmap level
+    pub(crate) fn downsample(&self) -> Image {
+        let width = self.width.div_ceil(2).max(1);
+        let height = self.height.div_ceil(2).max(1);
+        let at = |x: u32, y: u32| {
+            let (x, y) = (x.min(self.width - 1), y.min(self.height - 1));
+            unpack(self.data[(y * self.width + x) as usize])
+        };
+
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0; 4];
+                for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let pixel = at(2 * x + sx, 2 * y + sy);
+                    for (acc, channel) in sum.iter_mut().zip(pixel) {
+                        *acc += channel / 4.0;
+                    }
+                }
+                data.push(pack(sum));
+            }
+        }
+        Self::from_argb(width, height, data)
+    }
+
+    /// Resize the image with a Catmull-Rom cubic
+    ///
+    /// When shrinking, the filter is widened to cover every source pixel.
+    pub(crate) fn resize_bicubic(&self, width: u32, height: u32) -> Image {
+        if self.width == 0 || self.height == 0 || width == 0 || height == 0 {
+            return Self::from_argb(width, height, vec![0; (width * height) as usize]);
+        }
+
+        let pixels: Vec<[f32; 4]> = self.data.iter().map(|&p| unpack(p)).collect();
+        let rows: Vec<[f32; 4]> = pixels
+            .par_chunks(self.width as usize)
+            .flat_map_iter(|row| resample_line(row, width as usize))
+            .collect();
+
+        // Resample columns as rows of the transposed buffer
+        let columns = transpose(&rows, width as usize, self.height as usize);
+        let columns: Vec<[f32; 4]> = columns
+            .par_chunks(self.height as usize)
+            .flat_map_iter(|column| resample_line(column, height as usize))
+            .collect();
+        let data = transpose(&columns, height as usize, width as usize)
+            .into_iter()
+            .map(pack)
+            .collect();
+        Self::from_argb(width, height, data)
+    }
+}
+
+/// Split a nine-patch into `(src, dst)` cells
+///
+/// The corners keep their size, the edges stretch along one axis and the
+/// center stretches along both. When `dst` is smaller than the corners, they
+/// shrink proportionally. An invalid `center` draws the image as one cell.
+pub(crate) fn nine_patch_cells(image: &Image, center: IRect, dst: Rect) -> Vec<(Rect, Rect)> {
+    let (right, bottom) = match (center.right(), center.bottom()) {
+        (Some(right), Some(bottom))
+            if !center.is_empty() && right <= image.width && bottom <= image.height =>
+        {
+            (right, bottom)
+        }
+        _ => return vec![(image.bounds(), dst)],
+    };
+
+    let src_x = [0, center.x, right, image.width].map(|x| x as f32);
+    let src_y = [0, center.y, bottom, image.height].map(|y| y as f32);
+    let dst_x = stretch_edges(src_x, dst.left(), dst.right());
+    let dst_y = stretch_edges(src_y, dst.top(), dst.bottom());
+
+    let mut cells = Vec::with_capacity(9);
+    for row in 0..3 {
+        for column in 0..3 {
+            let src = Rect::from_ltrb(src_x[column], src_y[row], src_x[column + 1], src_y[row + 1]);
+            let dst = Rect::from_ltrb(dst_x[column], dst_y[row], dst_x[column + 1], dst_y[row + 1]);
+            if !src.is_empty() && !dst.is_empty() {
+                cells.push((src, dst));
+            }
+        }
+    }
+    cells
+}
+
+/// Place nine-patch edges between `start` and `end`, keeping the fixed parts
+fn stretch_edges(src: [f32; 4], start: f32, end: f32) -> [f32; 4] {
+    let (before, after) = (src[1] - src[0], src[3] - src[2]);
+    let fixed = before + after;
+    let scale = if fixed > end - start && fixed > 0.0 {
+        (end - start).max(0.0) / fixed
+    } else {
+        1.0
+    };
+    [start, start + before * scale, end - after * scale, end]
+}
+
+/// Catmull-Rom cubic weight at distance `x`
+fn catmull_rom(x: f32) -> f32 {
+    let x = x.abs();
+    if x < 1.0 {
+        1.5 * x * x * x - 2.5 * x * x + 1.0
+    } else if x < 2.0 {
+        -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+    } else {
+        0.0
+    }
+}
+
+/// Resample one row or column to `len` pixels
+fn resample_line(line: &[[f32; 4]], len: usize) -> Vec<[f32; 4]> {
+    let scale = len as f32 / line.len() as f32;
+    let filter_scale = scale.min(1.0);
+    let support = 2.0 / filter_scale;
+
+    (0..len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) / scale - 0.5;
+            let first = (center - support).floor() as isize;
+            let last = (center + support).ceil() as isize;
+
+            let mut sum = [0.0f32; 4];
+            let mut total = 0.0;
+            for j in first..=last {
+                let weight = catmull_rom((j as f32 - center) * filter_scale);
+                if weight == 0.0 {
+                    continue;
+                }
+                let pixel = line[j.clamp(0, line.len() as isize - 1) as usize];
+                for (acc, channel) in sum.iter_mut().zip(pixel) {
+                    *acc += channel * weight;
+                }
+                total += weight;
+            }
+            sum.map(|channel| channel / total)
+        })
+        .collect()
+}
+
+fn unpack(pixel: u32) -> [f32; 4] {
+    pixel.to_be_bytes().map(|channel| channel as f32)
+}
+
+fn pack(channels: [f32; 4]) -> u32 {
+    let [a, r, g, b] = channels.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    // The cubic overshoots, and premultiplied colors must not exceed alpha
+    u32::from_be_bytes([a, r.min(a), g.min(a), b.min(a)])
+}
+
+fn transpose<T: Copy>(pixels: &[T], width: usize, height: usize) -> Vec<T> {
+    (0..width)
+        .flat_map(|x| (0..height).map(move |y| pixels[y * width + x]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard(size: u32) -> Image {
+        let data = (0..size * size)
+            .map(|i| {
+                if (i % size + i / size).is_multiple_of(2) {
+                    0xFFFFFFFF
+                } else {
+                    0xFF000000
+                }
+            })
+            .collect();
+        Image::from_argb(size, size, data)
+    }
+
+    #[test]
+    fn test_from_pixels_premultiplies() {
+        let data = [255, 0, 0, 128, 0, 0, 255, 255];
+        let image = Image::from_pixels(2, 1, PixelFormat::RGBA8, &data, 8).unwrap();
+        assert_eq!(image.argb(), &[0x80800000, 0xFF0000FF]);
+        assert_eq!(image.pixels(PixelFormat::RGBA8), data);
+        assert!(Image::from_pixels(2, 2, PixelFormat::RGBA8, &data, 8).is_err());
+    }
+
+    #[test]
+    fn test_from_encoded_png() {
+        let mut png = Vec::new();
+        ::image::RgbaImage::from_pixel(3, 2, ::image::Rgba([0, 255, 0, 255]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let image = Image::from_encoded(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert!(image.argb().iter().all(|&p| p == 0xFF00FF00));
+        assert!(Image::from_encoded(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_subset_and_downsample() {
+        let image = checkerboard(4);
+        let subset = image.subset(IRect::new(1, 0, 2, 2)).unwrap();
+        assert_eq!(
+            subset.argb(),
+            &[0xFF000000, 0xFFFFFFFF, 0xFFFFFFFF, 0xFF000000]
+        );
+        assert!(image.subset(IRect::new(3, 3, 2, 2)).is_none());
+        assert!(image.subset(IRect::new(u32::MAX, 0, 2, 1)).is_none());
+        assert!(image.subset(IRect::new(0, u32::MAX, 1, 2)).is_none());
+
+        // A checkerboard averages to mid gray
+        let half = image.downsample();
+        assert_eq!((half.width(), half.height()), (2, 2));
+        assert!(half.argb().iter().all(|&p| p == 0xFF808080));
+    }
+
+    #[test]
+    fn test_resize_bicubic() {
+        let flat = Image::from_argb(3, 3, vec![0x80402010; 9]);
+        let resized = flat.resize_bicubic(7, 5);
+        assert_eq!((resized.width(), resized.height()), (7, 5));
+        assert!(resized.argb().iter().all(|&p| p == 0x80402010));
+
+        // Shrinking averages instead of picking alternate pixels
+        let shrunk = checkerboard(8).resize_bicubic(2, 2);
+        let gray = shrunk.argb()[0].to_be_bytes()[1];
+        assert!((100..=155).contains(&gray), "{gray}");
+    }
+
+    #[test]
+    fn test_nine_patch_cells() {
+        let image = Image::from_argb(9, 9, vec![0; 81]);
+        let center = IRect::new(3, 3, 3, 3);
+
+        let cells = nine_patch_cells(&image, center, Rect::new(0.0, 0.0, 30.0, 20.0));
+        assert_eq!(cells.len(), 9);
+        assert_eq!(
+            cells[0],
+            (Rect::new(0.0, 0.0, 3.0, 3.0), Rect::new(0.0, 0.0, 3.0, 3.0))
+        );
+        assert_eq!(
+            cells[4],
+            (
+                Rect::new(3.0, 3.0, 3.0, 3.0),
+                Rect::new(3.0, 3.0, 24.0, 14.0)
+            )
+        );
+
+        // Too small for the corners: they shrink and the center disappears
+        let cells = nine_patch_cells(&image, center, Rect::new(0.0, 0.0, 3.0, 3.0));
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[3].1, Rect::new(1.5, 1.5, 1.5, 1.5));
+
+        // Centers past the image, even overflowing, draw it as one cell
+        let dst = Rect::new(0.0, 0.0, 30.0, 20.0);
+        for center in [IRect::new(3, 3, 9, 3), IRect::new(3, u32::MAX, 3, 2)] {
+            assert_eq!(
+                nine_patch_cells(&image, center, dst),
+                [(image.bounds(), dst)]
+            );
+        }
+    }
+}
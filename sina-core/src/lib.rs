@@ -7,16 +7,21 @@
 //! - Colors and color spaces
 //! - Paths and path building
 //! - Paint styles and stroke settings
+//! - Raster images
 //! - Surface abstraction
+//! - SVG document rendering
 //! - CPU and GPU rendering backends
 //! - Text rendering and emoji support
 //! - Effects and filters
 
 pub mod color;
 pub mod geometry;
+pub mod image;
 pub mod paint;
 pub mod path;
+pub mod pixels;
 pub mod surface;
+pub mod svg;
 
 // Backends
 pub mod cpu;
@@ -27,18 +32,26 @@ pub mod effects;
 pub mod text;
 
 // Re-export commonly used types
+pub use crate::image::{FilterQuality, Image, ImageError};
 pub use color::Color;
-pub use geometry::{Matrix3x3, Point, Rect, Size};
-pub use paint::{BlendMode, Paint, StrokeStyle};
-pub use path::Path;
-pub use surface::{Canvas, Surface};
+pub use effects::{
+    BlurFilter, EdgeMode, Gradient, GradientStop, ImageFilter, Shader, Shadow, TileMode,
+};
+pub use geometry::{IRect, Matrix3x3, Point, RRect, Rect, Size};
+pub use paint::{BlendMode, LineCap, LineJoin, Paint, StrokeStyle};
+pub use path::{FillRule, Path, PathBuilder, PathDataError, PathEffect, PathOp};
+pub use pixels::{AlphaType, ColorType, PixelError, PixelFormat};
+pub use surface::{Canvas, ClipOp, PointMode, Surface};
+pub use svg::{SvgDocument, SvgError};
 
 // Re-export backends
 pub use cpu::CpuSurface;
-pub use gpu::GpuSurface;
+pub use gpu::{GpuError, GpuSurface};
 
 // Re-export text rendering
 pub use text::{
-    BitmapFontRenderer, BitmapGlyph, ColorEmojiRenderer, ColorLayer, Font, FontError, GlyphCache,
-    TextAlign, TextLayout, VariableFontManager, Variation, VariationAxis,
+    BitmapFontRenderer, BitmapGlyph, ColorEmojiRenderer, ColorLayer, Font, FontCollection,
+    FontError, FontFeature, FontRun, GlyphCache, LineMetrics, NamedInstance, Paragraph,
+    ParagraphStyle, TextAlign, TextDirection, TextLayout, TextStyle, VariableFontManager,
+    Variation, VariationAxis,
 };
@@ -1,11 +1,17 @@
 //! Paint styles and stroke configuration
 
+use crate::effects::{ImageFilter, Shader, Shadow};
+use crate::path::PathEffect;
 use crate::Color;
 
 /// Blend modes for compositing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Covers the Porter-Duff operators plus the separable and non-separable
+/// blend modes of the W3C Compositing and Blending specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum BlendMode {
     /// Source over destination (default)
+    #[default]
     SrcOver,
     /// Source
     Src,
@@ -13,18 +19,54 @@ pub enum BlendMode {
     Dst,
     /// Clear
     Clear,
+    /// Destination over source
+    DstOver,
+    /// Source where destination is present
+    SrcIn,
+    /// Destination where source is present
+    DstIn,
+    /// Source where destination is absent
+    SrcOut,
+    /// Destination where source is absent
+    DstOut,
+    /// Source atop destination
+    SrcAtop,
+    /// Destination atop source
+    DstAtop,
+    /// Source or destination, but not both
+    Xor,
+    /// Sum of source and destination, clamped
+    Plus,
     /// Multiply
     Multiply,
     /// Screen
     Screen,
     /// Overlay
     Overlay,
-}
-
-impl Default for BlendMode {
-    fn default() -> Self {
-        Self::SrcOver
-    }
+    /// Darker of source and destination
+    Darken,
+    /// Lighter of source and destination
+    Lighten,
+    /// Brighten destination to reflect source
+    ColorDodge,
+    /// Darken destination to reflect source
+    ColorBurn,
+    /// Multiply or screen, depending on source
+    HardLight,
+    /// Darken or lighten, depending on source
+    SoftLight,
+    /// Absolute difference of source and destination
+    Difference,
+    /// Like difference, with lower contrast
+    Exclusion,
+    /// Hue of source with saturation and luminosity of destination
+    Hue,
+    /// Saturation of source with hue and luminosity of destination
+    Saturation,
+    /// Hue and saturation of source with luminosity of destination
+    Color,
+    /// Luminosity of source with hue and saturation of destination
+    Luminosity,
 }
 
 /// Stroke line cap style
@@ -44,12 +86,44 @@ pub enum LineJoin {
 }
 
 /// Stroke style configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StrokeStyle {
     pub width: f32,
     pub cap: LineCap,
     pub join: LineJoin,
     pub miter_limit: f32,
+    /// Alternating dash and gap lengths; empty for a solid stroke
+    ///
+    /// An odd-length array is repeated to make it even, as in SVG.
+    pub dash_array: Vec<f32>,
+    /// Distance into the dash pattern at which the stroke starts
+    pub dash_offset: f32,
+}
+
+impl StrokeStyle {
+    /// Create a solid stroke with the given width
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Set the dash pattern and its starting offset
+    pub fn set_dash(&mut self, dash_array: Vec<f32>, dash_offset: f32) {
+        self.dash_array = dash_array;
+        self.dash_offset = dash_offset;
+    }
+
+    /// Check whether the dash pattern produces a dashed stroke
+    ///
+    /// Patterns with negative lengths or a zero total length are treated
+    /// as solid, matching SVG.
+    pub fn is_dashed(&self) -> bool {
+        !self.dash_array.is_empty()
+            && self.dash_array.iter().all(|&len| len >= 0.0)
+            && self.dash_array.iter().sum::<f32>() > 0.0
+    }
 }
 
 impl Default for StrokeStyle {
@@ -59,6 +133,8 @@ impl Default for StrokeStyle {
             cap: LineCap::Butt,
             join: LineJoin::Miter,
             miter_limit: 4.0,
+            dash_array: Vec::new(),
+            dash_offset: 0.0,
         }
     }
 }
@@ -70,6 +146,14 @@ pub struct Paint {
     pub anti_alias: bool,
     pub blend_mode: BlendMode,
     pub stroke: Option<StrokeStyle>,
+    /// Shader used instead of `color`; the color's alpha still sets the opacity
+    pub shader: Option<Shader>,
+    /// Filter applied to the drawn shape before it is composited
+    pub image_filter: Option<ImageFilter>,
+    /// Shadows drawn beneath (or inset within) the shape, in order
+    pub shadows: Vec<Shadow>,
+    /// Effect reshaping the geometry before it is filled or stroked
+    pub path_effect: Option<PathEffect>,
 }
 
 impl Paint {
@@ -100,6 +184,38 @@ impl Paint {
         self.stroke = Some(stroke);
     }
 
+    pub fn set_shader(&mut self, shader: impl Into<Shader>) {
+        self.shader = Some(shader.into());
+    }
+
+    pub fn clear_shader(&mut self) {
+        self.shader = None;
+    }
+
+    pub fn set_image_filter(&mut self, filter: impl Into<ImageFilter>) {
+        self.image_filter = Some(filter.into());
+    }
+
+    pub fn clear_image_filter(&mut self) {
+        self.image_filter = None;
+    }
+
+    pub fn add_shadow(&mut self, shadow: Shadow) {
+        self.shadows.push(shadow);
+    }
+
+    pub fn clear_shadows(&mut self) {
+        self.shadows.clear();
+    }
+
+    pub fn set_path_effect(&mut self, effect: PathEffect) {
+        self.path_effect = Some(effect);
+    }
+
+    pub fn clear_path_effect(&mut self) {
+        self.path_effect = None;
+    }
+
     pub fn set_fill(&mut self) {
         self.stroke = None;
     }
@@ -120,6 +236,10 @@ impl Default for Paint {
             anti_alias: true,
             blend_mode: BlendMode::SrcOver,
             stroke: None,
+            shader: None,
+            image_filter: None,
+            shadows: Vec::new(),
+            path_effect: None,
         }
     }
 }
@@ -145,4 +265,19 @@ mod tests {
         });
         assert!(paint.is_stroke());
     }
+
+    #[test]
+    fn test_dash_validation() {
+        let mut stroke = StrokeStyle::new(2.0);
+        assert!(!stroke.is_dashed());
+
+        stroke.set_dash(vec![4.0, 2.0], 1.0);
+        assert!(stroke.is_dashed());
+
+        stroke.set_dash(vec![0.0, 0.0], 0.0);
+        assert!(!stroke.is_dashed());
+
+        stroke.set_dash(vec![4.0, -1.0], 0.0);
+        assert!(!stroke.is_dashed());
+    }
 }
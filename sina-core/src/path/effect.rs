@@ -0,0 +1,520 @@
+//! Path effects that reshape geometry before it is drawn
+
+use super::{Contour, Path, PathBuilder, Segment};
+use crate::geometry::Point;
+
+/// Most pieces `Discrete` cuts a contour into before leaving it unchanged
+const MAX_DISCRETE_PIECES: f32 = 100_000.0;
+
+/// Most repeats of its pattern `Dash` cuts a path into before leaving it
+/// unchanged
+const MAX_DASH_REPEATS: f32 = 100_000.0;
+
+/// A transformation of a path's geometry, applied before it is filled or
+/// stroked
+///
+/// Set on a `Paint` with `set_path_effect`, or apply it directly with
+/// `apply`. Lengths are in the path's own units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathEffect {
+    /// Cut each sub-path into dashes
+    Dash {
+        /// Alternating dash and gap lengths; an odd-length array is repeated
+        intervals: Vec<f32>,
+        /// Distance into the pattern at which each sub-path starts
+        phase: f32,
+    },
+    /// Round the corners between straight lines
+    CornerRounding {
+        /// Distance from each corner at which the rounding starts
+        radius: f32,
+    },
+    /// Break sub-paths into short lines and jitter their ends
+    Discrete {
+        /// Approximate length of each line
+        segment_length: f32,
+        /// Largest distance a point moves, perpendicular to the path
+        deviation: f32,
+        /// Seed of the jitter, so that the same path always comes out the same
+        seed: u32,
+    },
+    /// Keep only a part of the path
+    Trim {
+        /// Start of the kept part, as a fraction (0.0-1.0) of the total length
+        start: f32,
+        /// End of the kept part, as a fraction (0.0-1.0) of the total length
+        end: f32,
+    },
+    /// Apply `inner`, then `outer` to its result
+    Compose {
+        outer: Box<PathEffect>,
+        inner: Box<PathEffect>,
+    },
+}
+
+impl PathEffect {
+    /// Create a dash effect
+    pub fn dash(intervals: Vec<f32>, phase: f32) -> Self {
+        PathEffect::Dash { intervals, phase }
+    }
+
+    /// Create a corner rounding effect
+    pub fn corner_rounding(radius: f32) -> Self {
+        PathEffect::CornerRounding { radius }
+    }
+
+    /// Create a discrete jitter effect
+    pub fn discrete(segment_length: f32, deviation: f32, seed: u32) -> Self {
+        PathEffect::Discrete {
+            segment_length,
+            deviation,
+            seed,
+        }
+    }
+
+    /// Create a trim effect keeping the part between two fractions of the
+    /// path's length
+    pub fn trim(start: f32, end: f32) -> Self {
+        PathEffect::Trim { start, end }
+    }
+
+    /// Create an effect applying `inner`, then `outer`
+    pub fn compose(outer: PathEffect, inner: PathEffect) -> Self {
+        PathEffect::Compose {
+            outer: Box::new(outer),
+            inner: Box::new(inner),
+        }
+    }
+
+    /// Apply the effect to a path
+    ///
+    /// The result keeps the path's fill rule. Effects with invalid parameters,
+    /// such as a dash pattern with a zero total length or a non-positive
+    /// radius, return the path unchanged.
+    pub fn apply(&self, path: &Path) -> Path {
+        let result = match self {
+            PathEffect::Dash { intervals, phase } => dash(path, intervals, *phase),
+            PathEffect::CornerRounding { radius } => round_corners(path, *radius),
+            PathEffect::Discrete {
+                segment_length,
+                deviation,
+                seed,
+            } => discrete(path, *segment_length, *deviation, *seed),
+            PathEffect::Trim { start, end } => trim(path, *start, *end),
+            PathEffect::Compose { outer, inner } => return outer.apply(&inner.apply(path)),
+        };
+        result.map_or_else(
+            || path.clone(),
+            |result| result.with_fill_rule(path.fill_rule()),
+        )
+    }
+}
+
+fn dash(path: &Path, intervals: &[f32], phase: f32) -> Option<Path> {
+    let total: f32 = intervals.iter().sum();
+    if intervals.is_empty() || intervals.iter().any(|&len| len < 0.0) || total <= 0.0 {
+        return None;
+    }
+    let intervals: Vec<f32> = if intervals.len() % 2 == 1 {
+        intervals.iter().chain(intervals).copied().collect()
+    } else {
+        intervals.to_vec()
+    };
+    let total: f32 = intervals.iter().sum();
+
+    let mut builder = PathBuilder::new();
+    let mut dashed_length = 0.0;
+    for contour in path.contours() {
+        let length = contour.length();
+        // Tiny intervals would make millions of dashes
+        dashed_length += length;
+        if dashed_length / total > MAX_DASH_REPEATS {
+            return None;
+        }
+
+        // Find where in the pattern the contour starts
+        let mut index = 0;
+        let mut remaining = intervals[0];
+        let mut skip = phase.rem_euclid(total);
+        while skip >= remaining {
+            skip -= remaining;
+            index = (index + 1) % intervals.len();
+            remaining = intervals[index];
+        }
+        remaining -= skip;
+
+        let mut dashes = Vec::new();
+        let mut distance = 0.0;
+        while distance < length {
+            let end = (distance + remaining).min(length);
+            // Intervals below the precision of the distance stop advancing
+            if end <= distance && remaining > 0.0 {
+                return None;
+            }
+            if index % 2 == 0 {
+                dashes.push((distance, end));
+            }
+            distance = end;
+            index = (index + 1) % intervals.len();
+            remaining = intervals[index];
+        }
+
+        // A dash running over the start of a closed contour joins the first one
+        let wraps = contour.closed
+            && dashes.len() > 1
+            && dashes[0].0 == 0.0
+            && dashes[dashes.len() - 1].1 == length;
+        let (first, rest) = match dashes.split_first() {
+            Some((&first, rest)) if wraps => (Some(first), rest),
+            _ => (None, dashes.as_slice()),
+        };
+        for &(from, to) in rest {
+            builder = contour.append_range(builder, from, to, false);
+        }
+        if let Some((from, to)) = first {
+            builder = contour.append_range(builder, from, to, true);
+        }
+    }
+    Some(builder.build())
+}
+
+fn round_corners(path: &Path, radius: f32) -> Option<Path> {
+    if radius <= 0.0 {
+        return None;
+    }
+
+    let mut builder = PathBuilder::new();
+    for contour in path.contours() {
+        let count = contour.segments.len();
+        let line = |index: usize| match contour.segments[index % count] {
+            Segment::Line(line) => Some((
+                Point::new(line.from.x, line.from.y),
+                Point::new(line.to.x, line.to.y),
+            )),
+            _ => None,
+        };
+
+        // How far each segment is cut back at its start and end
+        let mut cuts = vec![(0.0f32, 0.0f32); count];
+        let joins = if contour.closed { count } else { count - 1 };
+        for index in 0..joins {
+            let next = (index + 1) % count;
+            let (Some((from, corner)), Some((_, to))) = (line(index), line(next)) else {
+                continue;
+            };
+            let (before, after) = (corner - from, to - corner);
+            if before.perp_dot(after).abs() <= f32::EPSILON * before.length() * after.length() {
+                continue;
+            }
+            let cut = radius
+                .min(contour.lengths[index] / 2.0)
+                .min(contour.lengths[next] / 2.0);
+            cuts[index].1 = cut;
+            cuts[next].0 = cut;
+        }
+
+        let point_at = |index: usize, cut: f32, from_start: bool| {
+            let (from, to) = line(index).expect("only lines are cut");
+            let direction = (to - from).normalize_or_zero();
+            if from_start {
+                from + direction * cut
+            } else {
+                to - direction * cut
+            }
+        };
+
+        builder = builder.move_to(if cuts[0].0 > 0.0 {
+            point_at(0, cuts[0].0, true)
+        } else {
+            contour.segments[0].sample(0.0)
+        });
+        for index in 0..count {
+            let (_, cut) = cuts[index];
+            if cut <= 0.0 {
+                builder = contour.segments[index].append_to(builder);
+                continue;
+            }
+            let next = (index + 1) % count;
+            let corner = contour.segments[index].sample(1.0);
+            builder = builder
+                .line_to(point_at(index, cut, false))
+                .quad_to(corner, point_at(next, cuts[next].0, true));
+        }
+        if contour.closed {
+            builder = builder.close();
+        }
+    }
+    Some(builder.build())
+}
+
+fn discrete(path: &Path, segment_length: f32, deviation: f32, seed: u32) -> Option<Path> {
+    if segment_length <= 0.0 {
+        return None;
+    }
+
+    let mut random = XorShift::new(seed);
+    let mut builder = PathBuilder::new();
+    for contour in path.contours() {
+        let length = contour.length();
+        let pieces = (length / segment_length).round();
+        if pieces <= 1.0 || pieces > MAX_DISCRETE_PIECES {
+            builder = contour.append_to(builder);
+            continue;
+        }
+
+        // A closed contour's last point would land on its first
+        let pieces = pieces as usize;
+        let points = if contour.closed { pieces } else { pieces + 1 };
+        let step = length / pieces as f32;
+        for index in 0..points {
+            let (point, tangent) = contour.point_and_tangent_at(index as f32 * step);
+            let normal = Point::new(-tangent.y, tangent.x);
+            let point = point + normal * deviation * random.next_signed();
+            builder = if index == 0 {
+                builder.move_to(point)
+            } else {
+                builder.line_to(point)
+            };
+        }
+        if contour.closed {
+            builder = builder.close();
+        }
+    }
+    Some(builder.build())
+}
+
+fn trim(path: &Path, start: f32, end: f32) -> Option<Path> {
+    let contours = path.contours();
+    let total: f32 = contours.iter().map(Contour::length).sum();
+    let from = start.clamp(0.0, 1.0) * total;
+    let to = end.clamp(0.0, 1.0) * total;
+
+    let mut builder = PathBuilder::new();
+    let mut offset = 0.0;
+    for contour in &contours {
+        let length = contour.length();
+        let (start, end) = (offset, offset + length);
+        offset = end;
+        if from >= to || end <= from || start >= to {
+            continue;
+        }
+
+        // Contours kept whole stay closed
+        if from <= start && end <= to {
+            builder = contour.append_to(builder);
+        } else {
+            builder = contour.append_range(builder, from - start, to - start, false);
+        }
+    }
+    Some(builder.build())
+}
+
+/// Small deterministic generator for the discrete effect's jitter
+struct XorShift(u32);
+
+impl XorShift {
+    fn new(seed: u32) -> Self {
+        // Zero is a fixed point of xorshift, so mix the seed first
+        Self(seed.wrapping_mul(0x9E37_79B9) ^ 0x6D2B_79F5)
+    }
+
+    /// Get the next value in -1.0..1.0
+    fn next_signed(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rect;
+
+    fn sub_paths(path: &Path) -> Vec<(Point, Point, bool)> {
+        path.contours()
+            .iter()
+            .map(|contour| {
+                let first = contour.segments[0].sample(0.0);
+                let last = contour.segments[contour.segments.len() - 1].sample(1.0);
+                (first, last, contour.closed)
+            })
+            .collect()
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    fn assert_point(a: Point, b: Point) {
+        assert!(a.distance(b) < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_dash() {
+        let line = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .build();
+        let dashed = PathEffect::dash(vec![10.0, 5.0], 0.0).apply(&line);
+        let dashes = sub_paths(&dashed);
+        assert_eq!(dashes.len(), 7);
+        assert_point(dashes[1].0, Point::new(15.0, 0.0));
+        assert_point(dashes[6].0, Point::new(90.0, 0.0));
+        assert_point(dashes[6].1, Point::new(100.0, 0.0));
+        assert_near(dashed.length(), 70.0);
+
+        // The phase shifts the pattern, and an odd pattern repeats itself
+        let dashed = PathEffect::dash(vec![10.0, 5.0], 12.0).apply(&line);
+        assert_point(sub_paths(&dashed)[0].0, Point::new(3.0, 0.0));
+        let dashed = PathEffect::dash(vec![10.0], -15.0).apply(&line);
+        assert_point(sub_paths(&dashed)[0].1, Point::new(5.0, 0.0));
+        assert_near(dashed.length(), 50.0);
+
+        // A dash over the start of a closed contour is drawn in one piece
+        let square = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 10.0, 10.0))
+            .build();
+        let dashed = PathEffect::dash(vec![6.0, 4.0], 2.0).apply(&square);
+        let dashes = sub_paths(&dashed);
+        assert_eq!(dashes.len(), 4);
+        assert_point(dashes[3].0, Point::new(0.0, 2.0));
+        assert_point(dashes[3].1, Point::new(4.0, 0.0));
+        assert!(dashes.iter().all(|&(_, _, closed)| !closed));
+        assert_near(dashed.length(), 24.0);
+
+        // Dashes follow curves
+        let circle = Path::builder()
+            .add_circle(Point::new(0.0, 0.0), 10.0)
+            .build();
+        let dashed = PathEffect::dash(vec![1.0, 1.0], 0.0).apply(&circle);
+        assert!((dashed.length() / circle.length() - 0.5).abs() < 0.02);
+        for (first, last, _) in sub_paths(&dashed) {
+            assert!((first.length() - 10.0).abs() < 0.01);
+            assert!((last.length() - 10.0).abs() < 0.01);
+        }
+
+        // Invalid patterns, or ones too fine to draw, leave the path alone
+        for intervals in [vec![], vec![0.0, 0.0], vec![5.0, -1.0], vec![1e-10, 1e-10]] {
+            let result = PathEffect::dash(intervals, 0.0).apply(&line);
+            assert_eq!(result.to_svg_path_data(), line.to_svg_path_data());
+        }
+        // Long patterns of tiny intervals stop advancing along long paths
+        let long = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(17000.0, 0.0))
+            .build();
+        let result = PathEffect::dash(vec![5e-4; 400], 0.0).apply(&long);
+        assert_eq!(result.to_svg_path_data(), long.to_svg_path_data());
+    }
+
+    #[test]
+    fn test_corner_rounding() {
+        let square = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 10.0, 10.0))
+            .build();
+        let rounded = PathEffect::corner_rounding(2.0).apply(&square);
+        assert_eq!(
+            rounded.to_svg_path_data(),
+            "M 2 0 L 8 0 Q 10 0 10 2 L 10 8 Q 10 10 8 10 L 2 10 Q 0 10 0 8 L 0 2 Q 0 0 2 0 Z"
+        );
+        assert!(!rounded.contains(Point::new(0.2, 0.2)));
+        assert!(rounded.contains(Point::new(5.0, 5.0)));
+
+        // Open ends stay put, and the radius is limited by the lines' lengths
+        let corner = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(4.0, 0.0))
+            .line_to(Point::new(4.0, 10.0))
+            .build();
+        let rounded = PathEffect::corner_rounding(5.0).apply(&corner);
+        assert_eq!(rounded.to_svg_path_data(), "M 0 0 L 2 0 Q 4 0 4 2 L 4 10");
+
+        let line = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(5.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .build();
+        let result = PathEffect::corner_rounding(2.0).apply(&line);
+        assert_eq!(result.to_svg_path_data(), line.to_svg_path_data());
+    }
+
+    #[test]
+    fn test_discrete() {
+        let line = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .build();
+        let effect = PathEffect::discrete(10.0, 3.0, 7);
+        let jittered = effect.apply(&line);
+        let points: Vec<Point> = jittered.contours()[0]
+            .segments
+            .iter()
+            .map(|segment| segment.sample(1.0))
+            .collect();
+        assert_eq!(points.len(), 10);
+        for (index, point) in points.iter().enumerate() {
+            assert_near(point.x, (index + 1) as f32 * 10.0);
+            assert!(point.y.abs() <= 3.0);
+        }
+        assert!(points.iter().any(|point| point.y.abs() > 0.1));
+
+        // The same seed gives the same path, another seed a different one
+        let data = jittered.to_svg_path_data();
+        assert_eq!(effect.apply(&line).to_svg_path_data(), data);
+        let reseeded = PathEffect::discrete(10.0, 3.0, 8).apply(&line);
+        assert_ne!(reseeded.to_svg_path_data(), data);
+
+        // Closed contours stay closed
+        let circle = Path::builder()
+            .add_circle(Point::new(0.0, 0.0), 20.0)
+            .build();
+        let jittered = PathEffect::discrete(5.0, 1.0, 0).apply(&circle);
+        let contour = &jittered.contours()[0];
+        assert!(contour.closed);
+        assert_eq!(contour.segments.len(), 25);
+    }
+
+    #[test]
+    fn test_trim() {
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .add_rect(Rect::new(0.0, 10.0, 10.0, 10.0))
+            .build();
+
+        let trimmed = PathEffect::trim(0.1, 0.5).apply(&path);
+        assert_eq!(
+            trimmed.to_svg_path_data(),
+            "M 5 0 L 10 0 M 0 10 L 10 10 L 10 15"
+        );
+
+        // Whole contours keep their closing segment
+        let trimmed = PathEffect::trim(0.2, 1.0).apply(&path);
+        assert_eq!(sub_paths(&trimmed), sub_paths(&path)[1..]);
+        assert!(PathEffect::trim(0.6, 0.4).apply(&path).is_empty());
+        assert_near(PathEffect::trim(-1.0, 2.0).apply(&path).length(), 50.0);
+    }
+
+    #[test]
+    fn test_compose() {
+        let line = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .build();
+        let effect = PathEffect::compose(
+            PathEffect::dash(vec![10.0, 10.0], 0.0),
+            PathEffect::trim(0.0, 0.25),
+        );
+        let result = effect.apply(&line);
+        assert_eq!(sub_paths(&result).len(), 2);
+        assert_near(result.length(), 15.0);
+
+        let even_odd = line.with_fill_rule(crate::FillRule::EvenOdd);
+        assert_eq!(
+            effect.apply(&even_odd).fill_rule(),
+            crate::FillRule::EvenOdd
+        );
+    }
+}
@@ -0,0 +1,1248 @@
+//! Path representation and building
+
+use crate::geometry::{Matrix3x3, Point, RRect, Rect};
+use lyon_path::geom::{CubicBezierSegment, LineSegment, QuadraticBezierSegment};
+use lyon_path::iterator::PathIterator;
+use lyon_path::Path as LyonPath;
+use std::fmt::Write;
+use thiserror::Error;
+
+mod effect;
+mod ops;
+mod stroke;
+
+pub use effect::PathEffect;
+pub use ops::PathOp;
+pub(crate) use stroke::{to_raqote_path, to_raqote_stroke};
+
+/// Distance of cubic control points from a quarter ellipse's ends, as a
+/// fraction of its radius
+const KAPPA: f32 = 0.552_284_8;
+
+/// Maximum error when measuring or flattening curves
+const TOLERANCE: f32 = 0.01;
+
+/// Errors from parsing SVG path data
+#[derive(Debug, Error)]
+pub enum PathDataError {
+    #[error("Invalid SVG path data: {0}")]
+    Syntax(#[from] svgtypes::Error),
+}
+
+/// Rule deciding which areas enclosed by a path are inside it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where the outline winds around a point a non-zero number of times
+    #[default]
+    NonZero,
+    /// Inside where a ray from a point crosses the outline an odd number of times
+    EvenOdd,
+}
+
+/// 2D vector path
+#[derive(Debug, Clone)]
+pub struct Path {
+    inner: LyonPath,
+    fill_rule: FillRule,
+}
+
+impl Path {
+    /// Create a new path builder
+    pub fn builder() -> PathBuilder {
+        PathBuilder::new()
+    }
+
+    /// Get the underlying lyon path
+    pub fn lyon_path(&self) -> &LyonPath {
+        &self.inner
+    }
+
+    /// Wrap an already built lyon path
+    pub(crate) fn from_lyon(inner: LyonPath) -> Self {
+        Self {
+            inner,
+            fill_rule: FillRule::default(),
+        }
+    }
+
+    /// Get the rule used when filling or clipping with the path
+    pub fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
+    pub fn set_fill_rule(&mut self, fill_rule: FillRule) {
+        self.fill_rule = fill_rule;
+    }
+
+    /// Set the fill rule, returning the path
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    /// Check whether the path has no sub-paths
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().next().is_none()
+    }
+
+    /// Get the exact bounding box of the path's geometry
+    ///
+    /// Curves are bounded by their extremes rather than their control points.
+    /// Returns `None` for an empty path.
+    pub fn bounds(&self) -> Option<Rect> {
+        // Empty boxes are skipped by `Box2D::union`, so track the corners instead
+        let mut bounds: Option<(Point, Point)> = None;
+        let mut add = |other: lyon_path::math::Box2D| {
+            let (min, max) = (
+                Point::new(other.min.x, other.min.y),
+                Point::new(other.max.x, other.max.y),
+            );
+            bounds = Some(bounds.map_or((min, max), |(lo, hi)| (lo.min(min), hi.max(max))));
+        };
+        for event in self.inner.iter() {
+            match event {
+                lyon_path::Event::Begin { at } => add(lyon_path::math::Box2D::new(at, at)),
+                lyon_path::Event::Line { from, to } => add(LineSegment { from, to }.bounding_box()),
+                lyon_path::Event::Quadratic { from, ctrl, to } => {
+                    add(QuadraticBezierSegment { from, ctrl, to }.bounding_box())
+                }
+                lyon_path::Event::Cubic {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                } => add(CubicBezierSegment {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                }
+                .bounding_box()),
+                lyon_path::Event::End { .. } => {}
+            }
+        }
+        bounds.map(|(min, max)| Rect::from_ltrb(min.x, min.y, max.x, max.y))
+    }
+
+    /// Check whether a point is inside the path's filled area under its fill rule
+    ///
+    /// Open sub-paths are treated as closed, as they are when filled.
+    pub fn contains(&self, point: Point) -> bool {
+        // Sum the crossings of a ray going right from the point
+        let mut winding = 0;
+        let mut crossing = |from: lyon_path::math::Point, to: lyon_path::math::Point| {
+            let side = (to.x - from.x) * (point.y - from.y) - (point.x - from.x) * (to.y - from.y);
+            if from.y <= point.y && to.y > point.y && side > 0.0 {
+                winding += 1;
+            } else if from.y > point.y && to.y <= point.y && side < 0.0 {
+                winding -= 1;
+            }
+        };
+        for event in self.inner.iter().flattened(TOLERANCE) {
+            match event {
+                lyon_path::Event::Line { from, to } => crossing(from, to),
+                lyon_path::Event::End { last, first, .. } => crossing(last, first),
+                _ => {}
+            }
+        }
+
+        match self.fill_rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// Get the total length of every sub-path, including closing segments
+    pub fn length(&self) -> f32 {
+        self.contours().iter().map(Contour::length).sum()
+    }
+
+    /// Get the point `distance` along the path and the unit tangent there
+    ///
+    /// Sub-paths are measured one after another in order, including their
+    /// closing segments. The distance is clamped to the path's length. Returns
+    /// `None` for a path without segments.
+    pub fn point_and_tangent_at(&self, distance: f32) -> Option<(Point, Point)> {
+        let contours = self.contours();
+        let last = contours.last()?;
+
+        let mut remaining = distance.max(0.0);
+        for contour in &contours {
+            if remaining <= contour.length() {
+                return Some(contour.point_and_tangent_at(remaining));
+            }
+            remaining -= contour.length();
+        }
+        Some(last.point_and_tangent_at(last.length()))
+    }
+
+    /// Get a copy of the path mapped through `matrix`
+    pub fn transform(&self, matrix: &Matrix3x3) -> Path {
+        let transform = lyon_path::math::Transform::new(
+            matrix.x_axis.x,
+            matrix.x_axis.y,
+            matrix.y_axis.x,
+            matrix.y_axis.y,
+            matrix.z_axis.x,
+            matrix.z_axis.y,
+        );
+        Path {
+            inner: self.inner.clone().transformed(&transform),
+            fill_rule: self.fill_rule,
+        }
+    }
+
+    /// Split the path into sub-paths of line and curve segments
+    ///
+    /// Closed sub-paths end with a line back to their start when they do not
+    /// already end there. Sub-paths without segments are dropped.
+    pub(crate) fn contours(&self) -> Vec<Contour> {
+        let mut contours = Vec::new();
+        let mut segments = Vec::new();
+        for event in self.inner.iter() {
+            match event {
+                lyon_path::Event::Begin { .. } => {}
+                lyon_path::Event::Line { from, to } => {
+                    segments.push(Segment::Line(LineSegment { from, to }))
+                }
+                lyon_path::Event::Quadratic { from, ctrl, to } => {
+                    segments.push(Segment::Quadratic(QuadraticBezierSegment {
+                        from,
+                        ctrl,
+                        to,
+                    }))
+                }
+                lyon_path::Event::Cubic {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                } => segments.push(Segment::Cubic(CubicBezierSegment {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                })),
+                lyon_path::Event::End { last, first, close } => {
+                    if close && last != first {
+                        segments.push(Segment::Line(LineSegment {
+                            from: last,
+                            to: first,
+                        }));
+                    }
+                    if !segments.is_empty() {
+                        contours.push(Contour::new(std::mem::take(&mut segments), close));
+                    }
+                }
+            }
+        }
+        contours
+    }
+
+    /// Parse the path data of an SVG `d` attribute, such as `"M10 10 h20 v20 z"`
+    ///
+    /// Every command is supported, absolute and relative. Smooth curves are
+    /// expanded to their full control points and elliptical arcs are converted
+    /// to cubic curves.
+    pub fn from_svg_path_data(data: &str) -> Result<Path, PathDataError> {
+        match parse_svg_path_data(data) {
+            (_, Some(error)) => Err(error.into()),
+            (path, None) => Ok(path),
+        }
+    }
+
+    /// Serialize the path as SVG path data using absolute commands
+    ///
+    /// The result can be read back with `from_svg_path_data`.
+    pub fn to_svg_path_data(&self) -> String {
+        let mut data = String::new();
+        let mut push = |command: char, points: &[lyon_path::math::Point]| {
+            if !data.is_empty() {
+                data.push(' ');
+            }
+            data.push(command);
+            for point in points {
+                let _ = write!(data, " {} {}", point.x, point.y);
+            }
+        };
+
+        for event in self.inner.iter() {
+            match event {
+                lyon_path::Event::Begin { at } => push('M', &[at]),
+                lyon_path::Event::Line { to, .. } => push('L', &[to]),
+                lyon_path::Event::Quadratic { ctrl, to, .. } => push('Q', &[ctrl, to]),
+                lyon_path::Event::Cubic {
+                    ctrl1, ctrl2, to, ..
+                } => push('C', &[ctrl1, ctrl2, to]),
+                lyon_path::Event::End { close: true, .. } => push('Z', &[]),
+                lyon_path::Event::End { close: false, .. } => {}
+            }
+        }
+        data
+    }
+}
+
+/// Path builder for constructing paths
+///
+/// Sub-paths left open are ended when the next one starts or the path is
+/// built; drawing without a `move_to` starts a sub-path at the current point.
+pub struct PathBuilder {
+    builder: lyon_path::path::Builder,
+
+    /// Whether a sub-path has been started and not yet ended
+    in_subpath: bool,
+
+    /// End point of the last segment
+    current: Point,
+
+    /// Start of the current sub-path, where `close` returns to
+    start: Point,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self {
+            builder: LyonPath::builder(),
+            in_subpath: false,
+            current: Point::ZERO,
+            start: Point::ZERO,
+        }
+    }
+
+    /// Move to a point without drawing
+    pub fn move_to(mut self, point: Point) -> Self {
+        self.end_subpath(false);
+        self.builder.begin(to_lyon(point));
+        self.in_subpath = true;
+        self.current = point;
+        self.start = point;
+        self
+    }
+
+    /// Draw a line to a point
+    pub fn line_to(mut self, point: Point) -> Self {
+        self.ensure_subpath();
+        self.builder.line_to(to_lyon(point));
+        self.current = point;
+        self
+    }
+
+    /// Draw a quadratic Bezier curve
+    pub fn quad_to(mut self, control: Point, to: Point) -> Self {
+        self.ensure_subpath();
+        self.builder
+            .quadratic_bezier_to(to_lyon(control), to_lyon(to));
+        self.current = to;
+        self
+    }
+
+    /// Draw a cubic Bezier curve
+    pub fn cubic_to(mut self, control1: Point, control2: Point, to: Point) -> Self {
+        self.ensure_subpath();
+        self.builder
+            .cubic_bezier_to(to_lyon(control1), to_lyon(control2), to_lyon(to));
+        self.current = to;
+        self
+    }
+
+    /// Close the current sub-path
+    pub fn close(mut self) -> Self {
+        self.end_subpath(true);
+        self.current = self.start;
+        self
+    }
+
+    /// Draw a circular arc of `radius` tangent to the lines from the current
+    /// point to `p1` and from `p1` to `p2`
+    ///
+    /// A line joins the current point to the start of the arc. When the points
+    /// are collinear or the radius is zero, this draws a line to `p1`.
+    pub fn arc_to(mut self, p1: Point, p2: Point, radius: f32) -> Self {
+        self.ensure_subpath();
+        let to_start = (self.current - p1).normalize_or_zero();
+        let to_end = (p2 - p1).normalize_or_zero();
+        let sin = to_start.perp_dot(to_end);
+        if radius <= 0.0 || sin.abs() < 1e-6 || to_start == Point::ZERO || to_end == Point::ZERO {
+            return self.line_to(p1);
+        }
+
+        // Distance from the corner to where the arc touches each line
+        let half_angle = to_start.dot(to_end).clamp(-1.0, 1.0).acos() / 2.0;
+        let distance = radius / half_angle.tan();
+        let start = p1 + to_start * distance;
+        let end = p1 + to_end * distance;
+
+        // Turning left or right at the corner sets the sweep direction
+        self.line_to(start)
+            .svg_arc_to(Point::splat(radius), 0.0, false, sin < 0.0, end)
+    }
+
+    /// Draw an elliptical arc to `to`, as the SVG `A` command does
+    ///
+    /// `radii` are the ellipse's x and y radii and `x_rotation` its rotation in
+    /// degrees. The flags pick one of the four arcs through the two points.
+    /// Radii too small to reach `to` are scaled up; a zero radius draws a line.
+    pub fn svg_arc_to(
+        mut self,
+        radii: Point,
+        x_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        to: Point,
+    ) -> Self {
+        self.ensure_subpath();
+        if to == self.current {
+            return self;
+        }
+        if radii.x == 0.0 || radii.y == 0.0 {
+            return self.line_to(to);
+        }
+
+        // Find the center parameterization, then emit at most a quarter turn
+        // per cubic with the usual 4/3 tan(θ/4) control length
+        let arc = lyon_path::geom::Arc::from_svg_arc(&lyon_path::geom::SvgArc {
+            from: to_lyon(self.current),
+            to: to_lyon(to),
+            radii: lyon_path::math::vector(radii.x.abs(), radii.y.abs()),
+            x_rotation: lyon_path::math::Angle::degrees(x_rotation),
+            flags: lyon_path::ArcFlags { large_arc, sweep },
+        });
+        let ellipse = Ellipse {
+            center: Point::new(arc.center.x, arc.center.y),
+            radii: Point::new(arc.radii.x, arc.radii.y),
+            rotation: Point::from_angle(arc.x_rotation.radians),
+        };
+        self.elliptical_arc(
+            &ellipse,
+            arc.start_angle.radians,
+            arc.sweep_angle.radians,
+            to,
+        );
+        self
+    }
+
+    /// Draw an arc of the ellipse filling `oval`, joined to the current point
+    /// by a line
+    ///
+    /// Angles are in degrees, clockwise from the positive x axis. Without a
+    /// current sub-path, the arc starts a new one. Sweeps past a full turn
+    /// are clamped to one.
+    pub fn arc_to_oval(mut self, oval: Rect, start_angle: f32, sweep_angle: f32) -> Self {
+        let sweep_angle = sweep_angle.clamp(-360.0, 360.0);
+        let ellipse = Ellipse {
+            center: oval.center(),
+            radii: Point::new(oval.width / 2.0, oval.height / 2.0),
+            rotation: Point::X,
+        };
+        let (start, sweep) = (start_angle.to_radians(), sweep_angle.to_radians());
+        let from = ellipse.point(start);
+        self = if self.in_subpath {
+            self.line_to(from)
+        } else {
+            self.move_to(from)
+        };
+        self.elliptical_arc(&ellipse, start, sweep, ellipse.point(start + sweep));
+        self
+    }
+
+    /// Add an open arc of the ellipse filling `oval` as a new sub-path
+    ///
+    /// Angles are in degrees, clockwise from the positive x axis.
+    pub fn add_arc(mut self, oval: Rect, start_angle: f32, sweep_angle: f32) -> Self {
+        self.end_subpath(false);
+        self.arc_to_oval(oval, start_angle, sweep_angle)
+    }
+
+    /// Add a closed rectangle, clockwise from the top-left corner
+    pub fn add_rect(self, rect: Rect) -> Self {
+        self.move_to(Point::new(rect.left(), rect.top()))
+            .line_to(Point::new(rect.right(), rect.top()))
+            .line_to(Point::new(rect.right(), rect.bottom()))
+            .line_to(Point::new(rect.left(), rect.bottom()))
+            .close()
+    }
+
+    /// Add a closed ellipse filling `rect`, clockwise from the right-middle point
+    pub fn add_oval(self, rect: Rect) -> Self {
+        let center = rect.center();
+        let (rx, ry) = (rect.width / 2.0, rect.height / 2.0);
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        let Point { x: cx, y: cy } = center;
+        self.move_to(Point::new(cx + rx, cy))
+            .cubic_to(
+                Point::new(cx + rx, cy + ky),
+                Point::new(cx + kx, cy + ry),
+                Point::new(cx, cy + ry),
+            )
+            .cubic_to(
+                Point::new(cx - kx, cy + ry),
+                Point::new(cx - rx, cy + ky),
+                Point::new(cx - rx, cy),
+            )
+            .cubic_to(
+                Point::new(cx - rx, cy - ky),
+                Point::new(cx - kx, cy - ry),
+                Point::new(cx, cy - ry),
+            )
+            .cubic_to(
+                Point::new(cx + kx, cy - ry),
+                Point::new(cx + rx, cy - ky),
+                Point::new(cx + rx, cy),
+            )
+            .close()
+    }
+
+    /// Add a closed circle
+    pub fn add_circle(self, center: Point, radius: f32) -> Self {
+        self.add_oval(Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        ))
+    }
+
+    /// Add a closed rounded rectangle, clockwise from the top-left corner
+    pub fn add_rrect(self, rrect: RRect) -> Self {
+        let rect = rrect.rect();
+        if rrect.is_rect() {
+            return self.add_rect(rect);
+        }
+        let [top_left, top_right, bottom_right, bottom_left] = rrect.radii();
+        let (l, t, r, b) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+
+        // Each corner is a quarter ellipse from `from` to `to` around `corner`
+        let corner = |builder: Self, from: Point, corner: Point, to: Point| {
+            if from == to {
+                return builder;
+            }
+            builder.cubic_to(
+                from + (corner - from) * KAPPA,
+                to + (corner - to) * KAPPA,
+                to,
+            )
+        };
+
+        let mut builder = self.move_to(Point::new(l + top_left.x, t));
+        builder = builder.line_to(Point::new(r - top_right.x, t));
+        builder = corner(
+            builder,
+            Point::new(r - top_right.x, t),
+            Point::new(r, t),
+            Point::new(r, t + top_right.y),
+        );
+        builder = builder.line_to(Point::new(r, b - bottom_right.y));
+        builder = corner(
+            builder,
+            Point::new(r, b - bottom_right.y),
+            Point::new(r, b),
+            Point::new(r - bottom_right.x, b),
+        );
+        builder = builder.line_to(Point::new(l + bottom_left.x, b));
+        builder = corner(
+            builder,
+            Point::new(l + bottom_left.x, b),
+            Point::new(l, b),
+            Point::new(l, b - bottom_left.y),
+        );
+        builder = builder.line_to(Point::new(l, t + top_left.y));
+        builder = corner(
+            builder,
+            Point::new(l, t + top_left.y),
+            Point::new(l, t),
+            Point::new(l + top_left.x, t),
+        );
+        builder.close()
+    }
+
+    /// Add a polygon through `points`, closing it if `close` is set
+    pub fn add_polygon(mut self, points: &[Point], close: bool) -> Self {
+        let Some((&first, rest)) = points.split_first() else {
+            return self;
+        };
+        self = self.move_to(first);
+        for &point in rest {
+            self = self.line_to(point);
+        }
+        if close {
+            self = self.close();
+        }
+        self
+    }
+
+    /// Add a closed star with `points` tips, the first pointing straight up
+    ///
+    /// Tips lie on the outer radius and the notches between them on the inner
+    /// radius. Fewer than two points adds nothing.
+    pub fn add_star(
+        self,
+        center: Point,
+        points: u32,
+        outer_radius: f32,
+        inner_radius: f32,
+    ) -> Self {
+        if points < 2 {
+            return self;
+        }
+        let vertices: Vec<Point> = (0..points * 2)
+            .map(|i| {
+                let angle =
+                    i as f32 * std::f32::consts::PI / points as f32 - std::f32::consts::FRAC_PI_2;
+                let radius = if i.is_multiple_of(2) {
+                    outer_radius
+                } else {
+                    inner_radius
+                };
+                center + Point::from_angle(angle) * radius
+            })
+            .collect();
+        self.add_polygon(&vertices, true)
+    }
+
+    /// Build the final path
+    pub fn build(mut self) -> Path {
+        self.end_subpath(false);
+        Path::from_lyon(self.builder.build())
+    }
+
+    /// Emit cubics along an ellipse from the current point to `to`
+    ///
+    /// Each cubic covers at most a quarter turn, with the usual 4/3 tan(θ/4)
+    /// control length.
+    fn elliptical_arc(&mut self, ellipse: &Ellipse, start_angle: f32, sweep_angle: f32, to: Point) {
+        let segments = (sweep_angle.abs() / std::f32::consts::FRAC_PI_2 - 1e-4)
+            .ceil()
+            .max(1.0) as usize;
+        let step = sweep_angle / segments as f32;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        for i in 0..segments {
+            let a1 = start_angle + step * i as f32;
+            let a2 = a1 + step;
+            let end = if i + 1 == segments {
+                to
+            } else {
+                ellipse.point(a2)
+            };
+            self.builder.cubic_bezier_to(
+                to_lyon(ellipse.point(a1) + ellipse.derivative(a1) * k),
+                to_lyon(end - ellipse.derivative(a2) * k),
+                to_lyon(end),
+            );
+        }
+        self.current = to;
+    }
+
+    /// Start a sub-path at the current point unless one is open
+    fn ensure_subpath(&mut self) {
+        if !self.in_subpath {
+            self.builder.begin(to_lyon(self.current));
+            self.in_subpath = true;
+            self.start = self.current;
+        }
+    }
+
+    fn end_subpath(&mut self, close: bool) {
+        if self.in_subpath {
+            self.builder.end(close);
+            self.in_subpath = false;
+        }
+    }
+}
+
+/// An ellipse, rotated so that its x axis points along `rotation`
+struct Ellipse {
+    center: Point,
+    radii: Point,
+    rotation: Point,
+}
+
+impl Ellipse {
+    fn point(&self, angle: f32) -> Point {
+        let local = Point::new(self.radii.x * angle.cos(), self.radii.y * angle.sin());
+        self.center + self.rotation.rotate(local)
+    }
+
+    fn derivative(&self, angle: f32) -> Point {
+        let local = Point::new(-self.radii.x * angle.sin(), self.radii.y * angle.cos());
+        self.rotation.rotate(local)
+    }
+}
+
+/// A sub-path of a `Path`, with the length of each segment measured
+#[derive(Debug, Clone)]
+pub(crate) struct Contour {
+    pub segments: Vec<Segment>,
+    pub lengths: Vec<f32>,
+    pub closed: bool,
+}
+
+impl Contour {
+    pub fn new(segments: Vec<Segment>, closed: bool) -> Self {
+        let lengths = segments.iter().map(Segment::length).collect();
+        Self {
+            segments,
+            lengths,
+            closed,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths.iter().sum()
+    }
+
+    /// Get the point `distance` along the contour and the unit tangent there
+    pub fn point_and_tangent_at(&self, distance: f32) -> (Point, Point) {
+        let mut remaining = distance.max(0.0);
+        for (segment, &length) in self.segments.iter().zip(&self.lengths) {
+            if remaining <= length {
+                let t = segment.t_at_length(remaining, length);
+                return (segment.sample(t), segment.tangent(t));
+            }
+            remaining -= length;
+        }
+        let last = self.segments[self.segments.len() - 1];
+        (last.sample(1.0), last.tangent(1.0))
+    }
+
+    /// Append the part of the contour between two distances along it
+    ///
+    /// The part starts a new sub-path, unless `connect` is set, in which case
+    /// it continues the builder's current one.
+    pub fn append_range(
+        &self,
+        mut builder: PathBuilder,
+        from: f32,
+        to: f32,
+        connect: bool,
+    ) -> PathBuilder {
+        let mut started = connect;
+        let mut offset = 0.0;
+        for (segment, &length) in self.segments.iter().zip(&self.lengths) {
+            let (start, end) = (offset, offset + length);
+            offset = end;
+            if start > to {
+                break;
+            }
+            if end < from || (end == from && to > from) {
+                continue;
+            }
+
+            let t0 = segment.t_at_length(from - start, length);
+            let t1 = segment.t_at_length(to - start, length);
+            let part = segment.split(t0..t1);
+            if !started {
+                builder = builder.move_to(part.sample(0.0));
+                started = true;
+            }
+            builder = part.append_to(builder);
+            if end >= to {
+                break;
+            }
+        }
+        builder
+    }
+
+    /// Append the whole contour as a new sub-path
+    pub fn append_to(&self, mut builder: PathBuilder) -> PathBuilder {
+        builder = builder.move_to(self.segments[0].sample(0.0));
+        for segment in &self.segments {
+            builder = segment.append_to(builder);
+        }
+        if self.closed {
+            builder = builder.close();
+        }
+        builder
+    }
+}
+
+/// A line or curve of a `Path`
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Segment {
+    Line(LineSegment<f32>),
+    Quadratic(QuadraticBezierSegment<f32>),
+    Cubic(CubicBezierSegment<f32>),
+}
+
+impl Segment {
+    pub fn length(&self) -> f32 {
+        match self {
+            Segment::Line(line) => line.length(),
+            Segment::Quadratic(curve) => curve.length(),
+            Segment::Cubic(curve) => curve.approximate_length(TOLERANCE),
+        }
+    }
+
+    pub fn sample(&self, t: f32) -> Point {
+        let point = match self {
+            Segment::Line(line) => line.sample(t),
+            Segment::Quadratic(curve) => curve.sample(t),
+            Segment::Cubic(curve) => curve.sample(t),
+        };
+        Point::new(point.x, point.y)
+    }
+
+    /// Get the unit direction of travel at `t`
+    pub fn tangent(&self, t: f32) -> Point {
+        let derivative = |t: f32| {
+            let vector = match self {
+                Segment::Line(line) => line.to_vector(),
+                Segment::Quadratic(curve) => curve.derivative(t),
+                Segment::Cubic(curve) => curve.derivative(t),
+            };
+            Point::new(vector.x, vector.y).normalize_or_zero()
+        };
+
+        // Control points on top of an end point leave no derivative there
+        let tangent = derivative(t);
+        if tangent != Point::ZERO {
+            return tangent;
+        }
+        let nudged = derivative(if t < 0.5 { t + 1e-3 } else { t - 1e-3 });
+        if nudged != Point::ZERO {
+            return nudged;
+        }
+        (self.sample(1.0) - self.sample(0.0)).normalize_or_zero()
+    }
+
+    /// Find the parameter `distance` along a segment of the given length
+    pub fn t_at_length(&self, distance: f32, length: f32) -> f32 {
+        if length <= 0.0 {
+            return 0.0;
+        }
+        if let Segment::Line(_) = self {
+            return (distance / length).clamp(0.0, 1.0);
+        }
+
+        // Curves are not parameterized by length, so search for it
+        let (mut low, mut high) = (0.0f32, 1.0f32);
+        for _ in 0..24 {
+            let t = (low + high) / 2.0;
+            if self.split(0.0..t).length() < distance {
+                low = t;
+            } else {
+                high = t;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// Draw the segment from the builder's current point
+    pub fn append_to(&self, builder: PathBuilder) -> PathBuilder {
+        let point = |p: lyon_path::math::Point| Point::new(p.x, p.y);
+        match self {
+            Segment::Line(line) => builder.line_to(point(line.to)),
+            Segment::Quadratic(curve) => builder.quad_to(point(curve.ctrl), point(curve.to)),
+            Segment::Cubic(curve) => {
+                builder.cubic_to(point(curve.ctrl1), point(curve.ctrl2), point(curve.to))
+            }
+        }
+    }
+
+    /// Get the part of the segment between two parameters
+    pub fn split(&self, range: std::ops::Range<f32>) -> Segment {
+        match self {
+            Segment::Line(line) => Segment::Line(line.split_range(range)),
+            Segment::Quadratic(curve) => Segment::Quadratic(curve.split_range(range)),
+            Segment::Cubic(curve) => Segment::Cubic(curve.split_range(range)),
+        }
+    }
+}
+
+/// Build a path from SVG path data, keeping everything before the first error
+///
+/// The error, if any, is returned alongside the partial path, which is how
+/// SVG renderers treat malformed data.
+pub(crate) fn parse_svg_path_data(data: &str) -> (Path, Option<svgtypes::Error>) {
+    use svgtypes::SimplePathSegment as Segment;
+
+    let point = |x: f64, y: f64| Point::new(x as f32, y as f32);
+    let mut builder = PathBuilder::new();
+    for segment in svgtypes::SimplifyingPathParser::from(data) {
+        let segment = match segment {
+            Ok(segment) => segment,
+            Err(error) => return (builder.build(), Some(error)),
+        };
+        builder = match segment {
+            Segment::MoveTo { x, y } => builder.move_to(point(x, y)),
+            Segment::LineTo { x, y } => builder.line_to(point(x, y)),
+            Segment::Quadratic { x1, y1, x, y } => builder.quad_to(point(x1, y1), point(x, y)),
+            Segment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => builder.cubic_to(point(x1, y1), point(x2, y2), point(x, y)),
+            Segment::ClosePath => builder.close(),
+        };
+    }
+    (builder.build(), None)
+}
+
+fn to_lyon(point: Point) -> lyon_path::math::Point {
+    lyon_path::geom::point(point.x, point.y)
+}
+
+impl Default for PathBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_builder() {
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .line_to(Point::new(100.0, 100.0))
+            .close()
+            .build();
+
+        assert!(path.lyon_path().iter().count() > 0);
+    }
+
+    #[test]
+    fn test_open_subpaths() {
+        // Neither sub-path is closed; the second starts implicitly
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .move_to(Point::new(0.0, 10.0))
+            .line_to(Point::new(10.0, 10.0))
+            .close()
+            .line_to(Point::new(5.0, 20.0))
+            .build();
+
+        let events: Vec<_> = path.lyon_path().iter().collect();
+        assert!(matches!(
+            events[2],
+            lyon_path::Event::End { close: false, .. }
+        ));
+        assert!(matches!(
+            events[5],
+            lyon_path::Event::End { close: true, .. }
+        ));
+        // Drawing after `close` continues from the closed sub-path's start
+        assert!(matches!(events[6], lyon_path::Event::Begin { at } if at.y == 10.0));
+        assert_eq!(events.len(), 9);
+    }
+
+    #[test]
+    fn test_svg_path_data() {
+        // Relative commands, H/V, smooth curves and an arc
+        let path = Path::from_svg_path_data(
+            "M10 10 h20 v20 H10 Z m40 0 c0 10 10 10 10 0 s10 -10 10 0 q5 5 10 0 t10 0 a5 5 0 0 1 10 0",
+        )
+        .unwrap();
+        let events: Vec<_> = path.lyon_path().iter().collect();
+        assert!(matches!(events[0], lyon_path::Event::Begin { at } if at.x == 10.0));
+        assert!(
+            matches!(events[2], lyon_path::Event::Line { to, .. } if to.x == 30.0 && to.y == 30.0)
+        );
+        assert!(matches!(
+            events[4],
+            lyon_path::Event::End { close: true, .. }
+        ));
+        // `m` after `z` is relative to the closed sub-path's start
+        assert!(
+            matches!(events[5], lyon_path::Event::Begin { at } if at.x == 50.0 && at.y == 10.0)
+        );
+        // `s` mirrors the previous control point
+        assert!(matches!(
+            events[7],
+            lyon_path::Event::Cubic { ctrl1, to, .. } if ctrl1.x == 60.0 && ctrl1.y == 0.0 && to.x == 70.0
+        ));
+        // `t` mirrors the previous quadratic control point
+        assert!(matches!(
+            events[9],
+            lyon_path::Event::Quadratic { ctrl, to, .. } if ctrl.x == 85.0 && ctrl.y == 5.0 && to.x == 90.0
+        ));
+        // The arc ends where it should, as one or more cubics
+        let Some(lyon_path::Event::End { last, .. }) = events.last() else {
+            panic!("path is not ended");
+        };
+        assert!((last.x - 100.0).abs() < 1e-3 && (last.y - 10.0).abs() < 1e-3);
+
+        assert!(Path::from_svg_path_data("")
+            .unwrap()
+            .lyon_path()
+            .iter()
+            .next()
+            .is_none());
+        assert!(Path::from_svg_path_data("M10 10 L20").is_err());
+        assert!(Path::from_svg_path_data("X").is_err());
+    }
+
+    #[test]
+    fn test_svg_path_data_round_trip() {
+        let path = Path::builder()
+            .move_to(Point::new(0.5, -1.0))
+            .line_to(Point::new(10.0, 0.0))
+            .quad_to(Point::new(15.0, 5.0), Point::new(10.0, 10.0))
+            .cubic_to(
+                Point::new(5.0, 15.0),
+                Point::new(0.0, 10.0),
+                Point::new(0.0, 5.0),
+            )
+            .close()
+            .move_to(Point::new(20.0, 20.0))
+            .line_to(Point::new(30.0, 20.0))
+            .build();
+
+        let data = path.to_svg_path_data();
+        assert_eq!(
+            data,
+            "M 0.5 -1 L 10 0 Q 15 5 10 10 C 5 15 0 10 0 5 Z M 20 20 L 30 20"
+        );
+        let parsed = Path::from_svg_path_data(&data).unwrap();
+        assert!(parsed.lyon_path().iter().eq(path.lyon_path().iter()));
+        assert_eq!(parsed.to_svg_path_data(), data);
+    }
+
+    /// Get the end point of every segment
+    fn end_points(path: &Path) -> Vec<Point> {
+        path.lyon_path()
+            .iter()
+            .filter_map(|event| match event {
+                lyon_path::Event::Begin { at } => Some(Point::new(at.x, at.y)),
+                lyon_path::Event::Line { to, .. }
+                | lyon_path::Event::Quadratic { to, .. }
+                | lyon_path::Event::Cubic { to, .. } => Some(Point::new(to.x, to.y)),
+                lyon_path::Event::End { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Check that every flattened point of `path` is `radius` from `center`
+    fn assert_on_circle(path: &Path, center: Point, radius: f32) {
+        for event in path.lyon_path().iter().flattened(0.01) {
+            if let lyon_path::Event::Line { to, .. } = event {
+                let distance = Point::new(to.x, to.y).distance(center);
+                assert!((distance - radius).abs() < 0.01, "{distance} != {radius}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_arcs() {
+        // A tangent arc rounding a right-angled corner
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .arc_to(Point::new(20.0, 0.0), Point::new(20.0, 20.0), 5.0)
+            .build();
+        let points = end_points(&path);
+        assert!(points[1].distance(Point::new(15.0, 0.0)) < 1e-4);
+        assert!(points.last().unwrap().distance(Point::new(20.0, 5.0)) < 1e-4);
+        let arc = Path::builder()
+            .move_to(Point::new(15.0, 0.0))
+            .arc_to(Point::new(20.0, 0.0), Point::new(20.0, 20.0), 5.0)
+            .build();
+        assert_on_circle(&arc, Point::new(15.0, 5.0), 5.0);
+
+        // Collinear points draw a line
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .arc_to(Point::new(10.0, 0.0), Point::new(20.0, 0.0), 5.0)
+            .build();
+        assert_eq!(end_points(&path), [Point::ZERO, Point::new(10.0, 0.0)]);
+
+        // A half circle below the chord (y points down), and a zero radius line
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .svg_arc_to(Point::splat(10.0), 0.0, false, false, Point::new(20.0, 0.0))
+            .build();
+        assert_on_circle(&path, Point::new(10.0, 0.0), 10.0);
+        let below = path
+            .lyon_path()
+            .iter()
+            .flattened(0.01)
+            .any(|event| matches!(event, lyon_path::Event::Line { to, .. } if to.y > 9.0));
+        assert!(below);
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .svg_arc_to(Point::ZERO, 0.0, true, true, Point::new(5.0, 5.0))
+            .build();
+        assert_eq!(end_points(&path).len(), 2);
+
+        // Oval arcs run clockwise from the x axis, joined to the current point
+        let oval = Rect::new(0.0, 0.0, 20.0, 20.0);
+        let path = Path::builder()
+            .move_to(Point::new(10.0, 10.0))
+            .arc_to_oval(oval, 0.0, 90.0)
+            .close()
+            .build();
+        let points = end_points(&path);
+        assert_eq!(points[1], Point::new(20.0, 10.0));
+        assert!(points[2].distance(Point::new(10.0, 20.0)) < 1e-4);
+        assert!(path.contains(Point::new(15.0, 15.0)));
+        assert!(!path.contains(Point::new(15.0, 5.0)));
+
+        let arc = Path::builder()
+            .move_to(Point::new(50.0, 50.0))
+            .add_arc(oval, -90.0, -270.0)
+            .build();
+        assert_eq!(arc.contours().len(), 1);
+        assert_on_circle(&arc, Point::new(10.0, 10.0), 10.0);
+        let segments = &arc.contours()[0].segments;
+        assert!(segments[0].sample(0.0).distance(Point::new(10.0, 0.0)) < 1e-4);
+        assert!(segments[2].sample(1.0).distance(Point::new(20.0, 10.0)) < 1e-4);
+        assert!((arc.length() - 15.0 * std::f32::consts::PI).abs() < 0.01);
+
+        // Sweeps beyond a full turn stop after one
+        for sweep in [720.0, f32::INFINITY, -1e30] {
+            let arc = Path::builder().add_arc(oval, 0.0, sweep).build();
+            assert_eq!(arc.contours()[0].segments.len(), 4);
+            assert!((arc.length() - 20.0 * std::f32::consts::PI).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_shape_builders() {
+        let circle = Path::builder()
+            .add_circle(Point::new(10.0, 10.0), 8.0)
+            .build();
+        for event in circle.lyon_path().iter().flattened(0.01) {
+            if let lyon_path::Event::Line { to, .. } = event {
+                // The cubic approximation stays within 0.03% of the radius
+                let distance = Point::new(to.x, to.y).distance(Point::new(10.0, 10.0));
+                assert!((distance - 8.0).abs() < 8.0 * 3e-4);
+            }
+        }
+
+        let rect = Path::builder()
+            .add_rect(Rect::new(1.0, 2.0, 3.0, 4.0))
+            .build();
+        assert_eq!(rect.to_svg_path_data(), "M 1 2 L 4 2 L 4 6 L 1 6 Z");
+
+        // Square corners add no curves
+        let rrect = RRect::from_radii(
+            Rect::new(0.0, 0.0, 20.0, 10.0),
+            [
+                Point::splat(4.0),
+                Point::ZERO,
+                Point::splat(4.0),
+                Point::ZERO,
+            ],
+        );
+        let data = Path::builder().add_rrect(rrect).build().to_svg_path_data();
+        assert_eq!(data.matches('C').count(), 2);
+        assert!(data.starts_with("M 4 0 L 20 0 L 20 6 C"));
+        assert!(data.contains("L 0 10 L 0 4 C"));
+
+        let polygon = Path::builder()
+            .add_polygon(
+                &[Point::ZERO, Point::new(5.0, 0.0), Point::new(0.0, 5.0)],
+                false,
+            )
+            .build();
+        assert_eq!(polygon.to_svg_path_data(), "M 0 0 L 5 0 L 0 5");
+
+        let star = Path::builder()
+            .add_star(Point::new(50.0, 50.0), 5, 40.0, 20.0)
+            .build();
+        let points = end_points(&star);
+        assert_eq!(points.len(), 10);
+        assert!(points[0].distance(Point::new(50.0, 10.0)) < 1e-4);
+        assert!((points[1].distance(Point::new(50.0, 50.0)) - 20.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fill_rule_and_contains() {
+        // A square with a hole drawn in the same direction
+        let path = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 30.0, 30.0))
+            .add_rect(Rect::new(10.0, 10.0, 10.0, 10.0))
+            .build();
+        assert_eq!(path.fill_rule(), FillRule::NonZero);
+        assert!(path.contains(Point::new(15.0, 15.0)));
+        assert!(path.contains(Point::new(5.0, 5.0)));
+        assert!(!path.contains(Point::new(35.0, 5.0)));
+
+        let path = path.with_fill_rule(FillRule::EvenOdd);
+        assert!(!path.contains(Point::new(15.0, 15.0)));
+        assert!(path.contains(Point::new(5.0, 5.0)));
+
+        // Open sub-paths are filled as if closed, and curves are followed
+        let triangle = Path::builder()
+            .add_polygon(
+                &[Point::ZERO, Point::new(10.0, 0.0), Point::new(0.0, 10.0)],
+                false,
+            )
+            .build();
+        assert!(triangle.contains(Point::new(2.0, 2.0)));
+        assert!(!triangle.contains(Point::new(8.0, 8.0)));
+        let circle = Path::builder().add_circle(Point::ZERO, 10.0).build();
+        assert!(circle.contains(Point::new(7.0, 7.0)));
+        assert!(!circle.contains(Point::new(7.2, 7.2)));
+    }
+
+    #[test]
+    fn test_bounds_and_transform() {
+        assert_eq!(Path::builder().build().bounds(), None);
+        assert!(Path::builder().build().is_empty());
+
+        // Straight edges give boxes with no area, which still count
+        let rect = Rect::new(1.0, 2.0, 10.0, 5.0);
+        let path = Path::builder().add_rect(rect).build();
+        assert_eq!(path.bounds(), Some(rect));
+
+        // The curve's control point is outside its bounds
+        let path = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .quad_to(Point::new(10.0, 20.0), Point::new(20.0, 0.0))
+            .build();
+        let bounds = path.bounds().unwrap();
+        assert_eq!(
+            (bounds.left(), bounds.right(), bounds.top()),
+            (0.0, 20.0, 0.0)
+        );
+        assert!((bounds.bottom() - 10.0).abs() < 1e-4);
+
+        let matrix = Matrix3x3::from_translation(Point::new(5.0, 5.0))
+            * Matrix3x3::from_scale(Point::new(2.0, 1.0));
+        let moved = path.with_fill_rule(FillRule::EvenOdd).transform(&matrix);
+        assert_eq!(moved.fill_rule(), FillRule::EvenOdd);
+        let bounds = moved.bounds().unwrap();
+        assert_eq!(
+            (bounds.left(), bounds.right(), bounds.top()),
+            (5.0, 45.0, 5.0)
+        );
+    }
+
+    #[test]
+    fn test_length_and_point_at_distance() {
+        // Closing segments count towards the length
+        let square = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 10.0, 10.0))
+            .build();
+        assert!((square.length() - 40.0).abs() < 1e-4);
+        let (point, tangent) = square.point_and_tangent_at(15.0).unwrap();
+        assert!(point.distance(Point::new(10.0, 5.0)) < 1e-4);
+        assert!(tangent.distance(Point::new(0.0, 1.0)) < 1e-4);
+        let (point, tangent) = square.point_and_tangent_at(35.0).unwrap();
+        assert!(point.distance(Point::new(0.0, 5.0)) < 1e-4);
+        assert!(tangent.distance(Point::new(0.0, -1.0)) < 1e-4);
+        // Distances are clamped to the ends
+        let (point, _) = square.point_and_tangent_at(100.0).unwrap();
+        assert!(point.distance(Point::ZERO) < 1e-4);
+
+        let circle = Path::builder().add_circle(Point::ZERO, 10.0).build();
+        let circumference = 2.0 * std::f32::consts::PI * 10.0;
+        assert!((circle.length() - circumference).abs() < 0.05);
+        // A quarter of the way round, clockwise from the right-middle point
+        let (point, tangent) = circle.point_and_tangent_at(circumference / 4.0).unwrap();
+        assert!(point.distance(Point::new(0.0, 10.0)) < 0.05);
+        assert!(tangent.distance(Point::new(-1.0, 0.0)) < 0.01);
+
+        assert!(Path::builder()
+            .move_to(Point::ZERO)
+            .build()
+            .point_and_tangent_at(0.0)
+            .is_none());
+    }
+}
@@ -0,0 +1,210 @@
+//! Boolean operations on path areas
+
+use super::{FillRule, Path, PathBuilder, TOLERANCE};
+use crate::geometry::Point;
+use i_overlay::core::fill_rule::FillRule as OverlayFillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::simplify::SimplifyShape;
+use i_overlay::float::single::SingleFloatOverlay;
+use lyon_path::iterator::PathIterator;
+
+/// Boolean operation combining the areas of two paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathOp {
+    /// Area covered by either path
+    Union,
+    /// Area covered by both paths
+    Intersect,
+    /// Area of the first path outside the second
+    Difference,
+    /// Area of the second path outside the first
+    ReverseDifference,
+    /// Area covered by exactly one of the paths
+    Xor,
+}
+
+impl Path {
+    /// Combine the filled areas of this path and `other`
+    ///
+    /// Each path's area follows its own fill rule, and self-intersections are
+    /// resolved. Curves are flattened to within 0.01 units, so the result is
+    /// made of closed polygons: outer contours run counter-clockwise and holes
+    /// clockwise, giving the same area under either fill rule.
+    pub fn op(&self, other: &Path, op: PathOp) -> Path {
+        let rule = match op {
+            PathOp::Union => OverlayRule::Union,
+            PathOp::Intersect => OverlayRule::Intersect,
+            PathOp::Difference => OverlayRule::Difference,
+            PathOp::ReverseDifference => OverlayRule::InverseDifference,
+            PathOp::Xor => OverlayRule::Xor,
+        };
+
+        let subject = self.polygons();
+        let clip = other.polygons();
+        let shapes = if self.fill_rule == other.fill_rule {
+            subject.overlay(&clip, rule, overlay_fill_rule(self.fill_rule))
+        } else {
+            // Resolve each path under its own rule first; the outlines that come
+            // back wind consistently, so non-zero works for both
+            let subject: Vec<_> = subject
+                .simplify_shape(overlay_fill_rule(self.fill_rule))
+                .into_iter()
+                .flatten()
+                .collect();
+            let clip: Vec<_> = clip
+                .simplify_shape(overlay_fill_rule(other.fill_rule))
+                .into_iter()
+                .flatten()
+                .collect();
+            subject.overlay(&clip, rule, OverlayFillRule::NonZero)
+        };
+        from_polygons(shapes.into_iter().flatten())
+    }
+
+    /// Get a path covering the same area without overlapping contours
+    ///
+    /// Self-intersections are resolved under the path's fill rule. As with
+    /// `op`, curves are flattened and the result is made of closed polygons.
+    pub fn simplify(&self) -> Path {
+        let shapes = self
+            .polygons()
+            .simplify_shape(overlay_fill_rule(self.fill_rule));
+        from_polygons(shapes.into_iter().flatten())
+    }
+
+    /// Flatten every sub-path into a polygon, closing open ones
+    fn polygons(&self) -> Vec<Vec<[f32; 2]>> {
+        let mut polygons = Vec::new();
+        let mut polygon = Vec::new();
+        for event in self.inner.iter().flattened(TOLERANCE) {
+            match event {
+                lyon_path::Event::Begin { at } => polygon.push([at.x, at.y]),
+                lyon_path::Event::Line { to, .. } => polygon.push([to.x, to.y]),
+                lyon_path::Event::End { .. } => {
+                    if polygon.len() >= 3 {
+                        polygons.push(std::mem::take(&mut polygon));
+                    }
+                    polygon.clear();
+                }
+                _ => {}
+            }
+        }
+        polygons
+    }
+}
+
+fn overlay_fill_rule(fill_rule: FillRule) -> OverlayFillRule {
+    match fill_rule {
+        FillRule::NonZero => OverlayFillRule::NonZero,
+        FillRule::EvenOdd => OverlayFillRule::EvenOdd,
+    }
+}
+
+fn from_polygons(polygons: impl IntoIterator<Item = Vec<[f32; 2]>>) -> Path {
+    let mut builder = PathBuilder::new();
+    for polygon in polygons {
+        let points: Vec<Point> = polygon.into_iter().map(|[x, y]| Point::new(x, y)).collect();
+        builder = builder.add_polygon(&points, true);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rect;
+
+    fn square(x: f32, y: f32, size: f32) -> Path {
+        Path::builder()
+            .add_rect(Rect::new(x, y, size, size))
+            .build()
+    }
+
+    #[test]
+    fn test_boolean_ops() {
+        let a = square(0.0, 0.0, 20.0);
+        let b = square(10.0, 10.0, 20.0);
+        let (only_a, both, only_b, neither) = (
+            Point::new(5.0, 5.0),
+            Point::new(15.0, 15.0),
+            Point::new(25.0, 25.0),
+            Point::new(25.0, 5.0),
+        );
+
+        let cases = [
+            (PathOp::Union, [true, true, true]),
+            (PathOp::Intersect, [false, true, false]),
+            (PathOp::Difference, [true, false, false]),
+            (PathOp::ReverseDifference, [false, false, true]),
+            (PathOp::Xor, [true, false, true]),
+        ];
+        for (op, expected) in cases {
+            let result = a.op(&b, op);
+            let inside = [only_a, both, only_b].map(|point| result.contains(point));
+            assert_eq!(inside, expected, "{op:?}");
+            assert!(!result.contains(neither), "{op:?}");
+            // Holes wind the other way, so either fill rule gives the same area
+            let even_odd = result.clone().with_fill_rule(FillRule::EvenOdd);
+            assert_eq!(inside, [only_a, both, only_b].map(|p| even_odd.contains(p)));
+        }
+
+        let bounds = a.op(&b, PathOp::Intersect).bounds().unwrap();
+        assert!((bounds.x - 10.0).abs() < 1e-3 && (bounds.width - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_ops_with_curves_and_fill_rules() {
+        // Cutting a circle out of a square leaves a hole
+        let circle = Path::builder()
+            .add_circle(Point::new(10.0, 10.0), 5.0)
+            .build();
+        let result = square(0.0, 0.0, 20.0).op(&circle, PathOp::Difference);
+        assert!(!result.contains(Point::new(10.0, 10.0)));
+        assert!(!result.contains(Point::new(13.4, 13.4)));
+        assert!(result.contains(Point::new(13.8, 13.8)));
+        assert!(result.contains(Point::new(1.0, 1.0)));
+
+        // An even-odd ring combined with a non-zero square keeps its hole
+        let ring = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 30.0, 30.0))
+            .add_rect(Rect::new(10.0, 10.0, 10.0, 10.0))
+            .build()
+            .with_fill_rule(FillRule::EvenOdd);
+        let result = ring.op(&square(40.0, 0.0, 10.0), PathOp::Union);
+        assert!(!result.contains(Point::new(15.0, 15.0)));
+        assert!(result.contains(Point::new(5.0, 5.0)));
+        assert!(result.contains(Point::new(45.0, 5.0)));
+    }
+
+    #[test]
+    fn test_simplify() {
+        // A bow tie crossing itself becomes two triangles
+        let bow_tie = Path::builder()
+            .add_polygon(
+                &[
+                    Point::new(0.0, 0.0),
+                    Point::new(20.0, 20.0),
+                    Point::new(20.0, 0.0),
+                    Point::new(0.0, 20.0),
+                ],
+                true,
+            )
+            .build();
+        let simplified = bow_tie.simplify();
+        assert_eq!(simplified.contours().len(), 2);
+        assert!(simplified.contains(Point::new(2.0, 10.0)));
+        assert!(!simplified.contains(Point::new(10.0, 2.0)));
+
+        // Overlapping squares under even-odd lose their overlap
+        let squares = Path::builder()
+            .add_rect(Rect::new(0.0, 0.0, 20.0, 20.0))
+            .add_rect(Rect::new(10.0, 10.0, 20.0, 20.0))
+            .build()
+            .with_fill_rule(FillRule::EvenOdd);
+        let simplified = squares.simplify().with_fill_rule(FillRule::NonZero);
+        assert!(!simplified.contains(Point::new(15.0, 15.0)));
+        assert!(simplified.contains(Point::new(5.0, 5.0)));
+
+        assert!(Path::builder().build().simplify().is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Converting strokes to fillable outlines
+
+use super::{FillRule, Path, PathBuilder, PathEffect, TOLERANCE};
+use crate::geometry::Point;
+use crate::paint::{LineCap, LineJoin, StrokeStyle};
+use raqote::PathBuilder as RaqotePathBuilder;
+
+impl Path {
+    /// Get the outline of this path stroked with `stroke`
+    ///
+    /// Filling the result covers the same area as stroking the path, dashes
+    /// included. Curves are flattened to within 0.01 units first, and the
+    /// pieces of the stroke are merged, so the result is made of closed
+    /// polygons without overlaps, like the result of `op`.
+    pub fn stroke_to_path(&self, stroke: &StrokeStyle) -> Path {
+        let dashed;
+        let path = if stroke.is_dashed() {
+            dashed = PathEffect::dash(stroke.dash_array.clone(), stroke.dash_offset).apply(self);
+            &dashed
+        } else {
+            self
+        };
+
+        let mut style = to_raqote_stroke(stroke);
+        // The dashes have already been cut
+        style.dash_array.clear();
+        let outline = raqote::stroke_to_path(&to_raqote_path(path).flatten(TOLERANCE), &style);
+        // The stroker emits overlapping pieces for every segment and join
+        from_raqote_path(&outline).simplify()
+    }
+}
+
+/// Convert a path to a raqote path
+pub(crate) fn to_raqote_path(path: &Path) -> raqote::Path {
+    // Convert lyon path to raqote path
+    let mut raqote_path = RaqotePathBuilder::new();
+
+    for event in path.lyon_path().iter() {
+        match event {
+            lyon_path::Event::Begin { at } => {
+                raqote_path.move_to(at.x, at.y);
+            }
+            lyon_path::Event::Line { to, .. } => {
+                raqote_path.line_to(to.x, to.y);
+            }
+            lyon_path::Event::Quadratic { ctrl, to, .. } => {
+                raqote_path.quad_to(ctrl.x, ctrl.y, to.x, to.y);
+            }
+            lyon_path::Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                raqote_path.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
+            }
+            lyon_path::Event::End { close, .. } => {
+                if close {
+                    raqote_path.close();
+                }
+            }
+        }
+    }
+
+    let mut raqote_path = raqote_path.finish();
+    raqote_path.winding = match path.fill_rule() {
+        FillRule::NonZero => raqote::Winding::NonZero,
+        FillRule::EvenOdd => raqote::Winding::EvenOdd,
+    };
+    raqote_path
+}
+
+/// Convert a stroke style to raqote's, dropping invalid dash patterns
+pub(crate) fn to_raqote_stroke(stroke: &StrokeStyle) -> raqote::StrokeStyle {
+    raqote::StrokeStyle {
+        width: stroke.width,
+        cap: match stroke.cap {
+            LineCap::Butt => raqote::LineCap::Butt,
+            LineCap::Round => raqote::LineCap::Round,
+            LineCap::Square => raqote::LineCap::Square,
+        },
+        join: match stroke.join {
+            LineJoin::Miter => raqote::LineJoin::Miter,
+            LineJoin::Round => raqote::LineJoin::Round,
+            LineJoin::Bevel => raqote::LineJoin::Bevel,
+        },
+        miter_limit: stroke.miter_limit,
+        dash_array: if stroke.is_dashed() {
+            stroke.dash_array.clone()
+        } else {
+            Vec::new()
+        },
+        dash_offset: stroke.dash_offset,
+    }
+}
+
+/// Convert a raqote path back to a non-zero path
+fn from_raqote_path(path: &raqote::Path) -> Path {
+    let point = |x: f32, y: f32| Point::new(x, y);
+    let mut builder = PathBuilder::new();
+    for op in &path.ops {
+        builder = match *op {
+            raqote::PathOp::MoveTo(p) => builder.move_to(point(p.x, p.y)),
+            raqote::PathOp::LineTo(p) => builder.line_to(point(p.x, p.y)),
+            raqote::PathOp::QuadTo(c, p) => builder.quad_to(point(c.x, c.y), point(p.x, p.y)),
+            raqote::PathOp::CubicTo(c1, c2, p) => {
+                builder.cubic_to(point(c1.x, c1.y), point(c2.x, c2.y), point(p.x, p.y))
+            }
+            raqote::PathOp::Close => builder.close(),
+        };
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stroke_to_path() {
+        let line = Path::builder()
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .build();
+        let outline = line.stroke_to_path(&StrokeStyle::new(4.0));
+        let bounds = outline.bounds().unwrap();
+        assert!((bounds.x - 0.0).abs() < 1e-3 && (bounds.width - 10.0).abs() < 1e-3);
+        assert!((bounds.y + 2.0).abs() < 1e-3 && (bounds.height - 4.0).abs() < 1e-3);
+        assert!(outline.contains(Point::new(5.0, 1.5)));
+        assert!(!outline.contains(Point::new(5.0, 2.5)));
+
+        // Caps extend past the ends
+        let mut stroke = StrokeStyle::new(4.0);
+        stroke.cap = LineCap::Square;
+        assert!(line.stroke_to_path(&stroke).contains(Point::new(11.5, 0.0)));
+
+        // Dashes leave gaps
+        let mut stroke = StrokeStyle::new(4.0);
+        stroke.set_dash(vec![2.0, 2.0], 0.0);
+        let outline = line.stroke_to_path(&stroke);
+        assert!(outline.contains(Point::new(1.0, 0.0)));
+        assert!(!outline.contains(Point::new(3.0, 0.0)));
+        assert!(outline.contains(Point::new(5.0, 0.0)));
+
+        // A stroked circle is a ring
+        let circle = Path::builder()
+            .add_circle(Point::new(0.0, 0.0), 10.0)
+            .build()
+            .with_fill_rule(FillRule::EvenOdd);
+        let ring = circle.stroke_to_path(&StrokeStyle::new(2.0));
+        assert_eq!(ring.fill_rule(), FillRule::NonZero);
+        assert!(ring.contains(Point::new(0.0, 10.5)));
+        assert!(!ring.contains(Point::new(0.0, 0.0)));
+        assert!(!ring.contains(Point::new(11.5, 0.0)));
+
+        assert!(line.stroke_to_path(&StrokeStyle::new(0.0)).is_empty());
+
+        // The outline of a polyline is a single polygon
+        let zigzag = Path::builder()
+            .add_polygon(
+                &[
+                    Point::new(0.0, 0.0),
+                    Point::new(10.0, 10.0),
+                    Point::new(20.0, 0.0),
+                    Point::new(30.0, 10.0),
+                ],
+                false,
+            )
+            .build();
+        assert_eq!(
+            zigzag
+                .stroke_to_path(&StrokeStyle::new(2.0))
+                .contours()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Pixel formats for reading and writing surface memory
+
+use crate::geometry::IRect;
+use thiserror::Error;
+
+/// Order of the four 8-bit channels in a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorType {
+    /// Red, green, blue, alpha
+    #[default]
+    Rgba8,
+    /// Blue, green, red, alpha
+    Bgra8,
+}
+
+/// How color channels relate to alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaType {
+    /// Color channels are already multiplied by alpha
+    Premultiplied,
+    /// Color channels are independent of alpha
+    #[default]
+    Unpremultiplied,
+}
+
+/// Layout of pixel data exchanged with a surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PixelFormat {
+    pub color_type: ColorType,
+    pub alpha_type: AlphaType,
+}
+
+impl PixelFormat {
+    /// Unpremultiplied RGBA, as stored in PNG files
+    pub const RGBA8: Self = Self::new(ColorType::Rgba8, AlphaType::Unpremultiplied);
+
+    /// Premultiplied BGRA, as used by most video and windowing APIs
+    pub const BGRA8_PREMULTIPLIED: Self = Self::new(ColorType::Bgra8, AlphaType::Premultiplied);
+
+    pub const fn new(color_type: ColorType, alpha_type: AlphaType) -> Self {
+        Self {
+            color_type,
+            alpha_type,
+        }
+    }
+
+    /// Convert a premultiplied RGBA pixel into this format
+    pub(crate) fn encode(&self, rgba: [u8; 4]) -> [u8; 4] {
+        let [r, g, b, a] = match self.alpha_type {
+            AlphaType::Premultiplied => rgba,
+            AlphaType::Unpremultiplied => unpremultiply(rgba),
+        };
+        match self.color_type {
+            ColorType::Rgba8 => [r, g, b, a],
+            ColorType::Bgra8 => [b, g, r, a],
+        }
+    }
+
+    /// Convert a pixel in this format into premultiplied RGBA
+    pub(crate) fn decode(&self, pixel: [u8; 4]) -> [u8; 4] {
+        let rgba = match self.color_type {
+            ColorType::Rgba8 => pixel,
+            ColorType::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        };
+        match self.alpha_type {
+            // Clamp so malformed input stays a valid premultiplied color
+            AlphaType::Premultiplied => rgba.map(|c| c.min(rgba[3])),
+            AlphaType::Unpremultiplied => premultiply(rgba),
+        }
+    }
+}
+
+/// Errors from reading or writing surface pixels
+#[derive(Debug, Error)]
+pub enum PixelError {
+    #[error("Rectangle {0:?} is outside the {1}x{2} surface")]
+    OutOfBounds(IRect, u32, u32),
+
+    #[error("Row stride of {0} bytes is shorter than a row of {1} bytes")]
+    RowBytesTooSmall(usize, usize),
+
+    #[error("Buffer of {0} bytes is smaller than the {1} bytes required")]
+    BufferTooSmall(usize, usize),
+
+    #[error(transparent)]
+    Gpu(#[from] crate::gpu::GpuError),
+}
+
+/// Check that `rect` fits the surface and the buffer can hold it
+pub(crate) fn check_access(
+    rect: IRect,
+    surface_width: u32,
+    surface_height: u32,
+    buffer_len: usize,
+    row_bytes: usize,
+) -> Result<(), PixelError> {
+    match (rect.right(), rect.bottom()) {
+        (Some(right), Some(bottom)) if right <= surface_width && bottom <= surface_height => {}
+        _ => return Err(PixelError::OutOfBounds(rect, surface_width, surface_height)),
+    }
+
+    let min_row_bytes = rect.width as usize * 4;
+    if row_bytes < min_row_bytes {
+        return Err(PixelError::RowBytesTooSmall(row_bytes, min_row_bytes));
+    }
+
+    // No buffer can hold more than usize::MAX bytes
+    let too_large = PixelError::BufferTooSmall(buffer_len, usize::MAX);
+    let required = match rect.height as usize {
+        0 => 0,
+        rows => row_bytes
+            .checked_mul(rows - 1)
+            .and_then(|bytes| bytes.checked_add(min_row_bytes))
+            .ok_or(too_large)?,
+    };
+    if buffer_len < required {
+        return Err(PixelError::BufferTooSmall(buffer_len, required));
+    }
+    Ok(())
+}
+
+fn premultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let scale = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+fn unpremultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    if a == 0 {
+        return [0; 4];
+    }
+    let scale = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let half_red = [128, 0, 0, 128];
+        assert_eq!(PixelFormat::RGBA8.encode(half_red), [255, 0, 0, 128]);
+        assert_eq!(
+            PixelFormat::BGRA8_PREMULTIPLIED.encode(half_red),
+            [0, 0, 128, 128]
+        );
+        assert_eq!(PixelFormat::RGBA8.decode([255, 0, 0, 128]), half_red);
+        assert_eq!(PixelFormat::RGBA8.encode([0; 4]), [0; 4]);
+    }
+
+    #[test]
+    fn test_check_access() {
+        let rect = IRect::new(2, 2, 4, 3);
+        assert!(check_access(rect, 8, 8, 16 * 2 + 16, 16).is_ok());
+        assert!(matches!(
+            check_access(rect, 5, 8, 1024, 16),
+            Err(PixelError::OutOfBounds(..))
+        ));
+        assert!(matches!(
+            check_access(rect, 8, 8, 1024, 8),
+            Err(PixelError::RowBytesTooSmall(8, 16))
+        ));
+        assert!(matches!(
+            check_access(rect, 8, 8, 40, 16),
+            Err(PixelError::BufferTooSmall(40, 48))
+        ));
+        for rect in [IRect::new(u32::MAX, 0, 2, 1), IRect::new(0, u32::MAX, 1, 2)] {
+            assert!(matches!(
+                check_access(rect, 8, 8, 1024, 16),
+                Err(PixelError::OutOfBounds(..))
+            ));
+        }
+        assert!(matches!(
+            check_access(IRect::new(0, 0, 1, 3), 8, 8, 1024, usize::MAX / 2 + 1),
+            Err(PixelError::BufferTooSmall(1024, usize::MAX))
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 //! Surface and canvas abstractions for drawing
 
-use crate::{Color, Paint, Path, Point, Rect};
+use crate::image::{FilterQuality, Image};
+use crate::pixels::{PixelError, PixelFormat};
+use crate::{BlendMode, Color, FillRule, IRect, Matrix3x3, Paint, Path, Point, RRect, Rect};
 
 /// Generic surface for rendering
 pub trait Surface {
@@ -15,6 +17,57 @@ pub trait Surface {
 
     /// Save the surface to a PNG file
     fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Read the pixels of `rect` into `dst`, with rows `row_bytes` apart
+    fn read_pixels(
+        &self,
+        rect: IRect,
+        format: PixelFormat,
+        dst: &mut [u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError>;
+
+    /// Overwrite the pixels of `rect` from `src`, with rows `row_bytes` apart
+    ///
+    /// The canvas' matrix, clip and blending do not apply.
+    fn write_pixels(
+        &mut self,
+        rect: IRect,
+        format: PixelFormat,
+        src: &[u8],
+        row_bytes: usize,
+    ) -> Result<(), PixelError>;
+
+    /// Read the whole surface as tightly packed rows
+    fn pixels(&self, format: PixelFormat) -> Result<Vec<u8>, PixelError> {
+        let rect = IRect::from_size(self.width(), self.height());
+        let row_bytes = rect.width as usize * 4;
+        let mut pixels = vec![0; row_bytes * rect.height as usize];
+        self.read_pixels(rect, format, &mut pixels, row_bytes)?;
+        Ok(pixels)
+    }
+}
+
+/// How a clip shape combines with the current clip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipOp {
+    /// Keep only the area inside the shape
+    #[default]
+    Intersect,
+    /// Remove the area inside the shape
+    Difference,
+}
+
+/// How `Canvas::draw_points` connects its points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointMode {
+    /// Draw each point as a dot
+    #[default]
+    Points,
+    /// Draw a line between each pair of points
+    Lines,
+    /// Draw a line through every point in turn
+    Polygon,
 }
 
 /// Canvas for drawing operations
@@ -26,8 +79,68 @@ pub trait Canvas {
     fn save(&mut self);
 
     /// Restore the previous drawing state
+    ///
+    /// Does nothing if there is no matching `save`.
     fn restore(&mut self);
 
+    /// Get the depth of the state stack (1 when nothing has been saved)
+    fn save_count(&self) -> usize;
+
+    /// Restore states until `save_count` equals `count`
+    fn restore_to_count(&mut self, count: usize);
+
+    /// Set the opacity applied to every subsequent draw (0.0-1.0)
+    fn set_global_alpha(&mut self, alpha: f32);
+
+    /// Get the current global opacity
+    fn global_alpha(&self) -> f32;
+
+    /// Override the blend mode of every subsequent draw, or `None` to use each paint's own
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>);
+
+    /// Pre-concatenate a matrix with the current transform
+    ///
+    /// Subsequent drawing is transformed by `matrix` first, then by the
+    /// previous transform.
+    fn concat(&mut self, matrix: Matrix3x3);
+
+    /// Replace the current transform
+    fn set_matrix(&mut self, matrix: Matrix3x3);
+
+    /// Get the current transform
+    fn get_matrix(&self) -> Matrix3x3;
+
+    /// Reset the current transform to identity
+    fn reset_matrix(&mut self) {
+        self.set_matrix(Matrix3x3::IDENTITY);
+    }
+
+    /// Translate the coordinate system
+    fn translate(&mut self, dx: f32, dy: f32) {
+        self.concat(Matrix3x3::from_translation(Point::new(dx, dy)));
+    }
+
+    /// Scale the coordinate system
+    fn scale(&mut self, sx: f32, sy: f32) {
+        self.concat(Matrix3x3::from_scale(Point::new(sx, sy)));
+    }
+
+    /// Rotate the coordinate system clockwise by `degrees` around the origin
+    fn rotate(&mut self, degrees: f32) {
+        self.concat(Matrix3x3::from_angle(degrees.to_radians()));
+    }
+
+    /// Skew the coordinate system by `sx` horizontally and `sy` vertically
+    fn skew(&mut self, sx: f32, sy: f32) {
+        self.concat(crate::geometry::skew_matrix(sx, sy));
+    }
+
+    /// Clip subsequent drawing with a rectangle
+    fn clip_rect(&mut self, rect: Rect, op: ClipOp, anti_alias: bool);
+
+    /// Clip subsequent drawing with a path
+    fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool);
+
     /// Draw a filled or stroked path
     fn draw_path(&mut self, path: &Path, paint: &Paint);
 
@@ -40,6 +153,106 @@ pub trait Canvas {
     /// Draw a line
     fn draw_line(&mut self, from: Point, to: Point, paint: &Paint);
 
+    /// Draw a rounded rectangle
+    fn draw_rrect(&mut self, rrect: RRect, paint: &Paint) {
+        self.draw_path(&Path::builder().add_rrect(rrect).build(), paint);
+    }
+
+    /// Draw the ring between two rounded rectangles
+    ///
+    /// Fills cover the area inside `outer` and outside `inner`; strokes
+    /// outline both. Nothing is drawn unless `inner` lies within `outer`.
+    fn draw_drrect(&mut self, outer: RRect, inner: RRect, paint: &Paint) {
+        let (o, i) = (outer.rect(), inner.rect());
+        if i.left() < o.left()
+            || i.top() < o.top()
+            || i.right() > o.right()
+            || i.bottom() > o.bottom()
+        {
+            return;
+        }
+        let ring = Path::builder()
+            .add_rrect(outer)
+            .add_rrect(inner)
+            .build()
+            .with_fill_rule(FillRule::EvenOdd);
+        self.draw_path(&ring, paint);
+    }
+
+    /// Draw the ellipse filling `oval`
+    fn draw_oval(&mut self, oval: Rect, paint: &Paint) {
+        self.draw_path(&Path::builder().add_oval(oval).build(), paint);
+    }
+
+    /// Draw an arc of the ellipse filling `oval`
+    ///
+    /// Angles are in degrees, clockwise from the positive x axis. With
+    /// `use_center` the arc is joined to the center, drawing a wedge;
+    /// otherwise fills cover the area between the arc and its chord. Sweeps
+    /// of a full turn or more draw the whole ellipse.
+    fn draw_arc(
+        &mut self,
+        oval: Rect,
+        start_angle: f32,
+        sweep_angle: f32,
+        use_center: bool,
+        paint: &Paint,
+    ) {
+        if sweep_angle == 0.0 {
+            return;
+        }
+        if sweep_angle.abs() >= 360.0 {
+            return self.draw_oval(oval, paint);
+        }
+        let mut builder = Path::builder();
+        if use_center {
+            builder = builder.move_to(oval.center());
+        }
+        builder = builder.arc_to_oval(oval, start_angle, sweep_angle);
+        if use_center || paint.is_fill() {
+            builder = builder.close();
+        }
+        self.draw_path(&builder.build(), paint);
+    }
+
+    /// Draw a set of points as dots, separate lines or a connected line
+    ///
+    /// Points are always stroked, fill paints getting a 1px stroke as with
+    /// `draw_line`. Dots are circles with round caps and squares otherwise.
+    /// In `Lines` mode an unpaired last point is ignored.
+    fn draw_points(&mut self, mode: PointMode, points: &[Point], paint: &Paint) {
+        let stroke = paint.stroke.clone().unwrap_or_default();
+        let mut builder = Path::builder();
+        match mode {
+            PointMode::Points => {
+                let radius = stroke.width / 2.0;
+                for &point in points {
+                    builder = match stroke.cap {
+                        crate::LineCap::Round => builder.add_circle(point, radius),
+                        _ => builder.add_rect(Rect::new(
+                            point.x - radius,
+                            point.y - radius,
+                            stroke.width,
+                            stroke.width,
+                        )),
+                    };
+                }
+                let mut paint = paint.clone();
+                paint.set_fill();
+                return self.draw_path(&builder.build(), &paint);
+            }
+            PointMode::Lines => {
+                for pair in points.chunks_exact(2) {
+                    builder = builder.move_to(pair[0]).line_to(pair[1]);
+                }
+            }
+            PointMode::Polygon => builder = builder.add_polygon(points, false),
+        }
+        let mut paint = paint.clone();
+        paint.set_stroke(stroke);
+        self.draw_path(&builder.build(), &paint);
+    }
+
     /// Draw text at a position
     fn draw_text(
         &mut self,
@@ -48,5 +261,73 @@ pub trait Canvas {
         font: &crate::text::Font,
         font_size: f32,
         paint: &Paint,
+    ) {
+        let fonts = crate::text::FontCollection::new(font.clone());
+        self.draw_text_with_fallback(text, position, &fonts, font_size, paint);
+    }
+
+    /// Draw text at a position, taking each character from the first font
+    /// in `fonts` that has it
+    fn draw_text_with_fallback(
+        &mut self,
+        text: &str,
+        position: Point,
+        fonts: &crate::text::FontCollection,
+        font_size: f32,
+        paint: &Paint,
+    ) {
+        let style = crate::text::TextStyle::default();
+        self.draw_text_styled(text, position, fonts, font_size, &style, paint);
+    }
+
+    /// Draw text at a position with fallback fonts, shaped with the OpenType
+    /// features, language and script of `style`
+    fn draw_text_styled(
+        &mut self,
+        text: &str,
+        position: Point,
+        fonts: &crate::text::FontCollection,
+        font_size: f32,
+        style: &crate::text::TextStyle,
+        paint: &Paint,
+    );
+
+    /// Draw an image at its natural size with its top-left corner at `position`
+    ///
+    /// As with every image draw, the paint's color alpha sets the opacity;
+    /// its blend mode, image filter and shadows apply, its color and shader do not.
+    fn draw_image(&mut self, image: &Image, position: Point, paint: &Paint) {
+        let dst = Rect::new(
+            position.x,
+            position.y,
+            image.width() as f32,
+            image.height() as f32,
+        );
+        self.draw_image_rect(image, image.bounds(), dst, FilterQuality::default(), paint);
+    }
+
+    /// Draw the `src` part of an image scaled to fill `dst`
+    ///
+    /// Sampling stays within `src`, so neighbouring pixels never bleed in.
+    fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: Rect,
+        dst: Rect,
+        quality: FilterQuality,
+        paint: &Paint,
+    );
+
+    /// Draw an image as a nine-patch stretched over `dst`
+    ///
+    /// `center` splits the image into a 3x3 grid: the corners keep their size,
+    /// the edges stretch along one axis and the center along both.
+    fn draw_image_nine(
+        &mut self,
+        image: &Image,
+        center: IRect,
+        dst: Rect,
+        quality: FilterQuality,
+        paint: &Paint,
     );
 }
@@ -0,0 +1,376 @@
+//! SVG document rendering
+//!
+//! Parses static SVG 1.1 documents into a tree of paths, text and groups
+//! that can be drawn onto any `Canvas`. Supported: paths and basic shapes,
+//! groups and nested `svg` elements, `use`, transforms, fills and strokes
+//! (including dashes), linear and radial gradients, clip paths, opacity and
+//! simple text. Styles come from presentation attributes and `style`
+//! attributes; `<style>` sheets, filters, masks, patterns and embedded
+//! images are ignored.
+
+mod parse;
+mod render;
+
+use crate::effects::{GradientStop, TileMode};
+use crate::text::{Font, TextAlign};
+use crate::{Canvas, ClipOp, Color, Matrix3x3, Path, Point, Rect, Size, StrokeStyle};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Errors from loading an SVG document
+#[derive(Debug, Error)]
+pub enum SvgError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to parse XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    #[error("Root element is <{0}>, not <svg>")]
+    NotSvg(String),
+}
+
+/// A parsed SVG document
+pub struct SvgDocument {
+    /// Size of the document's viewport
+    size: Size,
+
+    /// Maps the view box onto the viewport
+    view_box_transform: Matrix3x3,
+
+    root: Group,
+
+    /// Font used for every `text` element
+    font: Option<Font>,
+}
+
+impl SvgDocument {
+    /// Parse a document from SVG source
+    pub fn parse(source: &str) -> Result<Self, SvgError> {
+        parse::parse_document(source)
+    }
+
+    /// Load and parse an SVG file
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, SvgError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Get the size of the document, from its `width` and `height`
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Set the font for text elements
+    ///
+    /// Text is skipped until a font is set. The `font-family` property is
+    /// ignored.
+    pub fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+    }
+
+    /// Draw the document at its own size, with its top-left corner at the origin
+    pub fn render(&self, canvas: &mut dyn Canvas) {
+        canvas.save();
+        // Content outside the viewport is hidden, as with `overflow: hidden`
+        canvas.clip_rect(
+            Rect::new(0.0, 0.0, self.size.width, self.size.height),
+            ClipOp::Intersect,
+            true,
+        );
+        let viewport = Rect::new(0.0, 0.0, self.size.width, self.size.height);
+        let viewport = parse::transform_rect(&canvas.get_matrix(), viewport);
+        canvas.concat(self.view_box_transform);
+        render::render_group(&self.root, canvas, viewport, self.font.as_ref());
+        canvas.restore();
+    }
+
+    /// Draw the document scaled to fill `rect`
+    pub fn render_in_rect(&self, canvas: &mut dyn Canvas, rect: Rect) {
+        if self.size.width <= 0.0 || self.size.height <= 0.0 {
+            return;
+        }
+        canvas.save();
+        canvas.translate(rect.x, rect.y);
+        canvas.scale(rect.width / self.size.width, rect.height / self.size.height);
+        self.render(canvas);
+        canvas.restore();
+    }
+}
+
+impl std::str::FromStr for SvgDocument {
+    type Err = SvgError;
+
+    fn from_str(source: &str) -> Result<Self, SvgError> {
+        Self::parse(source)
+    }
+}
+
+/// A group of nodes sharing a transform, clip and opacity
+#[derive(Debug, Clone)]
+struct Group {
+    transform: Matrix3x3,
+    opacity: f32,
+    clip_path: Option<Path>,
+    children: Vec<Node>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            transform: Matrix3x3::IDENTITY,
+            opacity: 1.0,
+            clip_path: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Group(Group),
+    Shape(Shape),
+    Text(Text),
+}
+
+/// A filled and/or stroked path
+#[derive(Debug, Clone)]
+struct Shape {
+    path: Path,
+
+    /// Bounding box of the path, for `objectBoundingBox` units
+    bounds: Option<Rect>,
+
+    fill: Option<SvgPaint>,
+    stroke: Option<(SvgPaint, StrokeStyle)>,
+}
+
+/// A single run of text
+#[derive(Debug, Clone)]
+struct Text {
+    text: String,
+
+    /// Position of the anchor point on the baseline
+    position: Point,
+
+    font_size: f32,
+    anchor: TextAlign,
+    fill: Option<SvgPaint>,
+}
+
+/// A fill or stroke with its opacity
+#[derive(Debug, Clone)]
+struct SvgPaint {
+    kind: PaintKind,
+    opacity: f32,
+}
+
+#[derive(Debug, Clone)]
+enum PaintKind {
+    Color(Color),
+    Gradient(Arc<GradientDef>),
+}
+
+/// A `linearGradient` or `radialGradient` with its references resolved
+#[derive(Debug, Clone)]
+struct GradientDef {
+    geometry: GradientGeometry,
+
+    /// Whether coordinates are fractions of the painted element's bounding box
+    bounding_box_units: bool,
+
+    transform: Matrix3x3,
+    tile_mode: TileMode,
+    stops: Vec<GradientStop>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GradientGeometry {
+    Linear {
+        start: Point,
+        end: Point,
+    },
+    Radial {
+        center: Point,
+        radius: f32,
+        focal: Point,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CpuSurface, PixelFormat, Surface};
+
+    fn render(source: &str) -> Vec<u8> {
+        let svg = SvgDocument::parse(source).unwrap();
+        let size = svg.size();
+        let mut surface = CpuSurface::new(size.width as i32, size.height as i32);
+        svg.render(&mut surface);
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    }
+
+    fn pixel(pixels: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * width + x) * 4;
+        pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_shapes_and_styles() {
+        let pixels = render(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+                <rect x="0" y="0" width="20" height="20" fill="#ff0000"/>
+                <g style="fill: blue" transform="translate(20 0)">
+                    <circle cx="10" cy="10" r="8"/>
+                    <path d="M0 0 H20" stroke="lime" stroke-width="4"/>
+                </g>
+            </svg>"##,
+        );
+        assert_eq!(pixel(&pixels, 40, 10, 10), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 40, 30, 10), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 40, 30, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 40, 21, 18), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_view_box_and_opacity() {
+        let pixels = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 2 2">
+                <rect width="1" height="1" fill="red" fill-opacity="0.5"/>
+                <rect x="1" y="1" width="1" height="1" fill="blue" opacity="0.5"/>
+            </svg>"#,
+        );
+        assert_eq!(pixel(&pixels, 20, 5, 5), [255, 0, 0, 128]);
+        assert_eq!(pixel(&pixels, 20, 15, 15), [0, 0, 255, 128]);
+        assert_eq!(pixel(&pixels, 20, 15, 5)[3], 0);
+    }
+
+    #[test]
+    fn test_group_opacity() {
+        // A semi-transparent group fades as a whole, so its overlapping
+        // children and a stroke over a fill don't show through each other
+        let pixels = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 2 2">
+                <g opacity="0.5" transform="translate(0.5 0)">
+                    <rect width="1" height="1" fill="red"/>
+                    <rect x="0.5" width="1" height="1" fill="blue"/>
+                </g>
+                <rect y="1" width="2" height="1" fill="red" stroke="blue" stroke-width="0.5"
+                    opacity="0.5"/>
+            </svg>"#,
+        );
+        assert_eq!(pixel(&pixels, 20, 7, 5), [255, 0, 0, 128]);
+        assert_eq!(pixel(&pixels, 20, 12, 5), [0, 0, 255, 128]);
+        assert_eq!(pixel(&pixels, 20, 2, 5)[3], 0);
+        assert_eq!(pixel(&pixels, 20, 10, 11), [0, 0, 255, 128]);
+        assert_eq!(pixel(&pixels, 20, 10, 16), [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_gradient_and_clip_path() {
+        let pixels = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="20">
+                <defs>
+                    <linearGradient id="g">
+                        <stop offset="0" stop-color="black"/>
+                        <stop offset="1" stop-color="white"/>
+                    </linearGradient>
+                    <clipPath id="c"><rect width="50" height="20"/></clipPath>
+                </defs>
+                <rect width="100" height="20" fill="url(#g)" clip-path="url(#c)"/>
+            </svg>"#,
+        );
+        // The gradient spans the rect's bounding box, and the clip hides the right half
+        assert!(pixel(&pixels, 100, 2, 10)[0] < 16);
+        assert!((100..156).contains(&pixel(&pixels, 100, 49, 10)[0]));
+        assert_eq!(pixel(&pixels, 100, 75, 10)[3], 0);
+    }
+
+    #[test]
+    fn test_fill_and_clip_rules() {
+        let pixels = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="30">
+                <clipPath id="c">
+                    <path d="M30 0 h30 v30 h-30 z M40 10 h10 v10 h-10 z" clip-rule="evenodd"/>
+                </clipPath>
+                <path d="M0 0 h30 v30 h-30 z M10 10 h10 v10 h-10 z" fill-rule="evenodd"/>
+                <rect x="30" width="30" height="30" clip-path="url(#c)"/>
+            </svg>"#,
+        );
+        assert_eq!(pixel(&pixels, 60, 5, 5)[3], 255);
+        assert_eq!(pixel(&pixels, 60, 15, 15)[3], 0);
+        assert_eq!(pixel(&pixels, 60, 35, 5)[3], 255);
+        assert_eq!(pixel(&pixels, 60, 45, 15)[3], 0);
+
+        // Clip children wound in opposite directions still add up
+        let pixels = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+                <clipPath id="c">
+                    <path d="M0 0 h10 v10 h-10 z"/>
+                    <path d="M5 0 v10 h10 v-10 z"/>
+                </clipPath>
+                <rect width="20" height="10" clip-path="url(#c)"/>
+            </svg>"#,
+        );
+        assert_eq!(pixel(&pixels, 20, 7, 5)[3], 255);
+        assert_eq!(pixel(&pixels, 20, 17, 5)[3], 0);
+    }
+
+    #[test]
+    fn test_use_and_errors() {
+        let pixels = render(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="20" height="10">
+                <defs><rect id="r" width="10" height="10" fill="green"/></defs>
+                <use xlink:href="#r" x="10"/>
+            </svg>"##,
+        );
+        assert_eq!(pixel(&pixels, 20, 5, 5)[3], 0);
+        assert_eq!(pixel(&pixels, 20, 15, 5), [0, 128, 0, 255]);
+
+        assert!(matches!(
+            SvgDocument::parse("<html/>"),
+            Err(SvgError::NotSvg(_))
+        ));
+        assert!(matches!(SvgDocument::parse("<svg"), Err(SvgError::Xml(_))));
+    }
+
+    #[test]
+    fn test_recursive_use() {
+        fn count(group: &Group) -> usize {
+            group
+                .children
+                .iter()
+                .map(|child| match child {
+                    Node::Group(group) => 1 + count(group),
+                    _ => 1,
+                })
+                .sum()
+        }
+        let parse = |body: &str| {
+            let source = format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, body);
+            count(&SvgDocument::parse(&source).unwrap().root)
+        };
+
+        // A group using itself four times would otherwise expand 4^16 times
+        let uses = r##"<use href="#a"/>"##.repeat(4);
+        assert_eq!(
+            parse(&format!(
+                r#"<g id="a"><rect width="1" height="1"/>{}</g>"#,
+                uses
+            )),
+            2
+        );
+
+        // So would references that cycle through each other
+        let cycle = r##"<g id="a"><use href="#b"/></g><g id="b"><rect width="1" height="1"/><use href="#a"/></g>"##;
+        assert!(parse(cycle) < 20);
+
+        // Copies without a cycle still stop at the element limit
+        let mut body = String::from(r#"<rect id="l0" width="1" height="1"/>"#);
+        for level in 1..12 {
+            body += &format!(r#"<g id="l{}">"#, level);
+            body += &format!(r##"<use href="#l{}"/>"##, level - 1).repeat(4);
+            body += "</g>";
+        }
+        assert!(parse(&body) <= 2 * parse::MAX_NODES);
+    }
+}
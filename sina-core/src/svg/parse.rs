@@ -0,0 +1,899 @@
+//! Building the render tree from SVG markup
+
+use super::{
+    GradientDef, GradientGeometry, Group, Node, PaintKind, Shape, SvgDocument, SvgError, SvgPaint,
+    Text,
+};
+use crate::effects::{GradientStop, TileMode};
+use crate::text::TextAlign;
+use crate::{
+    path, Color, FillRule, LineCap, LineJoin, Matrix3x3, Path, PathBuilder, PathOp, Point, RRect,
+    Rect, Size, StrokeStyle,
+};
+use roxmltree::Node as XmlNode;
+use std::collections::HashMap;
+use std::sync::Arc;
+use svgtypes::{Length, LengthUnit};
+
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// Font size used where none is inherited, as in browsers
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+/// Deepest chain of `use` elements or gradient references that is followed
+const MAX_REFERENCE_DEPTH: usize = 16;
+
+/// Most elements a document expands to, counting every copy made by `use`
+pub(super) const MAX_NODES: usize = 100_000;
+
+pub(super) fn parse_document(source: &str) -> Result<SvgDocument, SvgError> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document = roxmltree::Document::parse_with_options(source, options)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err(SvgError::NotSvg(root.tag_name().name().to_string()));
+    }
+
+    let view_box = root
+        .attribute("viewBox")
+        .and_then(|value| value.parse::<svgtypes::ViewBox>().ok())
+        .filter(|view_box| view_box.w > 0.0 && view_box.h > 0.0)
+        .map(|view_box| {
+            Rect::new(
+                view_box.x as f32,
+                view_box.y as f32,
+                view_box.w as f32,
+                view_box.h as f32,
+            )
+        });
+
+    // Percentage sizes are taken relative to the view box
+    let size_attribute = |name: &str, fallback: f32| match root
+        .attribute(name)
+        .and_then(|v| v.parse::<Length>().ok())
+    {
+        Some(length) if length.unit == LengthUnit::Percent => {
+            fallback * length.number as f32 / 100.0
+        }
+        Some(length) => absolute_length(length, DEFAULT_FONT_SIZE).unwrap_or(fallback),
+        None => fallback,
+    };
+    let size = Size::new(
+        size_attribute("width", view_box.map_or(100.0, |v| v.width)),
+        size_attribute("height", view_box.map_or(100.0, |v| v.height)),
+    );
+
+    let mut parser = Parser {
+        ids: document
+            .descendants()
+            .filter_map(|node| node.attribute("id").map(|id| (id, node)))
+            .collect(),
+        viewport: view_box.map_or(size, |v| Size::new(v.width, v.height)),
+        gradients: HashMap::new(),
+        expanding: Vec::new(),
+        nodes: 0,
+    };
+
+    let style = Style::default().inherit(root, &mut parser);
+    let root_group = Group {
+        opacity: opacity_property(root),
+        children: parser.parse_children(root, &style),
+        ..Default::default()
+    };
+
+    Ok(SvgDocument {
+        size,
+        view_box_transform: view_box.map_or(Matrix3x3::IDENTITY, |view_box| {
+            view_box_transform(view_box, size, root.attribute("preserveAspectRatio"))
+        }),
+        root: root_group,
+        font: None,
+    })
+}
+
+/// Which viewport dimension a percentage length refers to
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+    /// Normalized diagonal, for radii and stroke widths
+    Diagonal,
+}
+
+/// Inherited presentation properties
+#[derive(Clone)]
+struct Style {
+    fill: Option<PaintKind>,
+    fill_opacity: f32,
+    fill_rule: FillRule,
+    clip_rule: FillRule,
+    stroke: Option<PaintKind>,
+    stroke_opacity: f32,
+    stroke_style: StrokeStyle,
+
+    /// Value of `currentColor`
+    color: Color,
+
+    font_size: f32,
+    text_anchor: TextAlign,
+    visible: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some(PaintKind::Color(Color::BLACK)),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            clip_rule: FillRule::NonZero,
+            stroke: None,
+            stroke_opacity: 1.0,
+            stroke_style: StrokeStyle::default(),
+            color: Color::BLACK,
+            font_size: DEFAULT_FONT_SIZE,
+            text_anchor: TextAlign::Left,
+            visible: true,
+        }
+    }
+}
+
+impl Style {
+    /// Get the style of `node`, inheriting everything it does not set
+    fn inherit(&self, node: XmlNode, parser: &mut Parser) -> Style {
+        let mut style = self.clone();
+
+        if let Some(color) = property(node, "color").and_then(parse_color) {
+            style.color = color;
+        }
+        if let Some(font_size) = property(node, "font-size").and_then(|v| v.parse::<Length>().ok())
+        {
+            // Relative font sizes refer to the inherited size
+            style.font_size = match font_size.unit {
+                LengthUnit::Percent => self.font_size * font_size.number as f32 / 100.0,
+                _ => absolute_length(font_size, self.font_size).unwrap_or(self.font_size),
+            };
+        }
+
+        if let Some(fill) = property(node, "fill").and_then(|v| parser.parse_paint(v, &style)) {
+            style.fill = fill;
+        }
+        if let Some(opacity) = property(node, "fill-opacity").and_then(parse_opacity) {
+            style.fill_opacity = opacity;
+        }
+        if let Some(rule) = property(node, "fill-rule").and_then(parse_fill_rule) {
+            style.fill_rule = rule;
+        }
+        if let Some(rule) = property(node, "clip-rule").and_then(parse_fill_rule) {
+            style.clip_rule = rule;
+        }
+        if let Some(stroke) = property(node, "stroke").and_then(|v| parser.parse_paint(v, &style)) {
+            style.stroke = stroke;
+        }
+        if let Some(opacity) = property(node, "stroke-opacity").and_then(parse_opacity) {
+            style.stroke_opacity = opacity;
+        }
+
+        let stroke = &mut style.stroke_style;
+        if let Some(width) = property(node, "stroke-width").and_then(|v| v.parse().ok()) {
+            stroke.width = parser.length(width, Axis::Diagonal, style.font_size);
+        }
+        match property(node, "stroke-linecap") {
+            Some("butt") => stroke.cap = LineCap::Butt,
+            Some("round") => stroke.cap = LineCap::Round,
+            Some("square") => stroke.cap = LineCap::Square,
+            _ => {}
+        }
+        match property(node, "stroke-linejoin") {
+            Some("miter") => stroke.join = LineJoin::Miter,
+            Some("round") => stroke.join = LineJoin::Round,
+            Some("bevel") => stroke.join = LineJoin::Bevel,
+            _ => {}
+        }
+        if let Some(limit) = property(node, "stroke-miterlimit").and_then(|v| v.parse().ok()) {
+            stroke.miter_limit = limit;
+        }
+        match property(node, "stroke-dasharray") {
+            Some("none") => stroke.dash_array.clear(),
+            Some(value) => {
+                stroke.dash_array = svgtypes::LengthListParser::from(value)
+                    .filter_map(Result::ok)
+                    .map(|length| parser.length(length, Axis::Diagonal, style.font_size))
+                    .collect();
+            }
+            None => {}
+        }
+        if let Some(offset) = property(node, "stroke-dashoffset").and_then(|v| v.parse().ok()) {
+            stroke.dash_offset = parser.length(offset, Axis::Diagonal, style.font_size);
+        }
+
+        match property(node, "text-anchor") {
+            Some("start") => style.text_anchor = TextAlign::Left,
+            Some("middle") => style.text_anchor = TextAlign::Center,
+            Some("end") => style.text_anchor = TextAlign::Right,
+            _ => {}
+        }
+        match property(node, "visibility") {
+            Some("visible") => style.visible = true,
+            Some("hidden" | "collapse") => style.visible = false,
+            _ => {}
+        }
+
+        style
+    }
+
+    fn fill_paint(&self) -> Option<SvgPaint> {
+        let kind = self.fill.clone()?;
+        Some(SvgPaint {
+            kind,
+            opacity: self.fill_opacity,
+        })
+    }
+
+    fn stroke_paint(&self) -> Option<(SvgPaint, StrokeStyle)> {
+        let kind = self.stroke.clone()?;
+        if self.stroke_style.width <= 0.0 {
+            return None;
+        }
+        let paint = SvgPaint {
+            kind,
+            opacity: self.stroke_opacity,
+        };
+        Some((paint, self.stroke_style.clone()))
+    }
+}
+
+struct Parser<'a, 'input> {
+    /// Elements by their `id`
+    ids: HashMap<&'a str, XmlNode<'a, 'input>>,
+
+    /// Size that percentage lengths refer to
+    viewport: Size,
+
+    /// Gradients resolved so far, by `id`
+    gradients: HashMap<String, Option<Arc<GradientDef>>>,
+
+    /// Elements referenced by the `use` elements being expanded, outermost
+    /// first
+    expanding: Vec<roxmltree::NodeId>,
+
+    /// Number of elements parsed so far
+    nodes: usize,
+}
+
+impl<'a, 'input> Parser<'a, 'input> {
+    fn parse_children(&mut self, node: XmlNode<'a, 'input>, style: &Style) -> Vec<Node> {
+        node.children()
+            .filter_map(|child| self.parse_element(child, style))
+            .collect()
+    }
+
+    fn parse_element(&mut self, node: XmlNode<'a, 'input>, parent: &Style) -> Option<Node> {
+        if !node.is_element() || property(node, "display") == Some("none") {
+            return None;
+        }
+        if self.nodes >= MAX_NODES {
+            if self.nodes == MAX_NODES {
+                log::warn!("Ignoring elements past the first {MAX_NODES}");
+                self.nodes += 1;
+            }
+            return None;
+        }
+        self.nodes += 1;
+        let style = parent.inherit(node, self);
+
+        let mut content = match node.tag_name().name() {
+            "g" | "a" => Node::Group(Group {
+                children: self.parse_children(node, &style),
+                ..Default::default()
+            }),
+            "svg" => self.parse_nested_svg(node, &style),
+            "use" => self.parse_use(node, &style)?,
+            "text" => Node::Text(self.parse_text(node, &style)?),
+            _ => {
+                let path = self.shape_path(node, style.font_size)?;
+                if !style.visible {
+                    return None;
+                }
+                Node::Shape(Shape {
+                    bounds: path.bounds(),
+                    path: path.with_fill_rule(style.fill_rule),
+                    fill: style.fill_paint(),
+                    stroke: style.stroke_paint(),
+                })
+            }
+        };
+
+        let transform = transform_attribute(node, "transform");
+        let opacity = opacity_property(node);
+        let clip_path = match property(node, "clip-path") {
+            Some(value) if value != "none" => self.parse_clip_path(value, node_bounds(&content)),
+            _ => None,
+        };
+        if transform == Matrix3x3::IDENTITY && opacity == 1.0 && clip_path.is_none() {
+            return Some(content);
+        }
+
+        // Put the element's own attributes on its group, or wrap it in one
+        match &mut content {
+            Node::Group(group)
+                if group.transform == Matrix3x3::IDENTITY
+                    && group.opacity == 1.0
+                    && group.clip_path.is_none() =>
+            {
+                group.transform = transform;
+                group.opacity = opacity;
+                group.clip_path = clip_path;
+                Some(content)
+            }
+            _ => Some(Node::Group(Group {
+                transform,
+                opacity,
+                clip_path,
+                children: vec![content],
+            })),
+        }
+    }
+
+    /// Parse an inner `svg` element as a group positioned at its `x` and `y`
+    fn parse_nested_svg(&mut self, node: XmlNode<'a, 'input>, style: &Style) -> Node {
+        let x = self.length_attribute(node, "x", Axis::X, style.font_size, 0.0);
+        let y = self.length_attribute(node, "y", Axis::Y, style.font_size, 0.0);
+        let mut transform = Matrix3x3::from_translation(Point::new(x, y));
+
+        let view_box = node
+            .attribute("viewBox")
+            .and_then(|value| value.parse::<svgtypes::ViewBox>().ok())
+            .filter(|view_box| view_box.w > 0.0 && view_box.h > 0.0);
+        if let Some(view_box) = view_box {
+            let view_box = Rect::new(
+                view_box.x as f32,
+                view_box.y as f32,
+                view_box.w as f32,
+                view_box.h as f32,
+            );
+            let size = Size::new(
+                self.length_attribute(node, "width", Axis::X, style.font_size, view_box.width),
+                self.length_attribute(node, "height", Axis::Y, style.font_size, view_box.height),
+            );
+            transform *= view_box_transform(view_box, size, node.attribute("preserveAspectRatio"));
+        }
+
+        Node::Group(Group {
+            transform,
+            children: self.parse_children(node, style),
+            ..Default::default()
+        })
+    }
+
+    /// Parse a `use` element as a group holding a copy of what it references
+    fn parse_use(&mut self, node: XmlNode<'a, 'input>, style: &Style) -> Option<Node> {
+        let target = *self.ids.get(href(node)?)?;
+        // A `use` inside what it references, directly or through other
+        // `use` elements, would copy itself forever
+        if node.ancestors().any(|ancestor| ancestor == target)
+            || self.expanding.contains(&target.id())
+        {
+            log::warn!("Ignoring <use> that references itself");
+            return None;
+        }
+        if self.expanding.len() >= MAX_REFERENCE_DEPTH {
+            log::warn!("Ignoring <use> nested more than {MAX_REFERENCE_DEPTH} deep");
+            return None;
+        }
+
+        self.expanding.push(target.id());
+        let children = if target.tag_name().name() == "symbol" {
+            let symbol_style = style.inherit(target, self);
+            self.parse_children(target, &symbol_style)
+        } else {
+            self.parse_element(target, style).into_iter().collect()
+        };
+        self.expanding.pop();
+
+        let x = self.length_attribute(node, "x", Axis::X, style.font_size, 0.0);
+        let y = self.length_attribute(node, "y", Axis::Y, style.font_size, 0.0);
+        Some(Node::Group(Group {
+            transform: Matrix3x3::from_translation(Point::new(x, y)),
+            children,
+            ..Default::default()
+        }))
+    }
+
+    fn parse_text(&mut self, node: XmlNode, style: &Style) -> Option<Text> {
+        if !style.visible {
+            return None;
+        }
+
+        // Collapse whitespace as `xml:space="default"` does
+        let content: String = node
+            .descendants()
+            .filter(|child| child.is_text())
+            .filter_map(|child| child.text())
+            .collect();
+        let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return None;
+        }
+
+        // Only the first of a list of positions is used
+        let first = |name: &str, axis: Axis| {
+            node.attribute(name)
+                .and_then(|value| svgtypes::LengthListParser::from(value).next())
+                .and_then(Result::ok)
+                .map_or(0.0, |length| self.length(length, axis, style.font_size))
+        };
+
+        Some(Text {
+            text,
+            position: Point::new(first("x", Axis::X), first("y", Axis::Y)),
+            font_size: style.font_size,
+            anchor: style.text_anchor,
+            fill: style.fill_paint(),
+        })
+    }
+
+    /// Build the outline of a basic shape or `path` element
+    fn shape_path(&self, node: XmlNode, font_size: f32) -> Option<Path> {
+        let length =
+            |name: &str, axis: Axis| self.length_attribute(node, name, axis, font_size, 0.0);
+
+        let path = match node.tag_name().name() {
+            "path" => path::parse_svg_path_data(node.attribute("d")?).0,
+            "rect" => {
+                let rect = Rect::new(
+                    length("x", Axis::X),
+                    length("y", Axis::Y),
+                    length("width", Axis::X),
+                    length("height", Axis::Y),
+                );
+                if rect.is_empty() {
+                    return None;
+                }
+
+                // A missing radius takes the value of the other one
+                let rx = node.attribute("rx").map(|_| length("rx", Axis::X));
+                let ry = node.attribute("ry").map(|_| length("ry", Axis::Y));
+                let (rx, ry) = match (rx, ry) {
+                    (Some(rx), Some(ry)) => (rx, ry),
+                    (Some(r), None) | (None, Some(r)) => (r, r),
+                    (None, None) => (0.0, 0.0),
+                };
+                let rrect = RRect::new(
+                    rect,
+                    rx.clamp(0.0, rect.width / 2.0),
+                    ry.clamp(0.0, rect.height / 2.0),
+                );
+                PathBuilder::new().add_rrect(rrect).build()
+            }
+            "circle" => {
+                let radius = length("r", Axis::Diagonal);
+                if radius <= 0.0 {
+                    return None;
+                }
+                let center = Point::new(length("cx", Axis::X), length("cy", Axis::Y));
+                PathBuilder::new().add_circle(center, radius).build()
+            }
+            "ellipse" => {
+                let (rx, ry) = (length("rx", Axis::X), length("ry", Axis::Y));
+                if rx <= 0.0 || ry <= 0.0 {
+                    return None;
+                }
+                let (cx, cy) = (length("cx", Axis::X), length("cy", Axis::Y));
+                let rect = Rect::new(cx - rx, cy - ry, rx * 2.0, ry * 2.0);
+                PathBuilder::new().add_oval(rect).build()
+            }
+            "line" => PathBuilder::new()
+                .move_to(Point::new(length("x1", Axis::X), length("y1", Axis::Y)))
+                .line_to(Point::new(length("x2", Axis::X), length("y2", Axis::Y)))
+                .build(),
+            name @ ("polyline" | "polygon") => {
+                let points: Vec<Point> = svgtypes::PointsParser::from(node.attribute("points")?)
+                    .map(|(x, y)| Point::new(x as f32, y as f32))
+                    .collect();
+                if points.len() < 2 {
+                    return None;
+                }
+                PathBuilder::new()
+                    .add_polygon(&points, name == "polygon")
+                    .build()
+            }
+            _ => return None,
+        };
+        Some(path)
+    }
+
+    /// Resolve a `clip-path` reference into a single path in user space
+    fn parse_clip_path(&mut self, value: &str, bounds: Option<Rect>) -> Option<Path> {
+        let id = svgtypes::FuncIRI::from_str(value).ok()?.0;
+        let clip = *self.ids.get(id)?;
+        if clip.tag_name().name() != "clipPath" {
+            return None;
+        }
+
+        let mut transform = transform_attribute(clip, "transform");
+        if clip.attribute("clipPathUnits") == Some("objectBoundingBox") {
+            // Without a bounding box the clip hides everything
+            match bounds.filter(|bounds| !bounds.is_empty()) {
+                Some(bounds) => transform *= bounding_box_matrix(bounds),
+                None => return Some(PathBuilder::new().build()),
+            }
+        }
+
+        let style = Style::default().inherit(clip, self);
+        let mut paths = Vec::new();
+        for child in clip.children().filter(|child| child.is_element()) {
+            if property(child, "display") == Some("none") {
+                continue;
+            }
+            let child_style = style.inherit(child, self);
+            let Some(path) = self.shape_path(child, child_style.font_size) else {
+                continue;
+            };
+            if !child_style.visible {
+                continue;
+            }
+            let matrix = transform * transform_attribute(child, "transform");
+            paths.push(
+                path.transform(&matrix)
+                    .with_fill_rule(child_style.clip_rule),
+            );
+        }
+
+        // The clip is the union of its children, each under its own clip rule
+        let mut paths = paths.into_iter();
+        let first = paths.next().unwrap_or_else(|| PathBuilder::new().build());
+        Some(paths.fold(first, |clip, path| clip.op(&path, PathOp::Union)))
+    }
+
+    /// Parse a `fill` or `stroke` value
+    ///
+    /// Returns `None` to keep the inherited paint, or `Some(None)` for no paint.
+    fn parse_paint(&mut self, value: &str, style: &Style) -> Option<Option<PaintKind>> {
+        let fallback = |fallback: Option<svgtypes::PaintFallback>| match fallback {
+            Some(svgtypes::PaintFallback::Color(color)) => Some(PaintKind::Color(to_color(color))),
+            Some(svgtypes::PaintFallback::CurrentColor) => Some(PaintKind::Color(style.color)),
+            Some(svgtypes::PaintFallback::None) | None => None,
+        };
+
+        match svgtypes::Paint::from_str(value).ok()? {
+            svgtypes::Paint::None => Some(None),
+            svgtypes::Paint::Inherit => None,
+            svgtypes::Paint::CurrentColor => Some(Some(PaintKind::Color(style.color))),
+            svgtypes::Paint::Color(color) => Some(Some(PaintKind::Color(to_color(color)))),
+            svgtypes::Paint::FuncIRI(id, fallback_paint) => {
+                Some(match self.ids.get(id).map(|node| node.tag_name().name()) {
+                    Some("linearGradient" | "radialGradient") => {
+                        self.gradient(id).map(PaintKind::Gradient)
+                    }
+                    _ => fallback(fallback_paint),
+                })
+            }
+            svgtypes::Paint::ContextFill | svgtypes::Paint::ContextStroke => Some(None),
+        }
+    }
+
+    /// Resolve a gradient, following `href`s for inherited attributes and stops
+    fn gradient(&mut self, id: &str) -> Option<Arc<GradientDef>> {
+        if let Some(gradient) = self.gradients.get(id) {
+            return gradient.clone();
+        }
+
+        let node = *self.ids.get(id)?;
+        let mut chain = vec![node];
+        while chain.len() < MAX_REFERENCE_DEPTH {
+            let next = href(*chain.last().unwrap())
+                .and_then(|id| self.ids.get(id))
+                .filter(|next| next.tag_name().name().ends_with("Gradient"));
+            match next {
+                Some(&next) => chain.push(next),
+                None => break,
+            }
+        }
+        let attribute = |name: &str| chain.iter().find_map(|node| node.attribute(name));
+
+        let bounding_box_units = attribute("gradientUnits") != Some("userSpaceOnUse");
+        let coordinate = |name: &str, axis: Axis, default: &str| {
+            let length = attribute(name)
+                .unwrap_or(default)
+                .parse::<Length>()
+                .unwrap_or(Length::zero());
+            if bounding_box_units {
+                match length.unit {
+                    LengthUnit::Percent => length.number as f32 / 100.0,
+                    _ => length.number as f32,
+                }
+            } else {
+                self.length(length, axis, DEFAULT_FONT_SIZE)
+            }
+        };
+
+        let geometry = if node.tag_name().name() == "linearGradient" {
+            GradientGeometry::Linear {
+                start: Point::new(
+                    coordinate("x1", Axis::X, "0%"),
+                    coordinate("y1", Axis::Y, "0%"),
+                ),
+                end: Point::new(
+                    coordinate("x2", Axis::X, "100%"),
+                    coordinate("y2", Axis::Y, "0%"),
+                ),
+            }
+        } else {
+            let center = Point::new(
+                coordinate("cx", Axis::X, "50%"),
+                coordinate("cy", Axis::Y, "50%"),
+            );
+            let radius = coordinate("r", Axis::Diagonal, "50%");
+            let focal = Point::new(
+                attribute("fx").map_or(center.x, |_| coordinate("fx", Axis::X, "50%")),
+                attribute("fy").map_or(center.y, |_| coordinate("fy", Axis::Y, "50%")),
+            );
+            // A focal point outside the circle is moved onto it, as in SVG 1.1
+            let offset = focal - center;
+            let focal = if offset.length() > radius * 0.99 {
+                center + offset.normalize_or_zero() * radius * 0.99
+            } else {
+                focal
+            };
+            GradientGeometry::Radial {
+                center,
+                radius,
+                focal,
+            }
+        };
+
+        let stops = chain
+            .iter()
+            .map(|node| gradient_stops(*node))
+            .find(|stops| !stops.is_empty())
+            .unwrap_or_default();
+        let gradient = (!stops.is_empty()).then(|| {
+            Arc::new(GradientDef {
+                geometry,
+                bounding_box_units,
+                transform: chain
+                    .iter()
+                    .find(|node| node.has_attribute("gradientTransform"))
+                    .map_or(Matrix3x3::IDENTITY, |node| {
+                        transform_attribute(*node, "gradientTransform")
+                    }),
+                tile_mode: match attribute("spreadMethod") {
+                    Some("reflect") => TileMode::Mirror,
+                    Some("repeat") => TileMode::Repeat,
+                    _ => TileMode::Pad,
+                },
+                stops,
+            })
+        });
+
+        self.gradients.insert(id.to_string(), gradient.clone());
+        gradient
+    }
+
+    fn length_attribute(
+        &self,
+        node: XmlNode,
+        name: &str,
+        axis: Axis,
+        font_size: f32,
+        default: f32,
+    ) -> f32 {
+        node.attribute(name)
+            .and_then(|value| value.parse::<Length>().ok())
+            .map_or(default, |length| self.length(length, axis, font_size))
+    }
+
+    /// Convert a length to user units
+    fn length(&self, length: Length, axis: Axis, font_size: f32) -> f32 {
+        if length.unit != LengthUnit::Percent {
+            return absolute_length(length, font_size).unwrap_or(0.0);
+        }
+
+        let Size { width, height } = self.viewport;
+        let reference = match axis {
+            Axis::X => width,
+            Axis::Y => height,
+            Axis::Diagonal => ((width * width + height * height) / 2.0).sqrt(),
+        };
+        reference * length.number as f32 / 100.0
+    }
+}
+
+/// Convert a length that does not depend on the viewport to user units
+fn absolute_length(length: Length, font_size: f32) -> Option<f32> {
+    let number = length.number as f32;
+    let pixels = match length.unit {
+        LengthUnit::None | LengthUnit::Px => number,
+        LengthUnit::Em => number * font_size,
+        LengthUnit::Ex => number * font_size / 2.0,
+        LengthUnit::In => number * 96.0,
+        LengthUnit::Cm => number * 96.0 / 2.54,
+        LengthUnit::Mm => number * 96.0 / 25.4,
+        LengthUnit::Pt => number * 4.0 / 3.0,
+        LengthUnit::Pc => number * 16.0,
+        LengthUnit::Percent => return None,
+    };
+    Some(pixels)
+}
+
+/// Get a property from the `style` attribute or, failing that, its presentation attribute
+fn property<'a>(node: XmlNode<'a, '_>, name: &str) -> Option<&'a str> {
+    let declared = node.attribute("style").and_then(|style| {
+        style
+            .split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .filter(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim().trim_end_matches("!important").trim())
+            .next_back()
+    });
+    declared
+        .or_else(|| node.attribute(name))
+        .filter(|value| *value != "inherit")
+}
+
+fn href<'a>(node: XmlNode<'a, '_>) -> Option<&'a str> {
+    node.attribute((XLINK_NS, "href"))
+        .or_else(|| node.attribute("href"))
+        .and_then(|href| href.strip_prefix('#'))
+}
+
+/// Parse an opacity as a number or percentage, clamped to 0-1
+fn parse_opacity(value: &str) -> Option<f32> {
+    let opacity = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    Some(opacity.clamp(0.0, 1.0))
+}
+
+fn opacity_property(node: XmlNode) -> f32 {
+    property(node, "opacity")
+        .and_then(parse_opacity)
+        .unwrap_or(1.0)
+}
+
+fn parse_fill_rule(value: &str) -> Option<FillRule> {
+    match value {
+        "nonzero" => Some(FillRule::NonZero),
+        "evenodd" => Some(FillRule::EvenOdd),
+        _ => None,
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    value.parse::<svgtypes::Color>().ok().map(to_color)
+}
+
+fn to_color(color: svgtypes::Color) -> Color {
+    Color::rgba(color.red, color.green, color.blue, color.alpha)
+}
+
+fn transform_attribute(node: XmlNode, name: &str) -> Matrix3x3 {
+    let Some(transform) = node
+        .attribute(name)
+        .and_then(|value| value.parse::<svgtypes::Transform>().ok())
+    else {
+        return Matrix3x3::IDENTITY;
+    };
+    let svgtypes::Transform { a, b, c, d, e, f } = transform;
+    Matrix3x3::from_cols_array(&[
+        a as f32, b as f32, 0.0, c as f32, d as f32, 0.0, e as f32, f as f32, 1.0,
+    ])
+}
+
+/// Map the unit square onto a bounding box
+pub(super) fn bounding_box_matrix(bounds: Rect) -> Matrix3x3 {
+    Matrix3x3::from_translation(Point::new(bounds.x, bounds.y))
+        * Matrix3x3::from_scale(Point::new(bounds.width, bounds.height))
+}
+
+/// Map a view box onto a viewport according to `preserveAspectRatio`
+fn view_box_transform(view_box: Rect, size: Size, aspect: Option<&str>) -> Matrix3x3 {
+    let aspect = aspect
+        .and_then(|value| value.parse::<svgtypes::AspectRatio>().ok())
+        .unwrap_or(svgtypes::AspectRatio {
+            defer: false,
+            align: svgtypes::Align::XMidYMid,
+            slice: false,
+        });
+
+    let scale_x = size.width / view_box.width;
+    let scale_y = size.height / view_box.height;
+    let (scale_x, scale_y, align_x, align_y) = {
+        use svgtypes::Align::*;
+        let (align_x, align_y) = match aspect.align {
+            None => {
+                let translate = Point::new(-view_box.x * scale_x, -view_box.y * scale_y);
+                return Matrix3x3::from_translation(translate)
+                    * Matrix3x3::from_scale(Point::new(scale_x, scale_y));
+            }
+            XMinYMin => (0.0, 0.0),
+            XMidYMin => (0.5, 0.0),
+            XMaxYMin => (1.0, 0.0),
+            XMinYMid => (0.0, 0.5),
+            XMidYMid => (0.5, 0.5),
+            XMaxYMid => (1.0, 0.5),
+            XMinYMax => (0.0, 1.0),
+            XMidYMax => (0.5, 1.0),
+            XMaxYMax => (1.0, 1.0),
+        };
+        let scale = if aspect.slice {
+            scale_x.max(scale_y)
+        } else {
+            scale_x.min(scale_y)
+        };
+        (scale, scale, align_x, align_y)
+    };
+
+    let translate = Point::new(
+        (size.width - view_box.width * scale_x) * align_x - view_box.x * scale_x,
+        (size.height - view_box.height * scale_y) * align_y - view_box.y * scale_y,
+    );
+    Matrix3x3::from_translation(translate) * Matrix3x3::from_scale(Point::new(scale_x, scale_y))
+}
+
+/// Read the `stop` children of a gradient element
+fn gradient_stops(node: XmlNode) -> Vec<GradientStop> {
+    let mut last_offset = 0.0f32;
+    node.children()
+        .filter(|child| child.has_tag_name("stop"))
+        .map(|stop| {
+            let offset = stop
+                .attribute("offset")
+                .and_then(parse_opacity)
+                .unwrap_or(0.0);
+            // Offsets may not decrease
+            last_offset = last_offset.max(offset);
+
+            let mut color = match property(stop, "stop-color") {
+                Some("currentColor") => property(stop, "color").and_then(parse_color),
+                Some(value) => parse_color(value),
+                None => None,
+            }
+            .unwrap_or(Color::BLACK);
+            let opacity = property(stop, "stop-opacity")
+                .and_then(parse_opacity)
+                .unwrap_or(1.0);
+            color.a = (color.a as f32 * opacity).round() as u8;
+            GradientStop::new(last_offset, color)
+        })
+        .collect()
+}
+
+/// Get the bounding box of a node in its parent's coordinates
+fn node_bounds(node: &Node) -> Option<Rect> {
+    match node {
+        Node::Shape(shape) => shape.bounds,
+        Node::Text(_) => None,
+        Node::Group(group) => group
+            .children
+            .iter()
+            .filter_map(node_bounds)
+            .map(|bounds| transform_rect(&group.transform, bounds))
+            .reduce(|a, b| {
+                Rect::from_ltrb(
+                    a.left().min(b.left()),
+                    a.top().min(b.top()),
+                    a.right().max(b.right()),
+                    a.bottom().max(b.bottom()),
+                )
+            }),
+    }
+}
+
+/// Get the bounding box of a transformed rectangle
+pub(super) fn transform_rect(matrix: &Matrix3x3, rect: Rect) -> Rect {
+    let corners = [
+        Point::new(rect.left(), rect.top()),
+        Point::new(rect.right(), rect.top()),
+        Point::new(rect.right(), rect.bottom()),
+        Point::new(rect.left(), rect.bottom()),
+    ]
+    .map(|corner| matrix.transform_point2(corner));
+    let min = corners.into_iter().reduce(Point::min).unwrap();
+    let max = corners.into_iter().reduce(Point::max).unwrap();
+    Rect::from_ltrb(min.x, min.y, max.x, max.y)
+}
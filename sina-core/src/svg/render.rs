@@ -0,0 +1,184 @@
+//! Drawing the render tree onto a canvas
+
+use super::parse::bounding_box_matrix;
+use super::{GradientGeometry, Group, Node, PaintKind, Shape, SvgPaint, Text};
+use crate::effects::Gradient;
+use crate::text::{Font, TextAlign};
+use crate::{Canvas, ClipOp, CpuSurface, Matrix3x3, Paint, Point, Rect};
+
+/// Largest offscreen layer for a semi-transparent group, in pixels
+const MAX_LAYER_AREA: f32 = 4096.0 * 4096.0;
+
+/// Draw a group, clipped to `viewport` in device space
+pub(super) fn render_group(
+    group: &Group,
+    canvas: &mut dyn Canvas,
+    viewport: Rect,
+    font: Option<&Font>,
+) {
+    if group.opacity <= 0.0 {
+        return;
+    }
+
+    canvas.save();
+    canvas.concat(group.transform);
+    if let Some(clip_path) = &group.clip_path {
+        canvas.clip_path(clip_path, ClipOp::Intersect, true);
+    }
+    if group.opacity < 1.0 && !draws_once(group) {
+        render_layer(group, canvas, viewport, font);
+    } else {
+        canvas.set_global_alpha(canvas.global_alpha() * group.opacity);
+        render_children(group, canvas, viewport, font);
+    }
+    canvas.restore();
+}
+
+/// Check whether a group paints each pixel at most once, so that its opacity
+/// can be applied to its only child instead of to a layer
+fn draws_once(group: &Group) -> bool {
+    match group.children.as_slice() {
+        [Node::Shape(shape)] => shape.fill.is_none() || shape.stroke.is_none(),
+        [Node::Text(_)] => true,
+        _ => false,
+    }
+}
+
+/// Draw a group's children into an offscreen layer covering the viewport,
+/// then composite the layer with the group's opacity
+///
+/// Overlapping children then cover each other as they would if the group
+/// were opaque, rather than showing through.
+fn render_layer(group: &Group, canvas: &mut dyn Canvas, viewport: Rect, font: Option<&Font>) {
+    let (x, y) = (viewport.left().floor(), viewport.top().floor());
+    let width = viewport.right().ceil() - x;
+    let height = viewport.bottom().ceil() - y;
+    if !(width > 0.0 && height > 0.0) {
+        return;
+    }
+    if width * height > MAX_LAYER_AREA {
+        // Too large to buffer: fade each child instead
+        canvas.set_global_alpha(canvas.global_alpha() * group.opacity);
+        render_children(group, canvas, viewport, font);
+        return;
+    }
+
+    let mut layer = CpuSurface::new(width as i32, height as i32);
+    let offset = Point::new(-x, -y);
+    layer.set_matrix(Matrix3x3::from_translation(offset) * canvas.get_matrix());
+    let layer_viewport = Rect::new(
+        viewport.x - x,
+        viewport.y - y,
+        viewport.width,
+        viewport.height,
+    );
+    render_children(group, &mut layer, layer_viewport, font);
+
+    canvas.reset_matrix();
+    canvas.set_global_alpha(canvas.global_alpha() * group.opacity);
+    canvas.draw_image(&layer.to_image(), Point::new(x, y), &Paint::default());
+}
+
+fn render_children(group: &Group, canvas: &mut dyn Canvas, viewport: Rect, font: Option<&Font>) {
+    for child in &group.children {
+        match child {
+            Node::Group(group) => render_group(group, canvas, viewport, font),
+            Node::Shape(shape) => render_shape(shape, canvas),
+            Node::Text(text) => {
+                if let Some(font) = font {
+                    render_text(text, canvas, font);
+                }
+            }
+        }
+    }
+}
+
+fn render_shape(shape: &Shape, canvas: &mut dyn Canvas) {
+    if let Some(paint) = shape
+        .fill
+        .as_ref()
+        .and_then(|fill| to_paint(fill, shape.bounds))
+    {
+        canvas.draw_path(&shape.path, &paint);
+    }
+    if let Some((stroke, style)) = &shape.stroke {
+        if let Some(mut paint) = to_paint(stroke, shape.bounds) {
+            paint.set_stroke(style.clone());
+            canvas.draw_path(&shape.path, &paint);
+        }
+    }
+}
+
+fn render_text(text: &Text, canvas: &mut dyn Canvas, font: &Font) {
+    let Some(fill) = &text.fill else {
+        return;
+    };
+
+    let scale = text.font_size / font.units_per_em() as f32;
+    let width: f32 = text
+        .text
+        .chars()
+        .map(|c| font.glyph_advance(font.glyph_index(c).unwrap_or(0), text.font_size))
+        .sum();
+    let mut position = text.position;
+    position.x -= match text.anchor {
+        TextAlign::Left | TextAlign::Justify => 0.0,
+        TextAlign::Center => width / 2.0,
+        TextAlign::Right => width,
+    };
+
+    let ascent = font.ascender() as f32 * scale;
+    let descent = -(font.descender() as f32) * scale;
+    let bounds = Rect::new(position.x, position.y - ascent, width, ascent + descent);
+    if let Some(paint) = to_paint(fill, Some(bounds)) {
+        canvas.draw_text(&text.text, position, font, text.font_size, &paint);
+    }
+}
+
+/// Convert an SVG paint to a canvas paint for an element with the given bounds
+///
+/// Returns `None` when nothing would be drawn.
+fn to_paint(svg_paint: &SvgPaint, bounds: Option<Rect>) -> Option<Paint> {
+    if svg_paint.opacity <= 0.0 {
+        return None;
+    }
+
+    match &svg_paint.kind {
+        PaintKind::Color(color) => {
+            let mut color = *color;
+            color.a = (color.a as f32 * svg_paint.opacity).round() as u8;
+            Some(Paint::with_color(color))
+        }
+        PaintKind::Gradient(gradient) => {
+            let local_matrix = if gradient.bounding_box_units {
+                // Bounding box units are undefined for zero-width or zero-height elements
+                let bounds = bounds.filter(|bounds| !bounds.is_empty())?;
+                bounding_box_matrix(bounds) * gradient.transform
+            } else {
+                gradient.transform
+            };
+
+            let stops = gradient.stops.clone();
+            let shader = match gradient.geometry {
+                GradientGeometry::Linear { start, end } => Gradient::linear(start, end, stops),
+                GradientGeometry::Radial {
+                    center,
+                    radius,
+                    focal,
+                } if focal == center => Gradient::radial(center, radius, stops),
+                GradientGeometry::Radial {
+                    center,
+                    radius,
+                    focal,
+                } => Gradient::two_point_conical(focal, 0.0, center, radius, stops),
+            }
+            .with_tile_mode(gradient.tile_mode)
+            .with_local_matrix(local_matrix);
+
+            let mut paint = Paint::new();
+            paint.color.a = (255.0 * svg_paint.opacity).round() as u8;
+            paint.set_shader(shader);
+            Some(paint)
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Bidirectional text
+//!
+//! Runs the Unicode Bidirectional Algorithm (UAX #9) to split mixed
+//! left-to-right and right-to-left text into runs of one direction each,
+//! in the order they appear on screen.
+
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+
+/// Base direction of a paragraph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// Take the direction of the first strong character, or left-to-right
+    /// when there is none
+    #[default]
+    Auto,
+    /// Left-to-right, as in Latin scripts
+    Ltr,
+    /// Right-to-left, as in Arabic and Hebrew
+    Rtl,
+}
+
+impl TextDirection {
+    /// Resolve `Auto` against the text it applies to
+    pub fn resolve(self, text: &str) -> TextDirection {
+        match self {
+            TextDirection::Auto => match BidiInfo::new(text, None).paragraphs.first() {
+                Some(paragraph) if paragraph.level.is_rtl() => TextDirection::Rtl,
+                _ => TextDirection::Ltr,
+            },
+            direction => direction,
+        }
+    }
+
+    fn level(self) -> Option<Level> {
+        match self {
+            TextDirection::Auto => None,
+            TextDirection::Ltr => Some(Level::ltr()),
+            TextDirection::Rtl => Some(Level::rtl()),
+        }
+    }
+}
+
+/// A stretch of text with a single direction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BidiRun {
+    /// Byte range of the run's text
+    pub range: Range<usize>,
+    /// Whether the run reads right-to-left
+    pub rtl: bool,
+}
+
+/// Split a line of text into runs of a single direction, in visual order
+/// from left to right
+///
+/// Each paragraph of the text (separated by newlines) is laid out after the
+/// previous one.
+pub(crate) fn visual_runs(text: &str, direction: TextDirection) -> Vec<BidiRun> {
+    let info = BidiInfo::new(text, direction.level());
+    let mut runs = Vec::new();
+    for paragraph in &info.paragraphs {
+        let (levels, level_runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        runs.extend(level_runs.into_iter().map(|range| BidiRun {
+            rtl: levels[range.start].is_rtl(),
+            range,
+        }));
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(text: &str, direction: TextDirection) -> Vec<(&str, bool)> {
+        visual_runs(text, direction)
+            .into_iter()
+            .map(|run| (&text[run.range], run.rtl))
+            .collect()
+    }
+
+    #[test]
+    fn test_visual_runs() {
+        assert_eq!(runs("hello", TextDirection::Auto), [("hello", false)]);
+        assert_eq!(runs("", TextDirection::Auto), []);
+
+        // Hebrew inside a left-to-right sentence keeps its place
+        assert_eq!(
+            runs("say שלום now", TextDirection::Auto),
+            [("say ", false), ("שלום", true), (" now", false)]
+        );
+
+        // A right-to-left paragraph puts its first run on the right, and
+        // numbers still read left-to-right
+        assert_eq!(
+            runs("שלום world 42", TextDirection::Auto),
+            [("world 42", false), ("שלום ", true)]
+        );
+        assert_eq!(runs("abc def", TextDirection::Rtl), [("abc def", false)]);
+        assert_eq!(
+            runs("abc.", TextDirection::Rtl),
+            [(".", true), ("abc", false)]
+        );
+    }
+
+    #[test]
+    fn test_resolve_direction() {
+        assert_eq!(TextDirection::Auto.resolve("abc"), TextDirection::Ltr);
+        assert_eq!(TextDirection::Auto.resolve("123 مرحبا"), TextDirection::Rtl);
+        assert_eq!(TextDirection::Auto.resolve("123"), TextDirection::Ltr);
+        assert_eq!(TextDirection::Rtl.resolve("abc"), TextDirection::Rtl);
+    }
+}
@@ -1,8 +1,11 @@
 //! Bitmap font support for CBDT/SBIX tables
 //!
-//! Handles embedded bitmap glyphs (PNG, TIFF, etc.) in fonts.
+//! Handles embedded bitmap glyphs (PNG, JPEG, TIFF and raw BGRA) in fonts.
+//! Fonts store these in strikes, one per pixel size; a glyph is taken from
+//! the best strike for the requested size and scaled by the caller.
 
 use super::Font;
+use ttf_parser::{GlyphId, RasterImageFormat, Tag};
 
 /// Bitmap glyph data
 #[derive(Debug, Clone)]
@@ -16,10 +19,10 @@ pub struct BitmapGlyph {
     /// Height in pixels
     pub height: u32,
 
-    /// Horizontal bearing (offset from origin)
+    /// Horizontal bearing (offset of the left edge from the origin)
     pub bearing_x: i16,
 
-    /// Vertical bearing (offset from baseline)
+    /// Vertical bearing (offset of the bottom edge above the baseline)
     pub bearing_y: i16,
 
     /// Horizontal advance
@@ -35,25 +38,218 @@ pub struct BitmapFontRenderer;
 impl BitmapFontRenderer {
     /// Check if font has bitmap strikes
     pub fn has_bitmaps(font: &Font) -> bool {
+        let tables = font.face().tables();
+        tables.cbdt.is_some() || tables.sbix.is_some()
+    }
+
+    /// Get the pixel sizes of the font's bitmap strikes, smallest first
+    pub fn available_sizes(font: &Font) -> Vec<u16> {
+        let face = font.face();
+        let mut sizes: Vec<u16> = face
+            .raw_face()
+            .table(Tag::from_bytes(b"CBLC"))
+            .map(cblc_sizes)
+            .unwrap_or_default();
+        if let Some(sbix) = face.tables().sbix {
+            sizes.extend(sbix.strikes.into_iter().map(|strike| strike.pixels_per_em));
+        }
+        // Strikes claiming zero pixels per em can't be scaled to any size
+        sizes.retain(|&size| size > 0);
+        sizes.sort_unstable();
+        sizes.dedup();
+        sizes
+    }
+
+    /// Pick the strike to draw a size from
+    ///
+    /// This is the smallest strike at least as large as the requested size,
+    /// so that bitmaps are scaled down, or else the largest strike.
+    pub fn best_size(sizes: &[u16], ppem: u16) -> Option<u16> {
+        let larger = sizes.iter().copied().filter(|&size| size >= ppem).min();
+        larger.or_else(|| sizes.iter().copied().max())
+    }
+
+    /// Get bitmap glyph for a specific size
+    ///
+    /// The glyph comes from the best strike that has it (see
+    /// [`best_size`](Self::best_size)), unscaled; `ppem` on the result tells
+    /// which strike was used. sbix `dupe` glyphs are resolved to the glyph
+    /// they repeat. Returns `None` for glyphs without a color bitmap.
+    pub fn get_bitmap(font: &Font, glyph_id: u16, ppem: u16) -> Option<BitmapGlyph> {
         let face = font.face();
-        // Check for CBDT or SBIX tables
-        face.tables().cbdt.is_some()
+        let glyph = GlyphId(glyph_id);
+
+        let image = face
+            .raw_face()
+            .table(Tag::from_bytes(b"sbix"))
+            .and_then(|sbix| sbix_image(sbix, face.number_of_glyphs(), glyph, ppem))
+            .or_else(|| cbdt_image(face, glyph, ppem))?;
+
+        let (pixels, width, height) = match image.format {
+            BitmapFormat::Encoded => {
+                let decoded = image::load_from_memory(image.data).ok()?.to_rgba8();
+                let (width, height) = decoded.dimensions();
+                (decoded.into_vec(), width, height)
+            }
+            BitmapFormat::PremultipliedBgra => {
+                let (width, height) = (u32::from(image.width), u32::from(image.height));
+                let pixels = image.data.get(..(width * height * 4) as usize)?;
+                (unpremultiply_bgra(pixels), width, height)
+            }
+        };
+
+        let advance = face.glyph_hor_advance(glyph).unwrap_or(0) as f32
+            / face.units_per_em() as f32
+            * image.ppem as f32;
+
+        Some(BitmapGlyph {
+            pixels,
+            width,
+            height,
+            bearing_x: image.x,
+            bearing_y: image.y,
+            advance: advance.round() as u16,
+            ppem: image.ppem,
+        })
+    }
+}
+
+/// How a bitmap's pixels are stored
+enum BitmapFormat {
+    /// A PNG, JPEG or TIFF image
+    Encoded,
+    /// Raw premultiplied BGRA pixels
+    PremultipliedBgra,
+}
+
+/// A glyph bitmap as stored in the font, with its bottom-left corner
+struct RawBitmap<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    ppem: u16,
+    format: BitmapFormat,
+    data: &'a [u8],
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
+/// Read the ppem of every strike in a CBLC table
+fn cblc_sizes(cblc: &[u8]) -> Vec<u16> {
+    // Each 48-byte BitmapSize record has ppemX at byte 44
+    let count = read_u32(cblc, 4).unwrap_or(0);
+    (0..count)
+        .map_while(|i| cblc.get(8 + i * 48 + 44).map(|&ppem| u16::from(ppem)))
+        .collect()
+}
+
+/// Find a glyph's image in the best CBDT strike that has one
+fn cbdt_image<'a>(face: &ttf_parser::Face<'a>, glyph: GlyphId, ppem: u16) -> Option<RawBitmap<'a>> {
+    // ttf-parser picks strikes by the same rule as `best_size`
+    let image = face.tables().cbdt?.get(glyph, ppem)?;
+    if image.pixels_per_em == 0 {
+        return None;
     }
+    Some(RawBitmap {
+        x: image.x,
+        y: image.y,
+        width: image.width,
+        height: image.height,
+        ppem: image.pixels_per_em,
+        format: match image.format {
+            RasterImageFormat::PNG => BitmapFormat::Encoded,
+            RasterImageFormat::BitmapPremulBgra32 => BitmapFormat::PremultipliedBgra,
+            // Monochrome and grayscale strikes are not color bitmaps
+            _ => return None,
+        },
+        data: image.data,
+    })
+}
+
+/// Find a glyph's image in the best sbix strike that has one
+fn sbix_image(sbix: &[u8], num_glyphs: u16, glyph: GlyphId, ppem: u16) -> Option<RawBitmap<'_>> {
+    let count = read_u32(sbix, 4)?;
+    // The offsets end with the table, whatever the count claims
+    let strikes: Vec<(u16, &[u8])> = (0..count)
+        .map_while(|i| read_u32(sbix, 8 + i * 4))
+        .filter_map(|offset| {
+            let strike = sbix.get(offset..)?;
+            Some((read_u16(strike, 0)?, strike))
+        })
+        .filter(|&(size, _)| size > 0)
+        .collect();
+
+    // Try strikes from the best size outwards, skipping ones without the glyph
+    let mut sizes: Vec<u16> = strikes.iter().map(|&(size, _)| size).collect();
+    while let Some(size) = BitmapFontRenderer::best_size(&sizes, ppem) {
+        sizes.retain(|&other| other != size);
+        let found = strikes
+            .iter()
+            .filter(|&&(strike_size, _)| strike_size == size)
+            .find_map(|&(_, strike)| sbix_glyph(strike, num_glyphs, glyph, 0));
+        if let Some(mut image) = found {
+            image.ppem = size;
+            return Some(image);
+        }
+    }
+    None
+}
 
-    /// Get available bitmap sizes (stub - requires extended API)
-    pub fn available_sizes(_font: &Font) -> Vec<u16> {
-        // Stub - would need to parse bitmap strike tables
-        Vec::new()
+/// Read a glyph's image from an sbix strike, following `dupe` references
+fn sbix_glyph(strike: &[u8], num_glyphs: u16, glyph: GlyphId, depth: u8) -> Option<RawBitmap<'_>> {
+    if glyph.0 >= num_glyphs || depth > 8 {
+        return None;
+    }
+    let offsets = 4 + glyph.0 as usize * 4;
+    let (start, end) = (read_u32(strike, offsets)?, read_u32(strike, offsets + 4)?);
+    let record = strike.get(start..end)?;
+    let data = record.get(8..)?;
+    if data.is_empty() {
+        return None;
     }
 
-    /// Get bitmap glyph for a specific size (stub)
-    pub fn get_bitmap(_font: &Font, _glyph_id: u16, _ppem: u16) -> Option<BitmapGlyph> {
-        // Stub - would need:
-        // 1. Locate bitmap data in CBDT/SBIX
-        // 2. Decode embedded PNG/TIFF
-        // 3. Extract metrics
-        None
+    let format = match record.get(4..8)? {
+        b"png " | b"jpg " | b"tiff" => BitmapFormat::Encoded,
+        b"dupe" => {
+            return sbix_glyph(strike, num_glyphs, GlyphId(read_u16(data, 0)?), depth + 1);
+        }
+        // 'pdf ' and 'mask' graphics are not supported
+        _ => return None,
+    };
+    Some(RawBitmap {
+        x: read_u16(record, 0)? as i16,
+        y: read_u16(record, 2)? as i16,
+        width: 0,
+        height: 0,
+        ppem: 0,
+        format,
+        data,
+    })
+}
+
+/// Convert premultiplied BGRA pixels to straight RGBA bytes
+fn unpremultiply_bgra(pixels: &[u8]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(pixels.len());
+    for pixel in pixels.chunks_exact(4) {
+        let alpha = u16::from(pixel[3]);
+        for channel in [pixel[2], pixel[1], pixel[0]] {
+            rgba.push(match alpha {
+                0 => 0,
+                _ => ((u16::from(channel) * 255 + alpha / 2) / alpha).min(255) as u8,
+            });
+        }
+        rgba.push(pixel[3]);
     }
+    rgba
 }
 
 #[cfg(test)]
@@ -62,6 +258,92 @@ mod tests {
 
     #[test]
     fn test_bitmap_renderer() {
-        assert!(true); // Placeholder
+        let glyph = BitmapGlyph {
+            pixels: vec![0; 4],
+            width: 1,
+            height: 1,
+            bearing_x: 0,
+            bearing_y: 0,
+            advance: 1,
+            ppem: 16,
+        };
+        assert_eq!(
+            glyph.pixels.len(),
+            (glyph.width * glyph.height * 4) as usize
+        );
+    }
+
+    #[test]
+    fn test_best_size() {
+        let sizes = [20, 64, 109];
+        assert_eq!(BitmapFontRenderer::best_size(&sizes, 48), Some(64));
+        assert_eq!(BitmapFontRenderer::best_size(&sizes, 64), Some(64));
+        assert_eq!(BitmapFontRenderer::best_size(&sizes, 12), Some(20));
+        assert_eq!(BitmapFontRenderer::best_size(&sizes, 200), Some(109));
+        assert_eq!(BitmapFontRenderer::best_size(&[], 16), None);
+    }
+
+    #[test]
+    fn test_sbix_dupe() {
+        // A strike of three glyphs: glyph 1 is a PNG, glyph 2 a dupe of it
+        let mut strike = vec![0, 32, 0, 72];
+        for offset in [20u32, 20, 32, 42] {
+            strike.extend(offset.to_be_bytes());
+        }
+        strike.extend([0, 2, 0xFF, 0xFE]);
+        strike.extend(b"png ");
+        strike.extend([1, 2, 3, 4]);
+        strike.extend([0, 0, 0, 0]);
+        strike.extend(b"dupe");
+        strike.extend([0, 1]);
+
+        let image = sbix_glyph(&strike, 3, GlyphId(2), 0).unwrap();
+        assert_eq!((image.x, image.y, image.data), (2, -2, &[1, 2, 3, 4][..]));
+        assert!(sbix_glyph(&strike, 3, GlyphId(0), 0).is_none());
+        assert!(sbix_glyph(&strike, 3, GlyphId(3), 0).is_none());
+    }
+
+    /// Build an sbix table claiming `count` strikes, with one per size
+    /// holding a PNG for glyph 1 of 2
+    fn sbix_table(count: u32, sizes: &[u16]) -> Vec<u8> {
+        let mut table = vec![0, 1, 0, 0];
+        table.extend(count.to_be_bytes());
+        let mut offset = 8 + 4 * sizes.len() as u32;
+        let mut strikes = Vec::new();
+        for &size in sizes {
+            table.extend(offset.to_be_bytes());
+            let mut strike = [size, 72].map(u16::to_be_bytes).concat();
+            for glyph_offset in [16u32, 16, 28] {
+                strike.extend(glyph_offset.to_be_bytes());
+            }
+            strike.extend([0, 0, 0, 0]);
+            strike.extend(b"png ");
+            strike.extend([1, 2, 3, 4]);
+            offset += strike.len() as u32;
+            strikes.extend(strike);
+        }
+        table.extend(strikes);
+        table
+    }
+
+    #[test]
+    fn test_sbix_strikes() {
+        let table = sbix_table(2, &[0, 20]);
+        let image = sbix_image(&table, 2, GlyphId(1), 10).unwrap();
+        assert_eq!((image.ppem, image.data), (20, &[1, 2, 3, 4][..]));
+        // Strikes without a size are never used
+        assert!(sbix_image(&sbix_table(1, &[0]), 2, GlyphId(1), 10).is_none());
+
+        // An oversized strike count stops at the end of the offsets
+        let table = sbix_table(u32::MAX, &[20]);
+        assert_eq!(sbix_image(&table, 2, GlyphId(1), 10).unwrap().ppem, 20);
+    }
+
+    #[test]
+    fn test_unpremultiply_bgra() {
+        assert_eq!(
+            unpremultiply_bgra(&[0, 0, 255, 255, 128, 0, 0, 128, 9, 9, 9, 0]),
+            [255, 0, 0, 255, 0, 0, 255, 128, 0, 0, 0, 0]
+        );
     }
 }
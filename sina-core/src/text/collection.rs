@@ -0,0 +1,123 @@
+//! Font fallback
+//!
+//! A `FontCollection` is an ordered list of fonts. Text is split into runs
+//! that each use the first font with glyphs for their characters, so that
+//! scripts and emoji missing from the primary font still render.
+
+use super::Font;
+use std::ops::Range;
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+/// A stretch of text drawn with one font of a collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontRun {
+    /// Byte range of the run's text
+    pub range: Range<usize>,
+    /// Index of the run's font in the collection
+    pub font_index: usize,
+}
+
+/// A primary font followed by fallback fonts, in order of preference
+#[derive(Clone)]
+pub struct FontCollection {
+    fonts: Vec<Font>,
+}
+
+impl FontCollection {
+    /// Create a collection with a primary font and no fallbacks
+    pub fn new(primary: Font) -> Self {
+        Self {
+            fonts: vec![primary],
+        }
+    }
+
+    /// Add a fallback font after the existing ones
+    pub fn with_fallback(mut self, font: Font) -> Self {
+        self.add_fallback(font);
+        self
+    }
+
+    /// Add a fallback font after the existing ones
+    pub fn add_fallback(&mut self, font: Font) {
+        self.fonts.push(font);
+    }
+
+    /// Get the primary font
+    pub fn primary(&self) -> &Font {
+        &self.fonts[0]
+    }
+
+    /// Get every font, primary first
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// Get the index of the first font with a glyph for a character, if any
+    pub fn font_index_for(&self, character: char) -> Option<usize> {
+        self.fonts.iter().position(|font| font.has_glyph(character))
+    }
+
+    /// Split text into runs, each drawn with a single font
+    ///
+    /// Every character goes to the first font that has it. Combining marks,
+    /// joiners and emoji modifiers stay in the run of the character they
+    /// attach to, and characters no font has stay in the current run (or use
+    /// the primary font), where they render as that font's missing glyph.
+    pub fn itemize(&self, text: &str) -> Vec<FontRun> {
+        let mut runs: Vec<FontRun> = Vec::new();
+        for (offset, character) in text.char_indices() {
+            let end = offset + character.len_utf8();
+            let font_index = match (runs.last(), self.font_index_for(character)) {
+                (Some(run), _) if continues_cluster(character) => run.font_index,
+                (_, Some(index)) => index,
+                (Some(run), None) => run.font_index,
+                (None, None) => 0,
+            };
+            match runs.last_mut() {
+                Some(run) if run.font_index == font_index => run.range.end = end,
+                _ => runs.push(FontRun {
+                    range: offset..end,
+                    font_index,
+                }),
+            }
+        }
+        runs
+    }
+}
+
+impl From<Font> for FontCollection {
+    fn from(font: Font) -> Self {
+        Self::new(font)
+    }
+}
+
+/// Check whether a character modifies the one before it rather than
+/// starting a new cluster
+fn continues_cluster(character: char) -> bool {
+    character.general_category_group() == GeneralCategoryGroup::Mark
+        || matches!(
+            character,
+            // Zero width non-joiner and joiner
+            '\u{200C}' | '\u{200D}'
+            // Emoji skin tone modifiers
+            | '\u{1F3FB}'..='\u{1F3FF}'
+            // Tags, as used in subdivision flags
+            | '\u{E0020}'..='\u{E007F}'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_continues_cluster() {
+        // Combining acute accent, Bengali vowel sign and virama, and VS16
+        for mark in ['\u{301}', '\u{9BE}', '\u{9CD}', '\u{FE0F}', '\u{200D}'] {
+            assert!(continues_cluster(mark), "{:?}", mark);
+        }
+        for base in ['a', ' ', 'ক', '😀'] {
+            assert!(!continues_cluster(base), "{:?}", base);
+        }
+    }
+}
@@ -1,11 +1,25 @@
 //! Color emoji rendering with COLR/CPAL support
 //!
-//! Implements layered color emoji rendering using OpenType COLR and CPAL tables.
-//! Note: Full COLR/CPAL support requires ttf-parser with these features enabled.
+//! Renders color glyphs from the OpenType COLR table with colors from the
+//! CPAL palettes. Version 0 glyphs are stacks of single-color layers, as in
+//! Twemoji and Segoe UI Emoji; version 1 glyphs are paint graphs with
+//! gradients, transforms and compositing. Both are drawn into RGBA bitmaps.
 
-use super::Font;
+use super::{Font, GlyphFormat, RasterizedGlyph};
 use crate::Color;
+use raqote::{
+    BlendMode, DrawOptions, DrawTarget, Gradient, GradientStop, PathBuilder, SolidSource, Source,
+    Spread, Transform,
+};
 use std::collections::HashMap;
+use ttf_parser::colr::{self, CompositeMode, GradientExtend};
+use ttf_parser::{GlyphId, NormalizedCoordinate, RectF, RgbaColor, Tag};
+
+/// Palette entry that stands for the text color instead of a palette color
+const FOREGROUND_ENTRY: u16 = 0xFFFF;
+
+/// Largest color glyph bitmap, in pixels per side
+pub(super) const MAX_BITMAP_SIZE: f32 = 4096.0;
 
 /// A single color layer in a COLR glyph
 #[derive(Debug, Clone)]
@@ -16,14 +30,17 @@ pub struct ColorLayer {
     /// Color for this layer
     pub color: Color,
 
-    /// Palette index (for dynamic palette switching)
+    /// Palette entry the color comes from, or 0xFFFF for the foreground color
     pub palette_index: u16,
 }
 
 /// Color emoji renderer handling COLR/CPAL tables
 pub struct ColorEmojiRenderer {
-    /// Cache of parsed color layers for glyphs
-    layer_cache: HashMap<u16, Vec<ColorLayer>>,
+    /// Cache of parsed color layers, by glyph and palette
+    layer_cache: HashMap<(u16, u16), Vec<ColorLayer>>,
+
+    /// Color of layers drawn in the text color
+    foreground: Color,
 }
 
 impl ColorEmojiRenderer {
@@ -31,40 +48,140 @@ impl ColorEmojiRenderer {
     pub fn new() -> Self {
         Self {
             layer_cache: HashMap::new(),
+            foreground: Color::BLACK,
+        }
+    }
+
+    /// Get the color used for foreground-color layers
+    pub fn foreground(&self) -> Color {
+        self.foreground
+    }
+
+    /// Set the color used for foreground-color layers
+    pub fn set_foreground(&mut self, color: Color) {
+        if color != self.foreground {
+            self.foreground = color;
+            self.layer_cache.clear();
         }
     }
 
-    /// Check if a glyph has color layers (stub - requires extended ttf-parser features)
-    pub fn has_color_layers(&self, _font: &Font, _glyph_id: u16) -> bool {
-        // Note: Full implementation requires ttf-parser with COLR table support
-        // This is a stub for now
-        false
+    /// Check if a glyph has color layers or a COLR paint graph
+    pub fn has_color_layers(&self, font: &Font, glyph_id: u16) -> bool {
+        font.face().is_color_glyph(GlyphId(glyph_id))
     }
 
-    /// Extract color layers for a glyph using COLR/CPAL tables
+    /// Extract the layers of a COLR version 0 glyph, bottom layer first
     ///
-    /// Note: This is a stub implementation. Full COLR/CPAL support requires
-    /// additional ttf-parser features or a custom table parser.
+    /// Returns `None` for glyphs without layers, including glyphs that only
+    /// have a version 1 paint graph. The cache is keyed by glyph, so clear
+    /// it before reusing the renderer with another font.
     pub fn get_color_layers(
         &mut self,
-        _font: &Font,
-        _glyph_id: u16,
-        _palette_index: u16,
+        font: &Font,
+        glyph_id: u16,
+        palette_index: u16,
     ) -> Option<Vec<ColorLayer>> {
-        // Stub - would parse COLR/CPAL tables here
-        None
+        if let Some(layers) = self.layer_cache.get(&(glyph_id, palette_index)) {
+            return Some(layers.clone());
+        }
+
+        let face = font.face();
+        let colr = face.raw_face().table(Tag::from_bytes(b"COLR"))?;
+        let cpal = face
+            .raw_face()
+            .table(Tag::from_bytes(b"CPAL"))
+            .and_then(ttf_parser::cpal::Table::parse)?;
+        let palette = self.palette_for(font, palette_index);
+
+        let layers: Vec<ColorLayer> = layer_records(colr, glyph_id)?
+            .into_iter()
+            .map(|(glyph_id, entry)| {
+                let color = match entry {
+                    FOREGROUND_ENTRY => None,
+                    entry => cpal.get(palette, entry).map(from_rgba_color),
+                };
+                ColorLayer {
+                    glyph_id,
+                    color: color.unwrap_or(self.foreground),
+                    palette_index: entry,
+                }
+            })
+            .collect();
+
+        self.layer_cache
+            .insert((glyph_id, palette_index), layers.clone());
+        Some(layers)
     }
 
-    /// Get number of available color palettes (stub)
-    pub fn num_palettes(&self, _font: &Font) -> u16 {
-        // Stub - would check CPAL table
-        0
+    /// Get number of available color palettes
+    pub fn num_palettes(&self, font: &Font) -> u16 {
+        font.face().color_palettes().map_or(0, |count| count.get())
+    }
+
+    /// Rasterize a COLR glyph into an RGBA bitmap
+    ///
+    /// Palettes the font doesn't have fall back to the first one. Returns
+    /// `None` for glyphs without color data or with nothing to draw.
+    pub fn rasterize(
+        &self,
+        font: &Font,
+        glyph_id: u16,
+        font_size: f32,
+        palette_index: u16,
+    ) -> Option<RasterizedGlyph> {
+        let face = font.face();
+        let glyph = GlyphId(glyph_id);
+        if !face.is_color_glyph(glyph) {
+            return None;
+        }
+        let palette = self.palette_for(font, palette_index);
+        let foreground = to_rgba_color(self.foreground);
+
+        // Measure the painted area in font units first
+        let mut measure = ColrPainter::new(face, palette, Transform::identity(), None);
+        face.paint_color_glyph(glyph, palette, foreground, &mut measure)?;
+        let bounds = measure.bounds?;
+
+        // Then paint into a bitmap covering it, with y pointing down
+        let scale = font_size / face.units_per_em() as f32;
+        let left = (bounds.x_min * scale).floor();
+        let right = (bounds.x_max * scale).ceil();
+        let top = (-bounds.y_max * scale).floor();
+        let bottom = (-bounds.y_min * scale).ceil();
+        let (width, height) = (right - left, bottom - top);
+        if !(width >= 1.0 && height >= 1.0 && width.max(height) <= MAX_BITMAP_SIZE) {
+            return None;
+        }
+
+        let target = DrawTarget::new(width as i32, height as i32);
+        let base = Transform::new(scale, 0.0, 0.0, -scale, -left, -top);
+        let mut painter = ColrPainter::new(face, palette, base, Some(target));
+        face.paint_color_glyph(glyph, palette, foreground, &mut painter)?;
+        let target = painter.target?;
+
+        Some(RasterizedGlyph {
+            pixels: unpremultiply(target.get_data()),
+            format: GlyphFormat::Rgba,
+            width: width as usize,
+            height: height as usize,
+            bearing_x: left,
+            bearing_y: -bottom,
+            advance: font.glyph_advance(glyph_id, font_size),
+        })
     }
 
     /// Clear the layer cache
     pub fn clear_cache(&mut self) {
         self.layer_cache.clear();
     }
+
+    fn palette_for(&self, font: &Font, palette_index: u16) -> u16 {
+        if palette_index < self.num_palettes(font) {
+            palette_index
+        } else {
+            0
+        }
+    }
 }
 
 impl Default for ColorEmojiRenderer {
@@ -73,6 +190,485 @@ impl Default for ColorEmojiRenderer {
     }
 }
 
+/// Find the (glyph, palette entry) layer records of a COLR version 0 glyph
+fn layer_records(colr: &[u8], glyph_id: u16) -> Option<Vec<(u16, u16)>> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let bytes = colr.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let read_u32 = |offset: usize| -> Option<usize> {
+        let bytes = colr.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    let num_base_glyphs = read_u16(2)? as usize;
+    let base_glyphs = read_u32(4)?;
+    let layers = read_u32(8)?;
+    let num_layers = read_u16(12)? as usize;
+
+    // Base glyph records are sorted by glyph ID
+    let (mut low, mut high) = (0, num_base_glyphs);
+    let record = loop {
+        if low >= high {
+            return None;
+        }
+        let middle = (low + high) / 2;
+        let record = base_glyphs + middle * 6;
+        match read_u16(record)?.cmp(&glyph_id) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => break record,
+        }
+    };
+
+    let first = read_u16(record + 2)? as usize;
+    let count = read_u16(record + 4)? as usize;
+    if count == 0 || first + count > num_layers {
+        return None;
+    }
+    (first..first + count)
+        .map(|index| {
+            let layer = layers + index * 4;
+            Some((read_u16(layer)?, read_u16(layer + 2)?))
+        })
+        .collect()
+}
+
+/// Draws a COLR paint graph into a raqote target, or only measures it
+///
+/// Every path is transformed to target space as it is built, so the target
+/// itself keeps an identity transform. Without a target, the painter only
+/// accumulates the bounds of what would be painted.
+struct ColrPainter<'a> {
+    face: &'a ttf_parser::Face<'a>,
+    palette: u16,
+    coords: &'a [NormalizedCoordinate],
+
+    /// Transform from paint space to target space
+    transform: Transform,
+    saved_transforms: Vec<Transform>,
+
+    /// The last glyph outline and its bounds, in target space
+    outline: Option<(raqote::Path, RectF)>,
+
+    /// Bounds of each active clip, intersected with the clips below it
+    clips: Vec<Option<RectF>>,
+
+    /// Bounds of everything painted, in target space
+    bounds: Option<RectF>,
+
+    target: Option<DrawTarget>,
+}
+
+impl<'a> ColrPainter<'a> {
+    fn new(
+        face: &'a ttf_parser::Face<'a>,
+        palette: u16,
+        transform: Transform,
+        target: Option<DrawTarget>,
+    ) -> Self {
+        Self {
+            face,
+            palette,
+            coords: face.variation_coordinates(),
+            transform,
+            saved_transforms: Vec::new(),
+            outline: None,
+            clips: Vec::new(),
+            bounds: None,
+            target,
+        }
+    }
+
+    fn push_clip_path(&mut self, path: &raqote::Path, area: Option<RectF>) {
+        let area = match self.clips.last() {
+            Some(clip) => (*clip)
+                .zip(area)
+                .and_then(|(clip, area)| intersect(clip, area)),
+            None => area,
+        };
+        self.clips.push(area);
+        if let Some(target) = &mut self.target {
+            target.push_clip(path);
+        }
+    }
+
+    fn push_transform_matrix(&mut self, transform: Transform) {
+        self.saved_transforms.push(self.transform);
+        self.transform = transform.then(&self.transform);
+    }
+
+    /// Build the raqote source for a paint, in target space
+    fn source(&self, paint: colr::Paint<'a>) -> Option<Source<'static>> {
+        let to_paint = self.transform.inverse()?;
+        match paint {
+            colr::Paint::Solid(color) => Some(Source::Solid(solid_source(color))),
+            colr::Paint::LinearGradient(gradient) => {
+                let stops = self.gradient(gradient.stops(self.palette, self.coords))?;
+                let (start, end) = linear_gradient_points(
+                    (gradient.x0, gradient.y0),
+                    (gradient.x1, gradient.y1),
+                    (gradient.x2, gradient.y2),
+                );
+                match Source::new_linear_gradient(stops, start, end, spread(gradient.extend)) {
+                    Source::LinearGradient(stops, spread, transform) => Some(
+                        Source::LinearGradient(stops, spread, to_paint.then(&transform)),
+                    ),
+                    source => Some(source),
+                }
+            }
+            colr::Paint::RadialGradient(gradient) => Some(Source::TwoCircleRadialGradient(
+                self.gradient(gradient.stops(self.palette, self.coords))?,
+                spread(gradient.extend),
+                raqote::Point::new(gradient.x0, gradient.y0),
+                gradient.r0,
+                raqote::Point::new(gradient.x1, gradient.y1),
+                gradient.r1,
+                to_paint,
+            )),
+            colr::Paint::SweepGradient(gradient) => {
+                // Angles are in half turns, counter-clockwise. Turn the start
+                // angle to zero, as raqote mishandles other start angles.
+                let start = gradient.start_angle * 180.0;
+                let mut sweep = gradient.end_angle * 180.0 - start;
+                if sweep <= 0.0 {
+                    sweep += 360.0;
+                }
+                let to_gradient = Transform::translation(-gradient.center_x, -gradient.center_y)
+                    .then(&rotation(-start.to_radians()));
+                Some(Source::SweepGradient(
+                    self.gradient(gradient.stops(self.palette, self.coords))?,
+                    spread(gradient.extend),
+                    0.0,
+                    sweep,
+                    to_paint.then(&to_gradient),
+                ))
+            }
+        }
+    }
+
+    fn gradient(&self, stops: impl Iterator<Item = colr::ColorStop>) -> Option<Gradient> {
+        let mut stops: Vec<GradientStop> = stops
+            .map(|stop| GradientStop {
+                position: stop.stop_offset.clamp(0.0, 1.0),
+                color: raqote::Color::new(
+                    stop.color.alpha,
+                    stop.color.red,
+                    stop.color.green,
+                    stop.color.blue,
+                ),
+            })
+            .collect();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        // A single stop is a solid color
+        if stops.len() == 1 {
+            stops.push(GradientStop {
+                position: 1.0,
+                ..stops[0]
+            });
+            stops[0].position = 0.0;
+        }
+        (!stops.is_empty()).then_some(Gradient { stops })
+    }
+}
+
+impl<'a> colr::Painter<'a> for ColrPainter<'a> {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        let mut builder = OutlineToRaqote {
+            builder: PathBuilder::new(),
+            transform: self.transform,
+            bounds: None,
+        };
+        self.outline = self
+            .face
+            .outline_glyph(glyph_id, &mut builder)
+            .and(builder.bounds)
+            .map(|bounds| (builder.builder.finish(), bounds));
+    }
+
+    fn paint(&mut self, paint: colr::Paint<'a>) {
+        // Inside a clip the paint covers everything, otherwise (as in COLR
+        // version 0) it fills the last outline
+        let area = match self.clips.last() {
+            Some(clip) => *clip,
+            None => self.outline.as_ref().map(|(_, bounds)| *bounds),
+        };
+        let Some(area) = area else {
+            return;
+        };
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => union(bounds, area),
+            None => area,
+        });
+
+        if self.target.is_none() {
+            return;
+        }
+        let Some(source) = self.source(paint) else {
+            return;
+        };
+        let options = DrawOptions::new();
+        let Some(target) = &mut self.target else {
+            return;
+        };
+        match &self.outline {
+            Some((path, _)) if self.clips.is_empty() => target.fill(path, &source, &options),
+            _ => {
+                let (width, height) = (target.width() as f32, target.height() as f32);
+                target.fill_rect(0.0, 0.0, width, height, &source, &options);
+            }
+        }
+    }
+
+    fn push_clip(&mut self) {
+        match self.outline.take() {
+            Some((path, bounds)) => {
+                self.push_clip_path(&path, Some(bounds));
+                self.outline = Some((path, bounds));
+            }
+            None => self.push_clip_path(&PathBuilder::new().finish(), None),
+        }
+    }
+
+    fn push_clip_box(&mut self, clip_box: colr::ClipBox) {
+        let corners = [
+            (clip_box.x_min, clip_box.y_min),
+            (clip_box.x_max, clip_box.y_min),
+            (clip_box.x_max, clip_box.y_max),
+            (clip_box.x_min, clip_box.y_max),
+        ];
+        let mut builder = OutlineToRaqote {
+            builder: PathBuilder::new(),
+            transform: self.transform,
+            bounds: None,
+        };
+        for (i, (x, y)) in corners.into_iter().enumerate() {
+            if i == 0 {
+                ttf_parser::OutlineBuilder::move_to(&mut builder, x, y);
+            } else {
+                ttf_parser::OutlineBuilder::line_to(&mut builder, x, y);
+            }
+        }
+        ttf_parser::OutlineBuilder::close(&mut builder);
+        let bounds = builder.bounds;
+        self.push_clip_path(&builder.builder.finish(), bounds);
+    }
+
+    fn pop_clip(&mut self) {
+        if self.clips.pop().is_some() {
+            if let Some(target) = &mut self.target {
+                target.pop_clip();
+            }
+        }
+    }
+
+    fn push_layer(&mut self, mode: CompositeMode) {
+        if let Some(target) = &mut self.target {
+            target.push_layer_with_blend(1.0, blend_mode(mode));
+        }
+    }
+
+    fn pop_layer(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.pop_layer();
+        }
+    }
+
+    fn push_translate(&mut self, tx: f32, ty: f32) {
+        self.push_transform_matrix(Transform::translation(tx, ty));
+    }
+
+    fn push_scale(&mut self, sx: f32, sy: f32) {
+        self.push_transform_matrix(Transform::scale(sx, sy));
+    }
+
+    fn push_rotate(&mut self, angle: f32) {
+        // Angles are in half turns, counter-clockwise
+        self.push_transform_matrix(rotation(angle * std::f32::consts::PI));
+    }
+
+    fn push_skew(&mut self, skew_x: f32, skew_y: f32) {
+        let (x, y) = (skew_x * std::f32::consts::PI, skew_y * std::f32::consts::PI);
+        self.push_transform_matrix(Transform::new(1.0, y.tan(), -x.tan(), 1.0, 0.0, 0.0));
+    }
+
+    fn push_transform(&mut self, t: ttf_parser::Transform) {
+        self.push_transform_matrix(Transform::new(t.a, t.b, t.c, t.d, t.e, t.f));
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some(transform) = self.saved_transforms.pop() {
+            self.transform = transform;
+        }
+    }
+}
+
+/// Adapter from ttf-parser outline callbacks to a transformed raqote path
+struct OutlineToRaqote {
+    builder: PathBuilder,
+    transform: Transform,
+    bounds: Option<RectF>,
+}
+
+impl OutlineToRaqote {
+    fn point(&mut self, x: f32, y: f32) -> raqote::Point {
+        let point = self.transform.transform_point(raqote::Point::new(x, y));
+        let extent = RectF {
+            x_min: point.x,
+            y_min: point.y,
+            x_max: point.x,
+            y_max: point.y,
+        };
+        self.bounds = Some(self.bounds.map_or(extent, |bounds| union(bounds, extent)));
+        point
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineToRaqote {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder.move_to(to.x, to.y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder.line_to(to.x, to.y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (ctrl, to) = (self.point(x1, y1), self.point(x, y));
+        self.builder.quad_to(ctrl.x, ctrl.y, to.x, to.y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (ctrl1, ctrl2, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.builder
+            .cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
+    }
+
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
+
+/// Find the two-point form of a COLR linear gradient
+///
+/// COLR gradients run from p0 towards p1, with color lines perpendicular
+/// to p0→p2 rather than to p0→p1. Projecting p1 onto the normal of p0→p2
+/// gives the end point of the equivalent two-point gradient.
+fn linear_gradient_points(
+    p0: (f32, f32),
+    p1: (f32, f32),
+    p2: (f32, f32),
+) -> (raqote::Point, raqote::Point) {
+    let start = raqote::Point::new(p0.0, p0.1);
+    let normal = (-(p2.1 - p0.1), p2.0 - p0.0);
+    let length_squared = normal.0 * normal.0 + normal.1 * normal.1;
+    if length_squared == 0.0 {
+        return (start, raqote::Point::new(p1.0, p1.1));
+    }
+    let along = ((p1.0 - p0.0) * normal.0 + (p1.1 - p0.1) * normal.1) / length_squared;
+    let end = raqote::Point::new(p0.0 + normal.0 * along, p0.1 + normal.1 * along);
+    (start, end)
+}
+
+/// Counter-clockwise rotation in y-up space
+fn rotation(radians: f32) -> Transform {
+    let (sin, cos) = radians.sin_cos();
+    Transform::new(cos, sin, -sin, cos, 0.0, 0.0)
+}
+
+fn union(a: RectF, b: RectF) -> RectF {
+    RectF {
+        x_min: a.x_min.min(b.x_min),
+        y_min: a.y_min.min(b.y_min),
+        x_max: a.x_max.max(b.x_max),
+        y_max: a.y_max.max(b.y_max),
+    }
+}
+
+fn intersect(a: RectF, b: RectF) -> Option<RectF> {
+    let rect = RectF {
+        x_min: a.x_min.max(b.x_min),
+        y_min: a.y_min.max(b.y_min),
+        x_max: a.x_max.min(b.x_max),
+        y_max: a.y_max.min(b.y_max),
+    };
+    (rect.x_min < rect.x_max && rect.y_min < rect.y_max).then_some(rect)
+}
+
+fn spread(extend: GradientExtend) -> Spread {
+    match extend {
+        GradientExtend::Pad => Spread::Pad,
+        GradientExtend::Repeat => Spread::Repeat,
+        GradientExtend::Reflect => Spread::Reflect,
+    }
+}
+
+fn blend_mode(mode: CompositeMode) -> BlendMode {
+    match mode {
+        CompositeMode::Clear => BlendMode::Clear,
+        CompositeMode::Source => BlendMode::Src,
+        CompositeMode::Destination => BlendMode::Dst,
+        CompositeMode::SourceOver => BlendMode::SrcOver,
+        CompositeMode::DestinationOver => BlendMode::DstOver,
+        CompositeMode::SourceIn => BlendMode::SrcIn,
+        CompositeMode::DestinationIn => BlendMode::DstIn,
+        CompositeMode::SourceOut => BlendMode::SrcOut,
+        CompositeMode::DestinationOut => BlendMode::DstOut,
+        CompositeMode::SourceAtop => BlendMode::SrcAtop,
+        CompositeMode::DestinationAtop => BlendMode::DstAtop,
+        CompositeMode::Xor => BlendMode::Xor,
+        CompositeMode::Plus => BlendMode::Add,
+        CompositeMode::Screen => BlendMode::Screen,
+        CompositeMode::Overlay => BlendMode::Overlay,
+        CompositeMode::Darken => BlendMode::Darken,
+        CompositeMode::Lighten => BlendMode::Lighten,
+        CompositeMode::ColorDodge => BlendMode::ColorDodge,
+        CompositeMode::ColorBurn => BlendMode::ColorBurn,
+        CompositeMode::HardLight => BlendMode::HardLight,
+        CompositeMode::SoftLight => BlendMode::SoftLight,
+        CompositeMode::Difference => BlendMode::Difference,
+        CompositeMode::Exclusion => BlendMode::Exclusion,
+        CompositeMode::Multiply => BlendMode::Multiply,
+        CompositeMode::Hue => BlendMode::Hue,
+        CompositeMode::Saturation => BlendMode::Saturation,
+        CompositeMode::Color => BlendMode::Color,
+        CompositeMode::Luminosity => BlendMode::Luminosity,
+    }
+}
+
+fn solid_source(color: RgbaColor) -> SolidSource {
+    SolidSource::from_unpremultiplied_argb(color.alpha, color.red, color.green, color.blue)
+}
+
+fn to_rgba_color(color: Color) -> RgbaColor {
+    RgbaColor::new(color.r, color.g, color.b, color.a)
+}
+
+fn from_rgba_color(color: RgbaColor) -> Color {
+    Color::rgba(color.red, color.green, color.blue, color.alpha)
+}
+
+/// Convert premultiplied ARGB pixels to straight RGBA bytes
+fn unpremultiply(data: &[u32]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(data.len() * 4);
+    for &pixel in data {
+        let alpha = pixel >> 24;
+        if alpha == 0 {
+            rgba.extend_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+        for shift in [16, 8, 0] {
+            let channel = (pixel >> shift) & 0xFF;
+            rgba.push(((channel * 255 + alpha / 2) / alpha).min(255) as u8);
+        }
+        rgba.push(alpha as u8);
+    }
+    rgba
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +678,37 @@ mod tests {
         let renderer = ColorEmojiRenderer::new();
         assert_eq!(renderer.layer_cache.len(), 0);
     }
+
+    #[test]
+    fn test_layer_records() {
+        // One base glyph (5) with two layers: glyph 1 in entry 0 and glyph 2
+        // in the foreground color
+        let mut colr = vec![0, 0, 0, 1, 0, 0, 0, 14, 0, 0, 0, 20, 0, 2];
+        colr.extend_from_slice(&[0, 5, 0, 0, 0, 2]);
+        colr.extend_from_slice(&[0, 1, 0, 0, 0, 2, 0xFF, 0xFF]);
+
+        assert_eq!(layer_records(&colr, 5), Some(vec![(1, 0), (2, 0xFFFF)]));
+        assert_eq!(layer_records(&colr, 4), None);
+        assert_eq!(layer_records(&colr[..16], 5), None);
+    }
+
+    #[test]
+    fn test_linear_gradient_points() {
+        // With p2 straight above p0, p1 already lies on the normal
+        let (start, end) = linear_gradient_points((0.0, 0.0), (10.0, 5.0), (0.0, 10.0));
+        assert_eq!((start.x, start.y), (0.0, 0.0));
+        assert!((end.x - 10.0).abs() < 1e-4 && end.y.abs() < 1e-4);
+
+        // A skewed p2 moves the end point onto the normal of p0→p2
+        let (_, end) = linear_gradient_points((0.0, 0.0), (10.0, 0.0), (10.0, 10.0));
+        assert!((end.x - 5.0).abs() < 1e-4 && (end.y + 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_unpremultiply() {
+        assert_eq!(
+            unpremultiply(&[0xFF_FF_00_00, 0x80_40_00_80, 0]),
+            [255, 0, 0, 255, 128, 0, 255, 128, 0, 0, 0, 0]
+        );
+    }
 }
@@ -2,6 +2,8 @@
 //!
 //! Supports TrueType (.ttf) and OpenType (.otf) font formats.
 
+use super::{VariableFontManager, Variation};
+use crate::{Path, PathBuilder, Point};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -32,8 +34,14 @@ pub struct Font {
     /// Parsed font face
     face: ttf_parser::Face<'static>,
 
-    /// Fontdue font for rasterization
-    fontdue_font: fontdue::Font,
+    /// Fontdue font for rasterization (shared between clones)
+    fontdue_font: Arc<fontdue::Font>,
+
+    /// CPAL palette used for COLR color glyphs
+    color_palette: u16,
+
+    /// Variation axis values applied to the face
+    variations: Vec<Variation>,
 }
 
 impl Font {
@@ -76,7 +84,9 @@ impl Font {
             data,
             index,
             face,
-            fontdue_font,
+            fontdue_font: Arc::new(fontdue_font),
+            color_palette: 0,
+            variations: Vec::new(),
         })
     }
 
@@ -143,6 +153,11 @@ impl Font {
         self.face.glyph_index(character).map(|id| id.0)
     }
 
+    /// Check whether the font has a glyph for a character
+    pub fn has_glyph(&self, character: char) -> bool {
+        self.glyph_index(character).is_some_and(|id| id != 0)
+    }
+
     /// Get horizontal advance for a glyph at given font size
     pub fn glyph_advance(&self, glyph_id: u16, font_size: f32) -> f32 {
         let glyph_id = ttf_parser::GlyphId(glyph_id);
@@ -154,6 +169,114 @@ impl Font {
         }
     }
 
+    /// Get the outline of a glyph as a path, scaled to the font size
+    ///
+    /// The path is in pixel units relative to the glyph origin on the
+    /// baseline, with y pointing down. Returns `None` for glyphs without
+    /// an outline (e.g. spaces or bitmap-only glyphs).
+    pub fn glyph_outline(&self, glyph_id: u16, font_size: f32) -> Option<Path> {
+        let mut builder = OutlineToPath {
+            builder: PathBuilder::new(),
+            scale: font_size / self.units_per_em() as f32,
+        };
+        self.face
+            .outline_glyph(ttf_parser::GlyphId(glyph_id), &mut builder)?;
+        Some(builder.builder.build())
+    }
+
+    /// Get the CPAL palette used for color glyphs
+    pub fn color_palette(&self) -> u16 {
+        self.color_palette
+    }
+
+    /// Select the CPAL palette used for color glyphs
+    ///
+    /// Palette 0 is the default. Indices past the font's last palette fall
+    /// back to it.
+    pub fn set_color_palette(&mut self, palette: u16) {
+        self.color_palette = palette;
+    }
+
+    /// Set the value of a variation axis, such as `wght` or `wdth`
+    ///
+    /// Shaping, glyph outlines, rasterization and metrics all use the new
+    /// instance. Values are clamped to the axis range. Returns `false`, and
+    /// leaves the font unchanged, if the font has no such axis.
+    pub fn set_variation(&mut self, tag: &str, value: f32) -> bool {
+        let Some(axis) = self
+            .face
+            .variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag.to_bytes() == tag.as_bytes())
+        else {
+            return false;
+        };
+        let value = value.clamp(axis.min_value, axis.max_value);
+        match self.variations.iter_mut().find(|v| v.tag == tag) {
+            Some(variation) => variation.value = value,
+            None => self.variations.push(Variation::new(tag, value)),
+        }
+        self.apply_variations();
+        true
+    }
+
+    /// Set the values of several variation axes, ignoring unknown axes
+    pub fn set_variations(&mut self, variations: &[Variation]) {
+        for variation in variations {
+            self.set_variation(&variation.tag, variation.value);
+        }
+    }
+
+    /// Return the font with variation axis values set, ignoring unknown axes
+    pub fn with_variations(mut self, variations: &[Variation]) -> Self {
+        self.set_variations(variations);
+        self
+    }
+
+    /// Switch to one of the font's named instances, such as "Bold"
+    ///
+    /// Returns `false`, and leaves the font unchanged, if there is no
+    /// instance with that name.
+    pub fn set_named_instance(&mut self, name: &str) -> bool {
+        let instance = VariableFontManager::instances(self)
+            .into_iter()
+            .find(|instance| instance.name == name);
+        match instance {
+            Some(instance) => {
+                self.reset_variations();
+                self.set_variations(&instance.coordinates);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Go back to the default instance of a variable font
+    pub fn reset_variations(&mut self) {
+        self.variations.clear();
+        self.apply_variations();
+    }
+
+    /// Get the variation axis values set on the font, in the order set
+    pub fn variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    /// Apply the variation values to the face, starting from the defaults
+    ///
+    /// Axes are applied in the order they were first set, the same order
+    /// shaping applies them in.
+    fn apply_variations(&mut self) {
+        let axes: Vec<_> = self.face.variation_axes().into_iter().collect();
+        for axis in axes {
+            self.face.set_variation(axis.tag, axis.def_value);
+        }
+        for variation in &self.variations {
+            let tag = ttf_parser::Tag::from_bytes_lossy(variation.tag.as_bytes());
+            self.face.set_variation(tag, variation.value);
+        }
+    }
+
     /// Get fontdue font reference for rasterization
     pub(crate) fn fontdue_font(&self) -> &fontdue::Font {
         &self.fontdue_font
@@ -165,11 +288,55 @@ impl Font {
     }
 }
 
+/// Adapter from ttf-parser outline callbacks to a `PathBuilder`
+struct OutlineToPath {
+    builder: PathBuilder,
+    scale: f32,
+}
+
+impl OutlineToPath {
+    fn point(&self, x: f32, y: f32) -> Point {
+        Point::new(x * self.scale, -y * self.scale)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineToPath {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder = std::mem::take(&mut self.builder).move_to(to);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder = std::mem::take(&mut self.builder).line_to(to);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (ctrl, to) = (self.point(x1, y1), self.point(x, y));
+        self.builder = std::mem::take(&mut self.builder).quad_to(ctrl, to);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (ctrl1, ctrl2, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.builder = std::mem::take(&mut self.builder).cubic_to(ctrl1, ctrl2, to);
+    }
+
+    fn close(&mut self) {
+        self.builder = std::mem::take(&mut self.builder).close();
+    }
+}
+
 impl Clone for Font {
     fn clone(&self) -> Self {
-        // Clone by recreating from shared data
-        Self::from_bytes_with_index((*self.data).clone(), self.index)
-            .expect("Font clone should not fail")
+        // The face borrows from the shared data, which the clone keeps alive
+        Self {
+            data: Arc::clone(&self.data),
+            index: self.index,
+            face: self.face.clone(),
+            fontdue_font: Arc::clone(&self.fontdue_font),
+            color_palette: self.color_palette,
+            variations: self.variations.clone(),
+        }
     }
 }
 
@@ -179,8 +346,7 @@ mod tests {
 
     #[test]
     fn test_font_basics() {
-        // This test would need an actual font file
-        // For now, just verify the API compiles
-        assert!(true);
+        // A real font file is needed for anything more; invalid data must be rejected
+        assert!(Font::from_bytes(vec![0u8; 16]).is_err());
     }
 }
@@ -1,8 +1,9 @@
 //! Glyph rasterization and caching
 
 use crate::Color;
-use image::GenericImageView;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GlyphFormat {
     /// Grayscale alpha mask (standard text)
@@ -59,6 +60,25 @@ impl RasterizedGlyph {
 struct GlyphKey {
     glyph_index: u16,
     font_size_scaled: u32, // Font size * 100 to handle fractional sizes
+    palette: u16,
+    foreground: [u8; 4], // Color of foreground layers in color glyphs
+    variations: u64,     // Hash of the variable font instance
+}
+
+impl GlyphKey {
+    fn new(font: &super::Font, glyph_index: u16, font_size: f32, foreground: Color) -> Self {
+        let mut hasher = DefaultHasher::new();
+        for coordinate in font.face().variation_coordinates() {
+            coordinate.get().hash(&mut hasher);
+        }
+        Self {
+            glyph_index,
+            font_size_scaled: (font_size * 100.0) as u32,
+            palette: font.color_palette(),
+            foreground: [foreground.r, foreground.g, foreground.b, foreground.a],
+            variations: hasher.finish(),
+        }
+    }
 }
 
 /// LRU cache for rasterized glyphs
@@ -84,27 +104,27 @@ impl GlyphCache {
         font_size: f32,
     ) -> Option<RasterizedGlyph> {
         let glyph_index = font.glyph_index(character)?;
-        let key = GlyphKey {
-            glyph_index,
-            font_size_scaled: (font_size * 100.0) as u32,
-        };
+        let key = GlyphKey::new(font, glyph_index, font_size, Color::BLACK);
 
         // Check cache first
         if let Some(glyph) = self.cache.get(&key) {
             return Some(glyph.clone());
         }
 
-        // Rasterize using fontdue
-        let (metrics, pixels) = font.fontdue_font().rasterize(character, font_size);
-
-        let glyph = RasterizedGlyph {
-            pixels,
-            width: metrics.width,
-            height: metrics.height,
-            bearing_x: metrics.xmin as f32,
-            bearing_y: metrics.ymin as f32,
-            advance: metrics.advance_width,
-            format: GlyphFormat::Alpha,
+        // Rasterize using fontdue, which only knows the default instance
+        let glyph = if font.variations().is_empty() {
+            let (metrics, pixels) = font.fontdue_font().rasterize(character, font_size);
+            RasterizedGlyph {
+                pixels,
+                width: metrics.width,
+                height: metrics.height,
+                bearing_x: metrics.xmin as f32,
+                bearing_y: metrics.ymin as f32,
+                advance: metrics.advance_width,
+                format: GlyphFormat::Alpha,
+            }
+        } else {
+            rasterize_outline(font, glyph_index, font_size)
         };
 
         // Evict oldest entry if cache is full
@@ -125,119 +145,100 @@ impl GlyphCache {
         glyph_index: u16,
         font_size: f32,
     ) -> Option<RasterizedGlyph> {
-        let key = GlyphKey {
-            glyph_index,
-            font_size_scaled: (font_size * 100.0) as u32,
-        };
+        self.get_or_rasterize_with_color(font, glyph_index, font_size, Color::BLACK)
+    }
+
+    /// Get or rasterize a glyph by its index, drawing the foreground-color
+    /// layers of COLR color glyphs in the given color
+    pub fn get_or_rasterize_with_color(
+        &mut self,
+        font: &super::Font,
+        glyph_index: u16,
+        font_size: f32,
+        foreground: Color,
+    ) -> Option<RasterizedGlyph> {
+        let key = GlyphKey::new(font, glyph_index, font_size, foreground);
 
         // Check cache first
         if let Some(glyph) = self.cache.get(&key) {
             return Some(glyph.clone());
         }
 
-        let face = font.face();
-        let glyph_id = ttf_parser::GlyphId(glyph_index);
-
-        // Try to load a color bitmap (CBDT/CBLC/SBIX)
-        // 1. Try to get raster image at requested size directly
-        let target_ppem = font_size as u16;
-        let mut raster_image_opt = face.glyph_raster_image(glyph_id, target_ppem);
-
-        // If exact size not found, try common emoji bitmap sizes
-        if raster_image_opt.is_none() {
-            for &size in &[109, 128, 96, 64, 32] {
-                if let Some(img) = face.glyph_raster_image(glyph_id, size) {
-                    raster_image_opt = Some(img);
-                    break;
+        // 1. Try a color bitmap (CBDT/CBLC/SBIX) from the best strike
+        let ppem = font_size.round().max(1.0) as u16;
+        let bitmap = super::BitmapFontRenderer::get_bitmap(font, glyph_index, ppem)
+            // Strikes without a size can't be scaled, and huge bitmaps would
+            // exhaust memory
+            .filter(|bitmap| {
+                let scale = font_size / bitmap.ppem as f32;
+                let size = bitmap.width.max(bitmap.height) as f32 * scale;
+                bitmap.ppem > 0 && size.round() <= super::emoji::MAX_BITMAP_SIZE
+            });
+        if let Some(bitmap) = bitmap {
+            // 2. Scale the strike to the requested font size
+            let scale = font_size / bitmap.ppem as f32;
+            let target_w = ((bitmap.width as f32 * scale).round() as u32).max(1);
+            let target_h = ((bitmap.height as f32 * scale).round() as u32).max(1);
+            let image = image::RgbaImage::from_raw(bitmap.width, bitmap.height, bitmap.pixels)?;
+            let resized = image::imageops::resize(
+                &image,
+                target_w,
+                target_h,
+                image::imageops::FilterType::Lanczos3,
+            );
+
+            // Bearings come from the bitmap metrics, the advance from the outline metrics
+            let glyph = RasterizedGlyph {
+                pixels: resized.into_vec(),
+                width: target_w as usize,
+                height: target_h as usize,
+                bearing_x: bitmap.bearing_x as f32 * scale,
+                bearing_y: bitmap.bearing_y as f32 * scale,
+                advance: font.glyph_advance(glyph_index, font_size),
+                format: GlyphFormat::Rgba,
+            };
+
+            // Cache and return
+            if self.cache.len() >= self.max_size {
+                if let Some(k) = self.cache.keys().next().copied() {
+                    self.cache.remove(&k);
                 }
             }
+            self.cache.insert(key, glyph.clone());
+            return Some(glyph);
         }
 
-        // 2. Try to get raster image
-        if let Some(raster_image) = raster_image_opt {
-            // Use the actual size of the found bitmap for scaling
-            // ttf-parser might return a bitmap even if the requested size doesn't match perfectly
-            let found_ppem = raster_image.pixels_per_em;
-
-            // 3. Decode image
-            if let Ok(img) = image::load_from_memory(raster_image.data) {
-                // 4. Resize to requested font size
-                let (w, h) = img.dimensions();
-
-                // Target height is usually close to font_size, or em size.
-                let scale = font_size / found_ppem as f32;
-                let target_w = (w as f32 * scale).round() as u32;
-                let target_h = (h as f32 * scale).round() as u32;
-
-                let resized = img.resize(target_w, target_h, image::imageops::FilterType::Lanczos3);
-                let rgba_data = resized.to_rgba8().into_vec();
-                let (new_w, new_h) = resized.dimensions();
-
-                // Get vector metrics for advance/bearing
-                let units_per_em = face.units_per_em() as f32;
-                let advance_width = face.glyph_hor_advance(glyph_id).unwrap_or(0);
-                let advance = (advance_width as f32 / units_per_em) * font_size;
-
-                // FIX: Position the emoji on the baseline.
-                // Previous attempt used descender, which might be too low.
-                // Most bitmaps are full-height or centered, but anchoring bottom to baseline is a safe default.
-                // A small negative bearing (descent) might be appropriate but 0.0 is safer than full descender.
-                let bearing_y = 0.0; // Align bottom to baseline
-
-                // Use vector side bearing if available, else scaled bitmap x
-                let lsb = face.glyph_hor_side_bearing(glyph_id).unwrap_or(0);
-                let bearing_x = (lsb as f32 / units_per_em) * font_size;
-
-                if cfg!(debug_assertions) {
-                    // Check center pixel of the resized image to verify color
-                    // Removed debug print
+        // 3. Try COLR/CPAL (layered and gradient vector glyphs)
+        let mut renderer = super::ColorEmojiRenderer::new();
+        renderer.set_foreground(foreground);
+        if let Some(glyph) = renderer.rasterize(font, glyph_index, font_size, font.color_palette())
+        {
+            if self.cache.len() >= self.max_size {
+                if let Some(k) = self.cache.keys().next().copied() {
+                    self.cache.remove(&k);
                 }
-
-                let glyph = RasterizedGlyph {
-                    pixels: rgba_data,
-                    width: new_w as usize,
-                    height: new_h as usize,
-                    bearing_x,
-                    bearing_y,
-                    advance,
-                    format: GlyphFormat::Rgba,
-                };
-
-                // Cache and return
-                if self.cache.len() >= self.max_size {
-                    if let Some(k) = self.cache.keys().next().copied() {
-                        self.cache.remove(&k);
-                    }
-                }
-                self.cache.insert(key, glyph.clone());
-                return Some(glyph);
             }
+            self.cache.insert(key, glyph.clone());
+            return Some(glyph);
         }
 
-        // 3. Try COLR/CPAL (Layered Vectors)
-        // FIXME: ttf-parser 0.20 API mismatch for COLR/CPAL tables.
-        // Fields `cpal` not found on tables(), and `RgbaColor` fields mismatch.
-        // Disabling COLR support for now. CBDT bitmaps (Noto Color Emoji) are supported.
-        /*
-        if let (Some(colr), Some(cpal)) = (face.tables().colr, face.tables().cpal) {
-             let mut layers = colr.get(glyph_id);
-             // ... implementation commented out ...
-        }
-        */
-
-        // Rasterize using fontdue by index (fallback for non-bitmap glyphs)
-        let (metrics, pixels) = font
-            .fontdue_font()
-            .rasterize_indexed(glyph_index, font_size);
-
-        let glyph = RasterizedGlyph {
-            pixels,
-            width: metrics.width,
-            height: metrics.height,
-            bearing_x: metrics.xmin as f32,
-            bearing_y: metrics.ymin as f32,
-            advance: metrics.advance_width,
-            format: GlyphFormat::Alpha,
+        // Rasterize using fontdue by index (fallback for non-bitmap glyphs),
+        // or from the varied outline for variable font instances
+        let glyph = if font.variations().is_empty() {
+            let (metrics, pixels) = font
+                .fontdue_font()
+                .rasterize_indexed(glyph_index, font_size);
+            RasterizedGlyph {
+                pixels,
+                width: metrics.width,
+                height: metrics.height,
+                bearing_x: metrics.xmin as f32,
+                bearing_y: metrics.ymin as f32,
+                advance: metrics.advance_width,
+                format: GlyphFormat::Alpha,
+            }
+        } else {
+            rasterize_outline(font, glyph_index, font_size)
         };
 
         // Evict oldest entry if cache is full
@@ -267,6 +268,53 @@ impl GlyphCache {
     }
 }
 
+/// Rasterize a glyph outline into an alpha mask
+///
+/// fontdue ignores font variations, so variable font instances are filled
+/// from the outline ttf-parser varies instead.
+fn rasterize_outline(font: &super::Font, glyph_index: u16, font_size: f32) -> RasterizedGlyph {
+    let mut glyph = RasterizedGlyph {
+        pixels: Vec::new(),
+        width: 0,
+        height: 0,
+        bearing_x: 0.0,
+        bearing_y: 0.0,
+        advance: font.glyph_advance(glyph_index, font_size),
+        format: GlyphFormat::Alpha,
+    };
+    let Some(outline) = font.glyph_outline(glyph_index, font_size) else {
+        return glyph;
+    };
+    let Some(bounds) = outline.bounds() else {
+        return glyph;
+    };
+
+    // The outline is in pixels with y pointing down from the baseline
+    let left = bounds.x.floor();
+    let top = bounds.y.floor();
+    let right = (bounds.x + bounds.width).ceil();
+    let bottom = (bounds.y + bounds.height).ceil();
+    let (width, height) = ((right - left) as i32, (bottom - top) as i32);
+    if width <= 0 || height <= 0 {
+        return glyph;
+    }
+
+    let mut target = raqote::DrawTarget::new(width, height);
+    target.set_transform(&raqote::Transform::translation(-left, -top));
+    target.fill(
+        &crate::path::to_raqote_path(&outline),
+        &raqote::Source::Solid(raqote::SolidSource::from_unpremultiplied_argb(255, 0, 0, 0)),
+        &raqote::DrawOptions::new(),
+    );
+
+    glyph.pixels = target.get_data().iter().map(|&p| (p >> 24) as u8).collect();
+    glyph.width = width as usize;
+    glyph.height = height as usize;
+    glyph.bearing_x = left;
+    glyph.bearing_y = -bottom;
+    glyph
+}
+
 impl Default for GlyphCache {
     fn default() -> Self {
         Self::new(1000) // Default cache size: 1000 glyphs
@@ -279,7 +327,7 @@ mod tests {
 
     #[test]
     fn test_glyph_cache() {
-        let mut cache = GlyphCache::new(2);
+        let cache = GlyphCache::new(2);
         assert_eq!(cache.len(), 0);
         assert!(cache.is_empty());
     }
@@ -1,8 +1,11 @@
 //! Text layout and shaping
 //!
-//! Provides text shaping with rustybuzz for complex scripts.
+//! Provides text shaping with rustybuzz for complex scripts, falling back
+//! through a `FontCollection` for characters the primary font lacks, and
+//! ordering mixed-direction text with the Unicode Bidirectional Algorithm.
 
-use super::Font;
+use super::bidi::visual_runs;
+use super::{Font, FontCollection, Paragraph, ParagraphStyle, TextDirection, TextStyle};
 use crate::Point;
 
 /// Text alignment options
@@ -11,12 +14,20 @@ pub enum TextAlign {
     Left,
     Center,
     Right,
+    /// Stretch wrapped lines of a paragraph to its width; single lines and
+    /// the last line of each paragraph are aligned to the start of the
+    /// paragraph's direction
+    Justify,
 }
 
 /// A positioned glyph after shaping
 #[derive(Debug, Clone)]
 pub struct ShapedGlyph {
     pub glyph_index: u16,
+    /// Index of the glyph's font in the collection it was shaped with
+    pub font_index: usize,
+    /// Byte offset in the shaped text of the first character the glyph shows
+    pub cluster: usize,
     pub character: char,
     pub x_offset: f32,
     pub y_offset: f32,
@@ -25,6 +36,8 @@ pub struct ShapedGlyph {
 }
 
 /// Result of text shaping
+///
+/// Glyphs are in visual order, left to right, whatever the text's direction.
 pub struct ShapedText {
     pub glyphs: Vec<ShapedGlyph>,
     pub width: f32,
@@ -33,73 +46,62 @@ pub struct ShapedText {
 
 /// Text layout engine
 pub struct TextLayout {
-    font: Font,
+    fonts: FontCollection,
     font_size: f32,
+    direction: TextDirection,
+    style: TextStyle,
 }
 
 impl TextLayout {
     /// Create a new text layout with font and size
     pub fn new(font: Font, font_size: f32) -> Self {
-        Self { font, font_size }
+        Self::with_fonts(FontCollection::new(font), font_size)
     }
 
-    /// Shape text using rustybuzz for complex script support
-    pub fn shape(&self, text: &str) -> ShapedText {
-        // Create rustybuzz face from font data
-        let raw_face = self.font.face().raw_face();
-        // Use the correct index from our Font struct (0 if not in a collection, or the specific index if it is)
-        let face = rustybuzz::Face::from_slice(raw_face.data, self.font.index())
-            .expect("Failed to create rustybuzz face");
-
-        // Create buffer for shaping
-        let mut buffer = rustybuzz::UnicodeBuffer::new();
-        buffer.push_str(text);
-
-        // Auto-detect direction and script (crucial for Bangla, Arabic, etc.)
-        buffer.guess_segment_properties();
-
-        // Shape the text
-        let output = rustybuzz::shape(&face, &[], buffer);
-
-        let mut glyphs = Vec::new();
-        let mut x_position = 0.0;
-        let mut max_height = 0.0;
-
-        let positions = output.glyph_positions();
-        let infos = output.glyph_infos();
-
-        for (pos, info) in positions.iter().zip(infos.iter()) {
-            let character = text.chars().nth(info.cluster as usize).unwrap_or(' ');
+    /// Create a new text layout with fallback fonts and size
+    pub fn with_fonts(fonts: FontCollection, font_size: f32) -> Self {
+        Self {
+            fonts,
+            font_size,
+            direction: TextDirection::Auto,
+            style: TextStyle::default(),
+        }
+    }
 
-            // rustybuzz returns values in font design units, scale to font size
-            let scale = self.font_size / self.font.units_per_em() as f32;
+    /// Set the base direction text is laid out in
+    pub fn set_direction(&mut self, direction: TextDirection) {
+        self.direction = direction;
+    }
 
-            let x_advance = pos.x_advance as f32 * scale;
-            let y_advance = pos.y_advance as f32 * scale;
+    /// Get the base direction text is laid out in
+    pub fn direction(&self) -> TextDirection {
+        self.direction
+    }
 
-            let shaped_glyph = ShapedGlyph {
-                glyph_index: info.glyph_id as u16,
-                character,
-                x_offset: pos.x_offset as f32 * scale,
-                y_offset: pos.y_offset as f32 * scale,
-                x_advance,
-                y_advance,
-            };
+    /// Set the OpenType features, language and script text is shaped with
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+    }
 
-            glyphs.push(shaped_glyph);
-            x_position += x_advance;
+    /// Get the OpenType features, language and script text is shaped with
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
 
-            let glyph_height = self.font.line_height(self.font_size);
-            if glyph_height > max_height {
-                max_height = glyph_height;
-            }
-        }
+    /// Shape text using rustybuzz for complex script support
+    pub fn shape(&self, text: &str) -> ShapedText {
+        shape(
+            &self.fonts,
+            self.font_size,
+            text,
+            self.direction,
+            &self.style,
+        )
+    }
 
-        ShapedText {
-            glyphs,
-            width: x_position,
-            height: max_height,
-        }
+    /// Wrap text into a paragraph set in this layout's fonts
+    pub fn paragraph(&self, text: &str, style: &ParagraphStyle) -> Paragraph<'_> {
+        Paragraph::new(text, &self.fonts, self.font_size, style)
     }
 
     /// Layout text at a position with alignment
@@ -112,7 +114,7 @@ impl TextLayout {
         let shaped = self.shape(text);
 
         let x_offset = match align {
-            TextAlign::Left => 0.0,
+            TextAlign::Left | TextAlign::Justify => 0.0,
             TextAlign::Center => -shaped.width / 2.0,
             TextAlign::Right => -shaped.width,
         };
@@ -137,9 +139,14 @@ impl TextLayout {
         (shaped.width, shaped.height)
     }
 
-    /// Get the font
+    /// Get the primary font
     pub fn font(&self) -> &Font {
-        &self.font
+        self.fonts.primary()
+    }
+
+    /// Get the primary and fallback fonts
+    pub fn fonts(&self) -> &FontCollection {
+        &self.fonts
     }
 
     /// Get the font size
@@ -148,6 +155,124 @@ impl TextLayout {
     }
 }
 
+/// Shape text using rustybuzz, one run per direction and font
+pub(crate) fn shape(
+    fonts: &FontCollection,
+    font_size: f32,
+    text: &str,
+    direction: TextDirection,
+    style: &TextStyle,
+) -> ShapedText {
+    let mut glyphs = Vec::new();
+    let mut width = 0.0;
+    let mut height: f32 = 0.0;
+
+    for bidi_run in visual_runs(text, direction) {
+        let start = bidi_run.range.start;
+        let mut font_runs = fonts.itemize(&text[bidi_run.range]);
+        // Font runs of right-to-left text are laid out right to left too
+        if bidi_run.rtl {
+            font_runs.reverse();
+        }
+
+        for run in font_runs {
+            let font = &fonts.fonts()[run.font_index];
+            let offset = start + run.range.start;
+            let run_text = &text[offset..start + run.range.end];
+            for glyph in shape_run(font, font_size, run_text, offset, bidi_run.rtl, style) {
+                width += glyph.x_advance;
+                glyphs.push(ShapedGlyph {
+                    font_index: run.font_index,
+                    cluster: offset + glyph.cluster,
+                    ..glyph
+                });
+            }
+            height = height.max(font.line_height(font_size));
+        }
+    }
+
+    ShapedText {
+        glyphs,
+        width,
+        height,
+    }
+}
+
+/// Shape text of a single direction in a single font
+///
+/// `offset` is the byte offset of the run in the text the style's feature
+/// ranges refer to.
+fn shape_run(
+    font: &Font,
+    font_size: f32,
+    text: &str,
+    offset: usize,
+    rtl: bool,
+    style: &TextStyle,
+) -> Vec<ShapedGlyph> {
+    // Create rustybuzz face from font data
+    let raw_face = font.face().raw_face();
+    // Use the correct index from our Font struct (0 if not in a collection, or the specific index if it is)
+    let mut face = rustybuzz::Face::from_slice(raw_face.data, font.index())
+        .expect("Failed to create rustybuzz face");
+    let variations: Vec<_> = font
+        .variations()
+        .iter()
+        .map(|variation| rustybuzz::Variation {
+            tag: rustybuzz::Tag::from_bytes_lossy(variation.tag.as_bytes()),
+            value: variation.value,
+        })
+        .collect();
+    face.set_variations(&variations);
+
+    // Create buffer for shaping
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+
+    // Auto-detect script and language (crucial for Bangla, Arabic, etc.),
+    // keeping the direction the bidi algorithm resolved and any overrides
+    buffer.guess_segment_properties();
+    buffer.set_direction(if rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    if let Some(script) = style.rustybuzz_script() {
+        buffer.set_script(script);
+    }
+    if let Some(language) = style.rustybuzz_language() {
+        buffer.set_language(language);
+    }
+
+    // Shape the text
+    let features: Vec<_> = style
+        .features
+        .iter()
+        .filter_map(|feature| feature.to_rustybuzz(offset, text.len()))
+        .collect();
+    let output = rustybuzz::shape(&face, &features, buffer);
+
+    // rustybuzz returns values in font design units, scale to font size
+    let scale = font_size / font.units_per_em() as f32;
+
+    output
+        .glyph_positions()
+        .iter()
+        .zip(output.glyph_infos())
+        .map(|(pos, info)| ShapedGlyph {
+            glyph_index: info.glyph_id as u16,
+            font_index: 0,
+            // Clusters are byte offsets into the shaped text
+            cluster: info.cluster as usize,
+            character: text[info.cluster as usize..].chars().next().unwrap_or(' '),
+            x_offset: pos.x_offset as f32 * scale,
+            y_offset: pos.y_offset as f32 * scale,
+            x_advance: pos.x_advance as f32 * scale,
+            y_advance: pos.y_advance as f32 * scale,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
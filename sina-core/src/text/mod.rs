@@ -2,16 +2,24 @@
 //!
 //! Supports TrueType (.ttf) and OpenType (.otf) fonts using pure Rust.
 
+mod bidi;
 mod bitmap;
+mod collection;
 mod emoji;
 mod font;
 mod glyph;
 mod layout;
+mod paragraph;
+mod style;
 mod variable;
 
+pub use bidi::TextDirection;
 pub use bitmap::{BitmapFontRenderer, BitmapGlyph};
+pub use collection::{FontCollection, FontRun};
 pub use emoji::{ColorEmojiRenderer, ColorLayer};
 pub use font::{Font, FontError};
 pub use glyph::{GlyphCache, GlyphFormat, RasterizedGlyph};
 pub use layout::{ShapedText, TextAlign, TextLayout};
-pub use variable::{VariableFontManager, Variation, VariationAxis};
+pub use paragraph::{LineMetrics, Paragraph, ParagraphStyle};
+pub use style::{FontFeature, TextStyle};
+pub use variable::{NamedInstance, VariableFontManager, Variation, VariationAxis};
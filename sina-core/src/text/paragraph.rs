@@ -0,0 +1,617 @@
+//! Paragraph layout
+//!
+//! Wraps text into lines at Unicode line break opportunities (UAX #14),
+//! breaking inside words only when a single word is wider than the paragraph.
+//! Each line is then ordered for display with the Unicode Bidirectional
+//! Algorithm (UAX #9).
+
+use super::bidi::visual_runs;
+use super::layout::shape;
+use super::{FontCollection, TextAlign, TextDirection, TextStyle};
+use crate::{Canvas, Paint, Point};
+use std::ops::Range;
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+/// Layout options for a paragraph
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphStyle {
+    /// Width lines wrap at; infinite to break only at newlines
+    pub max_width: f32,
+    /// Horizontal alignment of each line within `max_width`, or within the
+    /// widest line when `max_width` is infinite
+    pub align: TextAlign,
+    /// Most lines to lay out, or `None` for no limit
+    pub max_lines: Option<usize>,
+    /// Text ending the last line when `max_lines` cuts the paragraph short
+    pub ellipsis: Option<String>,
+    /// Distance between baselines as a multiple of the line height
+    pub line_spacing: f32,
+    /// Base direction of each paragraph of the text
+    pub direction: TextDirection,
+    /// OpenType features, language and script to shape with
+    ///
+    /// Features limited to a range are ignored, as lines and words are
+    /// shaped separately from the rest of the text.
+    pub text_style: TextStyle,
+}
+
+impl ParagraphStyle {
+    /// Create a left-aligned style wrapping at `max_width`
+    pub fn new(max_width: f32) -> Self {
+        Self {
+            max_width,
+            ..Default::default()
+        }
+    }
+
+    /// Set the alignment
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Limit the number of lines, ending the last one with `ellipsis` if set
+    pub fn with_max_lines(mut self, max_lines: usize, ellipsis: Option<&str>) -> Self {
+        self.max_lines = Some(max_lines);
+        self.ellipsis = ellipsis.map(str::to_owned);
+        self
+    }
+
+    /// Set the line spacing
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Set the base direction
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set the OpenType features, language and script
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        self.text_style = text_style;
+        self
+    }
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        Self {
+            max_width: f32::INFINITY,
+            align: TextAlign::Left,
+            max_lines: None,
+            ellipsis: None,
+            line_spacing: 1.0,
+            direction: TextDirection::Auto,
+            text_style: TextStyle::default(),
+        }
+    }
+}
+
+/// Position and size of one line of a paragraph
+///
+/// Offsets are relative to the paragraph's top-left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMetrics {
+    /// Byte range of the line's text, without trailing spaces or line break
+    pub range: Range<usize>,
+    /// Offset of the line's left edge
+    pub left: f32,
+    /// Width of the line, including any ellipsis; justified lines fill the
+    /// paragraph's width
+    pub width: f32,
+    /// Offset of the line's baseline
+    pub baseline: f32,
+    /// Height above the baseline, the largest of the primary font and any
+    /// fallback fonts the line uses
+    pub ascent: f32,
+    /// Depth below the baseline
+    pub descent: f32,
+    /// Distance from this line's top to the next line's
+    pub height: f32,
+    /// Whether the line ends at a newline or the end of the text rather than
+    /// wrapping
+    pub hard_break: bool,
+}
+
+/// A piece of a line drawn with one `draw_text` call
+#[derive(Debug, Clone)]
+struct Run {
+    text: String,
+    x: f32,
+    /// Whether the run reads right-to-left
+    rtl: bool,
+}
+
+/// Text wrapped into lines, ready to draw
+pub struct Paragraph<'a> {
+    fonts: &'a FontCollection,
+    font_size: f32,
+    text_style: TextStyle,
+    lines: Vec<LineMetrics>,
+    runs: Vec<Vec<Run>>,
+    exceeded_max_lines: bool,
+}
+
+impl<'a> Paragraph<'a> {
+    /// Lay out `text` in `fonts` at `font_size`
+    pub fn new(
+        text: &str,
+        fonts: &'a FontCollection,
+        font_size: f32,
+        style: &ParagraphStyle,
+    ) -> Self {
+        let mut text_style = style.text_style.clone();
+        text_style
+            .features
+            .retain(|feature| feature.range.is_none());
+        let measure =
+            |text: &str| shape(fonts, font_size, text, style.direction, &text_style).width;
+        let mut breaks = break_lines(text, style.max_width, &measure);
+
+        let exceeded_max_lines = style.max_lines.is_some_and(|max| breaks.len() > max);
+        let mut ellipsized = None;
+        if let Some(max_lines) = style.max_lines.filter(|_| exceeded_max_lines) {
+            breaks.truncate(max_lines);
+            if let (Some(ellipsis), Some(last)) = (&style.ellipsis, breaks.last_mut()) {
+                let line = ellipsize(
+                    &text[last.range.clone()],
+                    ellipsis,
+                    style.max_width,
+                    &measure,
+                );
+                last.range.end = last.range.start + line.len();
+                ellipsized = Some(line + ellipsis);
+            }
+        }
+
+        let mut top = 0.0;
+        let mut lines = Vec::with_capacity(breaks.len());
+        let mut runs = Vec::with_capacity(breaks.len());
+        let mut rtl = Vec::with_capacity(breaks.len());
+        for (index, line) in breaks.iter().enumerate() {
+            let last = index + 1 == breaks.len();
+            let (content, truncated) = match &ellipsized {
+                Some(ellipsized) if last => (ellipsized.clone(), true),
+                _ => (text[line.range.clone()].to_owned(), false),
+            };
+            let width = measure(&content);
+            let direction = style
+                .direction
+                .resolve(paragraph_around(text, line.range.start));
+            let justify = style.align == TextAlign::Justify
+                && style.max_width.is_finite()
+                && !line.hard_break
+                && !truncated;
+            let (line_runs, width) = if justify {
+                justify_line(&content, width, style.max_width, direction, &measure)
+            } else {
+                (bidi_line_runs(&content, direction, &measure), width)
+            };
+
+            let (ascent, descent, line_height) = line_extents(fonts, font_size, &line_runs);
+            let height = line_height * style.line_spacing;
+            lines.push(LineMetrics {
+                range: line.range.clone(),
+                left: 0.0,
+                width,
+                baseline: top + ascent,
+                ascent,
+                descent,
+                height,
+                hard_break: line.hard_break,
+            });
+            runs.push(line_runs);
+            rtl.push(direction == TextDirection::Rtl);
+            top += height;
+        }
+
+        // Align within the paragraph's width, or the widest line's
+        let container = if style.max_width.is_finite() {
+            style.max_width
+        } else {
+            lines.iter().map(|line| line.width).fold(0.0, f32::max)
+        };
+        for (line, rtl) in lines.iter_mut().zip(rtl) {
+            line.left = match style.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (container - line.width) / 2.0,
+                TextAlign::Right => container - line.width,
+                // Lines left unjustified start on the paragraph's leading side
+                TextAlign::Justify if rtl => container - line.width,
+                TextAlign::Justify => 0.0,
+            };
+        }
+
+        Self {
+            fonts,
+            font_size,
+            text_style,
+            lines,
+            runs,
+            exceeded_max_lines,
+        }
+    }
+
+    /// Get the metrics of every line, top to bottom
+    pub fn lines(&self) -> &[LineMetrics] {
+        &self.lines
+    }
+
+    /// Get the number of lines
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Get the width of the widest line
+    pub fn width(&self) -> f32 {
+        self.lines.iter().map(|line| line.width).fold(0.0, f32::max)
+    }
+
+    /// Get the total height of the lines
+    pub fn height(&self) -> f32 {
+        self.lines.iter().map(|line| line.height).sum()
+    }
+
+    /// Check whether `max_lines` cut the paragraph short
+    pub fn did_exceed_max_lines(&self) -> bool {
+        self.exceeded_max_lines
+    }
+
+    /// Draw the paragraph with its top-left corner at `position`
+    pub fn paint(&self, canvas: &mut dyn Canvas, position: Point, paint: &Paint) {
+        for (line, runs) in self.lines.iter().zip(&self.runs) {
+            for run in runs {
+                let origin = position + Point::new(line.left + run.x, line.baseline);
+                // A leading mark keeps the run's direction when drawing
+                // resolves it again, as a run of only neutral characters such
+                // as brackets would otherwise read left-to-right
+                let mark = if run.rtl { '\u{200F}' } else { '\u{200E}' };
+                canvas.draw_text_styled(
+                    &format!("{}{}", mark, run.text),
+                    origin,
+                    self.fonts,
+                    self.font_size,
+                    &self.text_style,
+                    paint,
+                );
+            }
+        }
+    }
+}
+
+/// Get the ascent, descent and line height of a line: those of the primary
+/// font, grown to fit any fallback fonts its runs use
+fn line_extents(fonts: &FontCollection, font_size: f32, runs: &[Run]) -> (f32, f32, f32) {
+    let mut used = vec![0];
+    for run in runs {
+        used.extend(fonts.itemize(&run.text).iter().map(|r| r.font_index));
+    }
+    used.sort_unstable();
+    used.dedup();
+
+    used.into_iter()
+        .map(|index| {
+            let font = &fonts.fonts()[index];
+            let scale = font_size / font.units_per_em() as f32;
+            (
+                font.ascender() as f32 * scale,
+                -font.descender() as f32 * scale,
+                font.line_height(font_size),
+            )
+        })
+        .fold((0.0, 0.0, 0.0), |a, b| {
+            (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2))
+        })
+}
+
+/// A line found by `break_lines`
+#[derive(Debug, Clone, PartialEq)]
+struct LineBreak {
+    range: Range<usize>,
+    hard_break: bool,
+}
+
+/// Split text into lines no wider than `max_width`, as measured by `measure`
+///
+/// Lines break greedily at the last opportunity that fits, and inside a word
+/// only when the word alone does not fit. Every line keeps at least one
+/// character, so a narrow width cannot stall the layout. A line is measured
+/// a segment between break opportunities at a time, so that long lines
+/// don't take quadratic time.
+fn break_lines(text: &str, max_width: f32, measure: &dyn Fn(&str) -> f32) -> Vec<LineBreak> {
+    let mut lines = Vec::new();
+    let line = |range: Range<usize>, hard_break: bool| {
+        let content = trim_line_end(&text[range.clone()]);
+        LineBreak {
+            range: range.start..range.start + content.len(),
+            hard_break,
+        }
+    };
+    let wraps = max_width < f32::INFINITY;
+    let fits = |range: Range<usize>| measure(trim_line_end(&text[range])) <= max_width;
+
+    let mut start = 0;
+    let mut last_allowed = None;
+    // The width of the line from `start` to the last opportunity
+    let mut segment_start = 0;
+    let mut width = 0.0;
+    for (end, opportunity) in linebreaks(text) {
+        // Wrap at the last opportunity before the text stops fitting
+        if let Some(allowed) = last_allowed.filter(|_| wraps) {
+            let segment = trim_line_end(&text[segment_start..end]);
+            if width + measure(segment) > max_width {
+                lines.push(line(start..allowed, false));
+                start = allowed;
+                width = 0.0;
+            }
+        }
+        // Split a word too wide for a line of its own
+        while wraps && start == segment_start && !fits(start..end) {
+            let mut ends = text[start..end]
+                .char_indices()
+                .map(|(offset, c)| start + offset + c.len_utf8());
+            let mut split = ends.next().unwrap_or(end);
+            for candidate in ends {
+                if !fits(start..candidate) {
+                    break;
+                }
+                split = candidate;
+            }
+            if split >= end {
+                break;
+            }
+            lines.push(line(start..split, false));
+            start = split;
+            segment_start = split;
+        }
+
+        match opportunity {
+            BreakOpportunity::Mandatory => {
+                lines.push(line(start..end, true));
+                start = end;
+                last_allowed = None;
+                width = 0.0;
+            }
+            BreakOpportunity::Allowed => {
+                last_allowed = Some(end);
+                if wraps {
+                    width += measure(&text[segment_start..end]);
+                }
+            }
+        }
+        segment_start = end;
+    }
+
+    // A final line break leaves an empty line after it
+    if lines.is_empty() || start < text.len() || text.ends_with(is_line_terminator) {
+        lines.push(line(start..text.len(), true));
+    }
+    lines
+}
+
+/// Cut `line` short enough for `ellipsis` to follow it within `max_width`
+fn ellipsize(line: &str, ellipsis: &str, max_width: f32, measure: &dyn Fn(&str) -> f32) -> String {
+    let mut line = line.to_owned();
+    while !line.is_empty() && measure(&(line.clone() + ellipsis)) > max_width {
+        line.pop();
+    }
+    line.truncate(line.trim_end().len());
+    line
+}
+
+/// Split a line into runs of a single direction, placed left to right
+fn bidi_line_runs(line: &str, direction: TextDirection, measure: &dyn Fn(&str) -> f32) -> Vec<Run> {
+    let mut x = 0.0;
+    visual_runs(line, direction)
+        .into_iter()
+        .map(|run| {
+            let text = line[run.range].to_owned();
+            let width = measure(&text);
+            let run = Run {
+                text,
+                x,
+                rtl: run.rtl,
+            };
+            x += width;
+            run
+        })
+        .collect()
+}
+
+/// Spread the words of a line so that it fills `max_width`
+///
+/// Words keep their visual order, and every gap left by a space between
+/// two neighbouring words grows by the same amount.
+fn justify_line(
+    line: &str,
+    width: f32,
+    max_width: f32,
+    direction: TextDirection,
+    measure: &dyn Fn(&str) -> f32,
+) -> (Vec<Run>, f32) {
+    let runs = bidi_line_runs(line, direction, measure);
+
+    // Place each word where it sits in the unjustified line
+    let mut words = Vec::new();
+    for run in &runs {
+        let run_width = measure(&run.text);
+        let mut start = 0;
+        for word in run.text.split(' ') {
+            if !word.is_empty() {
+                let x = if run.rtl {
+                    run.x + run_width - measure(&run.text[..start + word.len()])
+                } else {
+                    run.x + measure(&run.text[..start])
+                };
+                words.push(Run {
+                    text: word.to_owned(),
+                    x,
+                    rtl: run.rtl,
+                });
+            }
+            start += word.len() + 1;
+        }
+    }
+    words.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+    // Neighbours with a space between them are spread apart; runs of a
+    // different direction can also meet without one
+    let min_gap = measure(" ") / 2.0;
+    let spaced: Vec<bool> = words
+        .windows(2)
+        .map(|pair| pair[1].x - (pair[0].x + measure(&pair[0].text)) > min_gap)
+        .collect();
+    let gaps = spaced.iter().filter(|&&spaced| spaced).count();
+    if gaps == 0 || width >= max_width {
+        return (runs, width);
+    }
+
+    let extra = (max_width - width) / gaps as f32;
+    let mut offset = 0.0;
+    for (index, word) in words.iter_mut().enumerate() {
+        if index > 0 && spaced[index - 1] {
+            offset += extra;
+        }
+        word.x += offset;
+    }
+    (words, max_width)
+}
+
+/// Get the text between the line terminators around `offset`
+fn paragraph_around(text: &str, offset: usize) -> &str {
+    let start = text[..offset]
+        .char_indices()
+        .rfind(|&(_, c)| is_line_terminator(c))
+        .map_or(0, |(index, c)| index + c.len_utf8());
+    let end = text[offset..]
+        .find(is_line_terminator)
+        .map_or(text.len(), |index| offset + index);
+    &text[start..end]
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Strip the trailing spaces and line break that a line does not draw
+fn trim_line_end(line: &str) -> &str {
+    line.trim_end_matches(is_line_terminator).trim_end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is 10 units wide
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32 * 10.0
+    }
+
+    fn lines(text: &str, max_width: f32) -> Vec<&str> {
+        break_lines(text, max_width, &measure)
+            .into_iter()
+            .map(|line| &text[line.range])
+            .collect()
+    }
+
+    #[test]
+    fn test_break_lines() {
+        assert_eq!(
+            lines("the quick brown fox jumps", 100.0),
+            ["the quick", "brown fox", "jumps"]
+        );
+        // Trailing spaces do not count towards the width
+        assert_eq!(lines("abcd  efgh", 40.0), ["abcd", "efgh"]);
+        assert_eq!(lines("no wrapping", f32::INFINITY), ["no wrapping"]);
+
+        // Lines are measured a segment at a time, and not at all when they
+        // can't wrap
+        let calls = std::cell::Cell::new(0);
+        let counting = |text: &str| {
+            calls.set(calls.get() + 1);
+            measure(text)
+        };
+        let text = "word ".repeat(100);
+        assert_eq!(break_lines(&text, 1e6, &counting).len(), 1);
+        assert!(calls.get() <= 300, "{}", calls.get());
+        calls.set(0);
+        assert_eq!(break_lines(&text, f32::INFINITY, &counting).len(), 1);
+        assert_eq!(calls.get(), 0);
+
+        // Newlines always break, and a trailing one leaves an empty line
+        let breaks = break_lines("one\ntwo three\r\n", 200.0, &measure);
+        let text: Vec<_> = breaks
+            .iter()
+            .map(|l| &"one\ntwo three\r\n"[l.range.clone()])
+            .collect();
+        assert_eq!(text, ["one", "two three", ""]);
+        assert!(breaks.iter().all(|line| line.hard_break));
+        assert_eq!(lines("", 100.0), [""]);
+
+        // Soft wraps are not hard breaks
+        let breaks = break_lines("aaa bbb", 30.0, &measure);
+        assert_eq!(
+            breaks
+                .iter()
+                .map(|line| line.hard_break)
+                .collect::<Vec<_>>(),
+            [false, true]
+        );
+    }
+
+    #[test]
+    fn test_break_long_words() {
+        // Words wider than the line are split, keeping at least one character
+        assert_eq!(lines("abcdefgh ij", 30.0), ["abc", "def", "gh", "ij"]);
+        assert_eq!(lines("abc", 5.0), ["a", "b", "c"]);
+        // Break opportunities follow UAX #14, such as after hyphens
+        assert_eq!(lines("well-known", 60.0), ["well-", "known"]);
+    }
+
+    #[test]
+    fn test_ellipsize_and_justify() {
+        assert_eq!(ellipsize("hello world", "…", 60.0, &measure), "hello");
+        assert_eq!(ellipsize("hello", "...", 20.0, &measure), "");
+
+        let ltr = TextDirection::Ltr;
+        let (runs, width) = justify_line("aa bb cc", 80.0, 100.0, ltr, &measure);
+        assert_eq!(width, 100.0);
+        let xs: Vec<f32> = runs.iter().map(|run| run.x).collect();
+        assert_eq!(xs, [0.0, 40.0, 80.0]);
+        assert_eq!(runs[2].text, "cc");
+
+        let (runs, width) = justify_line("single", 60.0, 100.0, ltr, &measure);
+        assert_eq!((runs.len(), width), (1, 60.0));
+    }
+
+    #[test]
+    fn test_bidi_lines() {
+        // Hebrew after Latin in a left-to-right line is placed after it
+        let runs: Vec<_> = bidi_line_runs("ab שלום", TextDirection::Ltr, &measure)
+            .into_iter()
+            .map(|run| (run.text, run.x, run.rtl))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("ab ".to_owned(), 0.0, false),
+                ("שלום".to_owned(), 30.0, true)
+            ]
+        );
+
+        // Right-to-left words are spread apart right to left
+        let (runs, width) = justify_line("אא בב", 50.0, 100.0, TextDirection::Rtl, &measure);
+        assert_eq!(width, 100.0);
+        let words: Vec<_> = runs.iter().map(|run| (run.text.as_str(), run.x)).collect();
+        assert_eq!(words, [("בב", 0.0), ("אא", 80.0)]);
+
+        assert_eq!(paragraph_around("one\ntwo three\nfour", 8), "two three");
+        assert_eq!(paragraph_around("one\n", 4), "");
+    }
+}
@@ -0,0 +1,149 @@
+//! OpenType features and shaping options
+//!
+//! A `TextStyle` holds the OpenType feature settings, language and script
+//! that text is shaped with, such as tabular numbers for columns of figures
+//! or no ligatures for code.
+
+use std::ops::Range;
+
+/// An OpenType feature setting, such as `tnum` on or `liga` off
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontFeature {
+    /// Feature tag (e.g., "liga", "kern", "smcp", "tnum", "ss01")
+    pub tag: String,
+
+    /// Feature value: 0 turns the feature off, 1 turns it on, and larger
+    /// values pick an alternate for features such as `salt` and `cvXX`
+    pub value: u32,
+
+    /// Byte range of the text the setting applies to, or `None` for all of it
+    pub range: Option<Range<usize>>,
+}
+
+impl FontFeature {
+    /// Create a feature setting for the whole text
+    pub fn new(tag: impl Into<String>, value: u32) -> Self {
+        Self {
+            tag: tag.into(),
+            value,
+            range: None,
+        }
+    }
+
+    /// Turn a feature on
+    pub fn on(tag: impl Into<String>) -> Self {
+        Self::new(tag, 1)
+    }
+
+    /// Turn a feature off
+    pub fn off(tag: impl Into<String>) -> Self {
+        Self::new(tag, 0)
+    }
+
+    /// Limit the setting to a byte range of the text
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Convert to a rustybuzz feature for a run starting at byte `offset`
+    /// of the text and `len` bytes long, if the setting applies to it
+    pub(crate) fn to_rustybuzz(&self, offset: usize, len: usize) -> Option<rustybuzz::Feature> {
+        let tag = rustybuzz::Tag::from_bytes_lossy(self.tag.as_bytes());
+        let Some(range) = &self.range else {
+            return Some(rustybuzz::Feature::new(tag, self.value, ..));
+        };
+        // Run clusters are byte offsets into the run's own text
+        let start = range.start.max(offset);
+        let end = range.end.min(offset + len);
+        (start < end)
+            .then(|| rustybuzz::Feature::new(tag, self.value, start - offset..end - offset))
+    }
+}
+
+/// Options for shaping text
+///
+/// Features the style doesn't mention keep the font's defaults, so `kern`
+/// and `liga` are on unless turned off. Without a language or script, they
+/// are guessed from the text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStyle {
+    /// OpenType feature settings, later settings overriding earlier ones
+    pub features: Vec<FontFeature>,
+
+    /// BCP 47 language tag (e.g., "tr", "sr", "zh-Hant"), which selects
+    /// language-specific glyphs such as Turkish dotted i or Serbian italics
+    pub language: Option<String>,
+
+    /// ISO 15924 script tag (e.g., "Latn", "Cyrl", "Arab")
+    pub script: Option<String>,
+}
+
+impl TextStyle {
+    /// Create a style with the font's default features
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a feature setting
+    pub fn with_feature(mut self, feature: FontFeature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Set the language
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Set the script
+    pub fn with_script(mut self, script: impl Into<String>) -> Self {
+        self.script = Some(script.into());
+        self
+    }
+
+    /// Get the language as rustybuzz parses it, if set and valid
+    pub(crate) fn rustybuzz_language(&self) -> Option<rustybuzz::Language> {
+        self.language.as_deref()?.parse().ok()
+    }
+
+    /// Get the script as rustybuzz parses it, if set and valid
+    pub(crate) fn rustybuzz_script(&self) -> Option<rustybuzz::Script> {
+        let tag = rustybuzz::Tag::from_bytes_lossy(self.script.as_deref()?.as_bytes());
+        rustybuzz::Script::from_iso15924_tag(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_ranges() {
+        let whole = FontFeature::off("liga").to_rustybuzz(10, 5).unwrap();
+        assert_eq!((whole.value, whole.start, whole.end), (0, 0, u32::MAX));
+
+        // Ranges are clipped to the run and made relative to its start
+        let ranged = FontFeature::on("smcp").with_range(8..12);
+        let feature = ranged.to_rustybuzz(10, 5).unwrap();
+        assert_eq!((feature.value, feature.start, feature.end), (1, 0, 1));
+        assert!(ranged.to_rustybuzz(12, 5).is_none());
+        assert!(ranged.to_rustybuzz(0, 8).is_none());
+    }
+
+    #[test]
+    fn test_language_and_script() {
+        let style = TextStyle::new().with_language("tr").with_script("cyrl");
+        assert_eq!(style.rustybuzz_language().unwrap().as_str(), "tr");
+        assert_eq!(
+            style.rustybuzz_script(),
+            rustybuzz::Script::from_iso15924_tag(rustybuzz::Tag::from_bytes(b"Cyrl"))
+        );
+        assert!(TextStyle::new()
+            .with_script("")
+            .rustybuzz_script()
+            .is_none());
+        assert!(TextStyle::new().rustybuzz_language().is_none());
+    }
+}
@@ -1,8 +1,10 @@
 //! Variable font support for font variations
 //!
-//! Handles OpenType variable fonts with design space axes.
+//! Handles OpenType variable fonts with design space axes. Variations are
+//! applied to a `Font` with `Font::set_variation` or a named instance.
 
 use super::Font;
+use ttf_parser::Tag;
 
 /// A variable font axis
 #[derive(Debug, Clone)]
@@ -33,6 +35,26 @@ pub struct Variation {
     pub value: f32,
 }
 
+impl Variation {
+    /// Create a variation setting, e.g. `Variation::new("wght", 650.0)`
+    pub fn new(tag: impl Into<String>, value: f32) -> Self {
+        Self {
+            tag: tag.into(),
+            value,
+        }
+    }
+}
+
+/// A named instance of a variable font, such as "Bold" or "Condensed"
+#[derive(Debug, Clone)]
+pub struct NamedInstance {
+    /// Instance name
+    pub name: String,
+
+    /// Axis values of the instance
+    pub coordinates: Vec<Variation>,
+}
+
 /// Variable font manager
 pub struct VariableFontManager;
 
@@ -76,10 +98,55 @@ impl VariableFontManager {
     }
 
     /// Get named instances (predefined variations)
-    /// Note: Requires instance iteration support in ttf-parser
-    pub fn instances(_font: &Font) -> Vec<String> {
-        // Stub - ttf-parser's fvar doesn't expose instances iterator
-        Vec::new()
+    pub fn instances(font: &Font) -> Vec<NamedInstance> {
+        let face = font.face();
+        let Some(fvar) = face.raw_face().table(Tag::from_bytes(b"fvar")) else {
+            return Vec::new();
+        };
+        let axes: Vec<_> = face.variation_axes().into_iter().collect();
+        let read_u16 = |offset: usize| -> Option<u16> {
+            let bytes = fvar.get(offset..offset + 2)?;
+            Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+        let read_fixed = |offset: usize| -> Option<f32> {
+            let bytes = fvar.get(offset..offset + 4)?;
+            Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 65536.0)
+        };
+
+        // Instance records follow the axis records
+        let parse = || -> Option<Vec<NamedInstance>> {
+            let axes_offset = read_u16(4)? as usize;
+            let axis_count = read_u16(8)? as usize;
+            let axis_size = read_u16(10)? as usize;
+            let instance_count = read_u16(12)? as usize;
+            let instance_size = read_u16(14)? as usize;
+            let start = axes_offset + axis_count * axis_size;
+
+            (0..instance_count)
+                .map(|i| {
+                    let record = start + i * instance_size;
+                    let name_id = read_u16(record)?;
+                    let coordinates = axes
+                        .iter()
+                        .enumerate()
+                        .map(|(axis_index, axis)| {
+                            Some(Variation::new(
+                                String::from_utf8_lossy(&axis.tag.to_bytes()),
+                                read_fixed(record + 4 + axis_index * 4)?,
+                            ))
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    let name = face
+                        .names()
+                        .into_iter()
+                        .find(|name| name.name_id == name_id)
+                        .and_then(|name| name.to_string())
+                        .unwrap_or_else(|| "Unknown".to_string());
+                    Some(NamedInstance { name, coordinates })
+                })
+                .collect()
+        };
+        parse().unwrap_or_default()
     }
 }
 
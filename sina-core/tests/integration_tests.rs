@@ -103,6 +103,496 @@ fn test_paint_configuration() {
     assert_eq!(paint.color.r, 255);
 }
 
+#[test]
+fn test_restore_to_count() {
+    let mut surface = CpuSurface::new(100, 100);
+    let canvas = surface.canvas();
+    assert_eq!(canvas.save_count(), 1);
+
+    canvas.save();
+    let count = canvas.save_count();
+    canvas.set_global_alpha(0.5);
+    canvas.save();
+    canvas.save();
+    canvas.set_global_alpha(0.1);
+    assert_eq!(canvas.save_count(), 4);
+
+    canvas.restore_to_count(count);
+    assert_eq!(canvas.save_count(), count);
+    assert_eq!(canvas.global_alpha(), 0.5);
+
+    canvas.restore_to_count(0);
+    assert_eq!(canvas.save_count(), 1);
+    assert_eq!(canvas.global_alpha(), 1.0);
+}
+
+#[test]
+fn test_matrix_stack() {
+    let mut surface = CpuSurface::new(100, 100);
+    let canvas = surface.canvas();
+
+    canvas.translate(10.0, 20.0);
+    canvas.save();
+    canvas.scale(2.0, 2.0);
+    let p = canvas.get_matrix().transform_point2(Point::new(1.0, 1.0));
+    assert_eq!(p, Point::new(12.0, 22.0));
+
+    canvas.restore();
+    let p = canvas.get_matrix().transform_point2(Point::new(1.0, 1.0));
+    assert_eq!(p, Point::new(11.0, 21.0));
+
+    canvas.set_matrix(Matrix3x3::IDENTITY);
+    canvas.skew(1.0, 0.0);
+    let p = canvas.get_matrix().transform_point2(Point::new(0.0, 5.0));
+    assert_eq!(p, Point::new(5.0, 5.0));
+}
+
+#[test]
+fn test_transformed_text() {
+    let font = dejavu("DejaVuSans.ttf");
+    let render = |degrees: f32| {
+        let mut surface = CpuSurface::new(100, 100);
+        let canvas = surface.canvas();
+        canvas.translate(50.0, 50.0);
+        canvas.rotate(degrees);
+        canvas.draw_text("Hi", Point::new(0.0, 0.0), &font, 24.0, &Paint::new());
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    };
+    // Rows of the surface with any ink in them
+    let inked_rows = |pixels: &[u8]| -> Vec<usize> {
+        (0..100)
+            .filter(|y| pixels[y * 400..(y + 1) * 400].chunks(4).any(|p| p[3] > 0))
+            .collect()
+    };
+
+    // "Hi" has no descenders, so upright it stays above the baseline; turned
+    // 30 degrees clockwise, its baseline runs down to the right below y = 50
+    let upright = render(0.0);
+    let rotated = render(30.0);
+    assert_ne!(rotated, upright);
+    assert!(inked_rows(&upright).iter().all(|&y| y <= 50));
+    assert!(inked_rows(&rotated).iter().any(|&y| y > 52));
+}
+
+#[test]
+fn test_paragraph_layout() {
+    let font = dejavu("DejaVuSans.ttf");
+    let text = "The quick brown fox jumps over the lazy dog.\nSecond paragraph.";
+    let layout = TextLayout::new(font, 16.0);
+
+    let style = ParagraphStyle::new(150.0).with_line_spacing(1.5);
+    let paragraph = layout.paragraph(text, &style);
+    assert!(paragraph.line_count() >= 4);
+    assert!(paragraph.width() <= 150.0);
+    let lines = paragraph.lines();
+    assert_eq!(&text[lines[0].range.clone()], "The quick brown");
+    assert!(!lines[0].hard_break);
+    let line_height = layout.font().line_height(16.0) * 1.5;
+    assert!((lines[1].baseline - lines[0].baseline - line_height).abs() < 1e-3);
+    assert!((paragraph.height() - line_height * lines.len() as f32).abs() < 1e-3);
+    let last_two: Vec<_> = lines[lines.len() - 2..]
+        .iter()
+        .map(|line| (&text[line.range.clone()], line.hard_break))
+        .collect();
+    assert_eq!(last_two, [("Second", false), ("paragraph.", true)]);
+
+    // Justified lines of several words fill the width, except the last of
+    // each paragraph
+    let justified = layout.paragraph(text, &style.clone().with_align(TextAlign::Justify));
+    for line in justified.lines() {
+        assert_eq!(line.left, 0.0);
+        let words = text[line.range.clone()].contains(' ');
+        assert_eq!(line.width == 150.0, words && !line.hard_break);
+    }
+    let centered = layout.paragraph(text, &style.clone().with_align(TextAlign::Center));
+    let first = &centered.lines()[0];
+    assert!((first.left * 2.0 + first.width - 150.0).abs() < 1e-3);
+
+    // Cutting the paragraph short ends it with an ellipsis
+    let truncated = layout.paragraph(text, &style.with_max_lines(2, Some("…")));
+    assert_eq!(truncated.line_count(), 2);
+    assert!(truncated.did_exceed_max_lines());
+    assert!(truncated.lines()[1].width <= 150.0);
+
+    let mut surface = CpuSurface::new(200, 200);
+    truncated.paint(surface.canvas(), Point::new(10.0, 10.0), &Paint::new());
+    let pixels = surface.pixels(PixelFormat::RGBA8).unwrap();
+    assert!(pixels.chunks(4).any(|pixel| pixel[3] > 0));
+}
+
+#[test]
+fn test_font_fallback() {
+    // DejaVu Sans lacks the mathematical alphanumerics its math sibling has
+    let (sans, math) = (dejavu("DejaVuSans.ttf"), dejavu("DejaVuMathTeXGyre.ttf"));
+    assert!(!sans.has_glyph('𝐀') && math.has_glyph('𝐀'));
+    let fonts = FontCollection::new(sans).with_fallback(math);
+
+    let runs: Vec<_> = fonts
+        .itemize("A𝐀B")
+        .into_iter()
+        .map(|run| (run.range, run.font_index))
+        .collect();
+    assert_eq!(runs, [(0..1, 0), (1..5, 1), (5..6, 0)]);
+    // A combining mark stays with its base even though the primary font has it
+    assert_eq!(fonts.itemize("𝐀\u{301}").len(), 1);
+
+    let layout = TextLayout::with_fonts(fonts.clone(), 24.0);
+    let shaped = layout.shape("A𝐀B");
+    let font_indices: Vec<_> = shaped.glyphs.iter().map(|g| g.font_index).collect();
+    assert_eq!(font_indices, [0, 1, 0]);
+    assert!(shaped.glyphs.iter().all(|g| g.glyph_index != 0));
+
+    // Drawing with fallback renders the glyph instead of the missing-glyph box
+    let render = |fallback: bool| {
+        let mut surface = CpuSurface::new(60, 40);
+        let canvas = surface.canvas();
+        let paint = Paint::new();
+        let origin = Point::new(10.0, 30.0);
+        if fallback {
+            canvas.draw_text_with_fallback("𝐀", origin, &fonts, 24.0, &paint);
+        } else {
+            canvas.draw_text("𝐀", origin, fonts.primary(), 24.0, &paint);
+        }
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    };
+    let with_fallback = render(true);
+    assert!(with_fallback.chunks(4).any(|pixel| pixel[3] > 0));
+    assert_ne!(with_fallback, render(false));
+}
+
+#[test]
+fn test_bidi_layout() {
+    let font = dejavu("DejaVuSans.ttf");
+    let mut layout = TextLayout::new(font, 20.0);
+    let clusters = |layout: &TextLayout, text: &str| -> Vec<usize> {
+        layout
+            .shape(text)
+            .glyphs
+            .iter()
+            .map(|g| g.cluster)
+            .collect()
+    };
+
+    // Hebrew is shaped right to left after the Latin word
+    assert_eq!(layout.direction(), TextDirection::Auto);
+    assert_eq!(clusters(&layout, "abc שלום"), [0, 1, 2, 3, 10, 8, 6, 4]);
+    // In a right-to-left paragraph the Hebrew word comes first
+    layout.set_direction(TextDirection::Rtl);
+    assert_eq!(clusters(&layout, "abc שלום"), [10, 8, 6, 4, 3, 0, 1, 2]);
+    // Auto direction follows the first strong character
+    layout.set_direction(TextDirection::Auto);
+    assert_eq!(clusters(&layout, "שלום abc"), [9, 10, 11, 8, 6, 4, 2, 0]);
+
+    // Unjustified lines of a right-to-left paragraph hug the right edge
+    let text = "שלום עולם שלום עולם שלום";
+    let style = ParagraphStyle::new(150.0).with_align(TextAlign::Justify);
+    let paragraph = layout.paragraph(text, &style);
+    assert!(paragraph.line_count() >= 2);
+    let last = paragraph.lines().last().unwrap();
+    assert!((last.left + last.width - 150.0).abs() < 1e-3);
+    let ltr = layout.paragraph(text, &style.with_direction(TextDirection::Ltr));
+    assert_eq!(ltr.lines().last().unwrap().left, 0.0);
+}
+
+#[test]
+fn test_font_features() {
+    let font = dejavu("DejaVuSans.ttf");
+    let mut layout = TextLayout::new(font.clone(), 20.0);
+    let glyphs = |layout: &TextLayout, text: &str| -> Vec<u16> {
+        layout
+            .shape(text)
+            .glyphs
+            .iter()
+            .map(|g| g.glyph_index)
+            .collect()
+    };
+
+    // Ligatures and kerning are on by default
+    let ligated = glyphs(&layout, "fi fi");
+    assert_eq!(ligated.len(), 3);
+    let kerned = layout.measure("AV").0;
+    layout.set_style(
+        TextStyle::new()
+            .with_feature(FontFeature::off("liga"))
+            .with_feature(FontFeature::off("kern")),
+    );
+    assert_eq!(glyphs(&layout, "fi fi").len(), 5);
+    assert!(layout.measure("AV").0 > kerned);
+
+    // A ranged setting only affects its part of the text
+    layout.set_style(TextStyle::new().with_feature(FontFeature::off("liga").with_range(0..2)));
+    assert_eq!(glyphs(&layout, "fi fi")[2..], ligated[1..]);
+    assert_eq!(glyphs(&layout, "fi fi").len(), 4);
+
+    // Serbian uses its own form of be, but only in Cyrillic text
+    layout.set_style(TextStyle::new());
+    let be = glyphs(&layout, "б");
+    layout.set_style(TextStyle::new().with_language("sr"));
+    let serbian = glyphs(&layout, "б");
+    assert_ne!(serbian, be);
+    layout.set_style(TextStyle::new().with_language("sr").with_script("Latn"));
+    assert_eq!(glyphs(&layout, "б"), be);
+
+    // Paragraphs and canvases shape with the style too
+    let style = TextStyle::new().with_feature(FontFeature::off("kern"));
+    let plain = TextLayout::new(font.clone(), 20.0);
+    let paragraph = plain.paragraph(
+        "AV",
+        &ParagraphStyle::default().with_text_style(style.clone()),
+    );
+    assert!(paragraph.width() > kerned);
+    let render = |style: &TextStyle| {
+        let mut surface = CpuSurface::new(60, 30);
+        let fonts = FontCollection::new(font.clone());
+        let canvas = surface.canvas();
+        canvas.draw_text_styled(
+            "AV",
+            Point::new(0.0, 24.0),
+            &fonts,
+            20.0,
+            style,
+            &Paint::new(),
+        );
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    };
+    assert_ne!(render(&style), render(&TextStyle::new()));
+}
+
+#[test]
+fn test_color_glyphs() {
+    let mut font = color_test_font();
+    let mut renderer = ColorEmojiRenderer::new();
+    let (a, b) = (
+        font.glyph_index('A').unwrap(),
+        font.glyph_index('B').unwrap(),
+    );
+    assert!(renderer.has_color_layers(&font, a) && renderer.has_color_layers(&font, b));
+    assert!(!renderer.has_color_layers(&font, 1));
+    assert_eq!(renderer.num_palettes(&font), 2);
+
+    // Version 0 layers, with the second one in the foreground color
+    let layers = |renderer: &mut ColorEmojiRenderer, palette| {
+        renderer
+            .get_color_layers(&font, a, palette)
+            .unwrap()
+            .into_iter()
+            .map(|layer| (layer.glyph_id, layer.color, layer.palette_index))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        layers(&mut renderer, 0),
+        [(1, Color::rgb(255, 0, 0), 0), (2, Color::BLACK, 0xFFFF)]
+    );
+    renderer.set_foreground(Color::WHITE);
+    assert_eq!(
+        layers(&mut renderer, 1),
+        [(1, Color::rgb(0, 255, 0), 0), (2, Color::WHITE, 0xFFFF)]
+    );
+    // The version 1 glyph is a paint graph rather than layers
+    assert!(renderer.get_color_layers(&font, b, 0).is_none());
+
+    let render = |font: &Font| {
+        let mut surface = CpuSurface::new(100, 60);
+        let paint = Paint::with_color(Color::rgb(0, 0, 200));
+        let canvas = surface.canvas();
+        canvas.draw_text("AB", Point::new(10.0, 50.0), font, 40.0, &paint);
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    };
+    let pixel = |pixels: &[u8], x: usize, y: usize| {
+        let i = (y * 100 + x) * 4;
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    };
+
+    // 'A' is a red square with a square in the paint color inside
+    let pixels = render(&font);
+    assert_eq!(pixel(&pixels, 18, 22), [255, 0, 0, 255]);
+    assert_eq!(pixel(&pixels, 30, 34), [0, 0, 200, 255]);
+    // 'B' is a gradient from red on the left to blue on the right
+    let (left, right) = (pixel(&pixels, 56, 34), pixel(&pixels, 84, 34));
+    assert!(left[0] > 200 && left[2] < 50, "{:?}", left);
+    assert!(right[0] < 50 && right[2] > 200, "{:?}", right);
+
+    // Another palette swaps the colors
+    font.set_color_palette(1);
+    let pixels = render(&font);
+    assert_eq!(pixel(&pixels, 18, 22), [0, 255, 0, 255]);
+    let right = pixel(&pixels, 84, 34);
+    assert!(
+        right[0] > 200 && right[1] > 200 && right[2] < 50,
+        "{:?}",
+        right
+    );
+}
+
+#[test]
+fn test_bitmap_glyphs() {
+    let font = bitmap_test_font();
+    let (a, b) = (
+        font.glyph_index('A').unwrap(),
+        font.glyph_index('B').unwrap(),
+    );
+    assert!(BitmapFontRenderer::has_bitmaps(&font));
+    assert_eq!(BitmapFontRenderer::available_sizes(&font), [20, 40, 60]);
+
+    let bitmap = |glyph, ppem| {
+        let bitmap = BitmapFontRenderer::get_bitmap(&font, glyph, ppem).unwrap();
+        let center = ((bitmap.height / 2 * bitmap.width + bitmap.width / 2) * 4) as usize;
+        let color = &bitmap.pixels[center..center + 3];
+        let color = [color[0] > 128, color[1] > 128, color[2] > 128];
+        (bitmap.ppem, bitmap.width, bitmap.bearing_y, color)
+    };
+    // The smallest strike at least as large as the request is used
+    assert_eq!(bitmap(a, 16), (20, 20, -4, [true, false, false]));
+    assert_eq!(bitmap(a, 21), (40, 40, -8, [false, false, true]));
+    // JPEG graphics decode too, and larger requests use the largest strike
+    assert_eq!(bitmap(a, 100), (60, 60, -12, [false, true, false]));
+    // 'B' repeats 'A' in the only strike that has it
+    assert_eq!(bitmap(b, 16), (40, 40, -8, [false, false, true]));
+    assert_eq!(
+        BitmapFontRenderer::get_bitmap(&font, 1, 20).map(|b| b.ppem),
+        None
+    );
+
+    // Drawn at 20 pixels, the bitmap hangs 4 pixels below the baseline
+    let mut surface = CpuSurface::new(60, 60);
+    let canvas = surface.canvas();
+    canvas.draw_text("A", Point::new(10.0, 40.0), &font, 20.0, &Paint::new());
+    let pixels = surface.pixels(PixelFormat::RGBA8).unwrap();
+    let pixel = |x: usize, y: usize| &pixels[(y * 60 + x) * 4..(y * 60 + x) * 4 + 4];
+    assert_eq!(pixel(20, 42), [255, 0, 0, 255]);
+    assert_eq!(pixel(20, 19)[3], 0);
+    assert_eq!(pixel(20, 45)[3], 0);
+
+    // Bitmaps that would scale past the size limit are not used
+    let mut cache = GlyphCache::new(4);
+    let huge = cache.get_or_rasterize_indexed(&font, a, 100_000.0);
+    assert!(huge.is_none_or(|glyph| glyph.format != sina::text::GlyphFormat::Rgba));
+}
+
+#[test]
+fn test_cbdt_bitmap_glyphs() {
+    let font = cbdt_test_font();
+    let (a, b) = (
+        font.glyph_index('A').unwrap(),
+        font.glyph_index('B').unwrap(),
+    );
+    assert!(BitmapFontRenderer::has_bitmaps(&font));
+    assert_eq!(BitmapFontRenderer::available_sizes(&font), [20, 40]);
+
+    // Premultiplied BGRA pixels come back as straight RGBA
+    let bgra = BitmapFontRenderer::get_bitmap(&font, a, 16).unwrap();
+    assert_eq!(
+        (
+            bgra.ppem,
+            bgra.width,
+            bgra.height,
+            bgra.bearing_x,
+            bgra.bearing_y
+        ),
+        (20, 2, 1, 1, 2)
+    );
+    assert_eq!(bgra.pixels, [255, 0, 0, 128, 0, 0, 255, 255]);
+
+    let png = BitmapFontRenderer::get_bitmap(&font, a, 21).unwrap();
+    assert_eq!(
+        (
+            png.ppem,
+            png.width,
+            png.height,
+            png.bearing_x,
+            png.bearing_y
+        ),
+        (40, 40, 40, 2, -8)
+    );
+    assert_eq!(png.pixels[..4], [0, 255, 0, 255]);
+
+    assert!(BitmapFontRenderer::get_bitmap(&font, b, 20).is_none());
+}
+
+#[test]
+fn test_variable_font_instances() {
+    let mut font = variable_test_font();
+    assert!(VariableFontManager::is_variable(&font));
+    let axes = VariableFontManager::axes(&font);
+    assert_eq!(
+        (axes[0].tag.as_str(), axes[0].name.as_str()),
+        ("wght", "Weight")
+    );
+    let instances: Vec<_> = VariableFontManager::instances(&font)
+        .into_iter()
+        .map(|instance| {
+            let axis = &instance.coordinates[0];
+            (instance.name, axis.tag.clone(), axis.value)
+        })
+        .collect();
+    assert_eq!(
+        instances,
+        [
+            ("Regular".to_string(), "wght".to_string(), 400.0),
+            ("Bold".to_string(), "wght".to_string(), 700.0)
+        ]
+    );
+
+    let glyph = font.glyph_index('@').unwrap();
+    // Normalized coordinates are F2Dot14, so widths are only close to exact
+    let width = |font: &Font| {
+        font.glyph_outline(glyph, 1000.0)
+            .unwrap()
+            .bounds()
+            .unwrap()
+            .width
+            .round()
+    };
+    let advance = |font: &Font| TextLayout::new(font.clone(), 100.0).shape("@").glyphs[0].x_advance;
+    assert_eq!((width(&font), advance(&font)), (200.0, 100.0));
+
+    // Halfway to the heaviest weight
+    assert!(font.set_variation("wght", 650.0));
+    assert!(!font.set_variation("wdth", 100.0));
+    assert_eq!(font.variations()[0].value, 650.0);
+    assert_eq!((width(&font), advance(&font)), (300.0, 110.0));
+
+    // Values are clamped to the axis, and named instances replace them
+    font.set_variations(&[Variation::new("wght", 2000.0)]);
+    assert_eq!(width(&font), 400.0);
+    assert!(font.set_named_instance("Bold"));
+    assert!(!font.set_named_instance("Black"));
+    assert_eq!(width(&font), 320.0);
+    font.reset_variations();
+    assert_eq!(width(&font), 200.0);
+
+    // Rasterized glyphs follow the instance too
+    let render = |font: &Font| {
+        let mut surface = CpuSurface::new(100, 60);
+        let canvas = surface.canvas();
+        canvas.draw_text("@", Point::new(0.0, 50.0), font, 100.0, &Paint::new());
+        surface.pixels(PixelFormat::RGBA8).unwrap()
+    };
+    let alpha = |pixels: &[u8], x: usize| pixels[(10 * 100 + x) * 4 + 3];
+    let regular = render(&font);
+    assert_eq!((alpha(&regular, 50), alpha(&regular, 70)), (255, 0));
+    let heavy = render(
+        &font
+            .clone()
+            .with_variations(&[Variation::new("wght", 900.0)]),
+    );
+    assert_eq!((alpha(&heavy, 50), alpha(&heavy, 70)), (255, 255));
+}
+
+#[test]
+fn test_clip_under_transform() {
+    let mut surface = CpuSurface::new(100, 100);
+    let canvas = surface.canvas();
+    canvas.translate(50.0, 50.0);
+    canvas.clip_rect(Rect::new(0.0, 0.0, 10.0, 10.0), ClipOp::Intersect, true);
+    canvas.save();
+    canvas.clip_rect(Rect::new(100.0, 100.0, 10.0, 10.0), ClipOp::Intersect, true);
+    // Fully clipped out; must not panic
+    canvas.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), &Paint::new());
+    canvas.restore();
+    canvas.draw_circle(Point::new(5.0, 5.0), 20.0, &Paint::new());
+}
+
 #[test]
 fn test_png_export() -> Result<(), Box<dyn std::error::Error>> {
     let mut surface = CpuSurface::new(100, 100);
@@ -149,3 +639,284 @@ fn test_variable_font_manager() {
     assert_eq!(axis.tag, "wght");
     assert_eq!(axis.default_value, 400.0);
 }
+
+/// Load a DejaVu font from the system
+///
+/// Text shaping tests need a real font's GSUB and GPOS tables. This panics
+/// with a hint, rather than skipping the test, when the font is missing.
+fn dejavu(file: &str) -> Font {
+    let path = format!("/usr/share/fonts/truetype/dejavu/{}", file);
+    Font::from_file(&path)
+        .unwrap_or_else(|error| panic!("{} is needed (install fonts-dejavu): {}", path, error))
+}
+
+/// Write numbers as big-endian 16-bit words
+fn words(values: &[i32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|&v| (v as u16).to_be_bytes())
+        .collect()
+}
+
+/// Build a minimal TrueType font with extra tables
+///
+/// The font has five glyphs: .notdef, a square from (100, 0) to (900, 800),
+/// a smaller square from (400, 300) to (600, 500) that '@' maps to, and
+/// two empty glyphs that 'A' and 'B' map to. Every glyph advances 1000
+/// units of the 1000 unit em.
+fn test_font(extra: Vec<(&[u8; 4], Vec<u8>)>) -> Font {
+    fn square(x_min: i32, y_min: i32, x_max: i32, y_max: i32) -> Vec<u8> {
+        let mut glyph = words(&[1, x_min, y_min, x_max, y_max, 3, 0]);
+        glyph.extend([1, 1, 1, 1]);
+        glyph.extend(words(&[x_min, 0, x_max - x_min, 0]));
+        glyph.extend(words(&[y_min, y_max - y_min, 0, y_min - y_max, 0]));
+        glyph
+    }
+
+    let head = words(&[
+        1, 0, 1, 0, 0, 0, 0x5F0F, 0x3CF5, 0, 1000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1000, 1000, 0, 8,
+        2, 1, 0,
+    ]);
+    let hhea = words(&[
+        1, 0, 800, -200, 0, 1000, 0, 0, 1000, 1, 0, 0, 0, 0, 0, 0, 0, 5,
+    ]);
+    let maxp = words(&[0, 0x5000, 5]);
+    let hmtx = words(&[1000, 0, 1000, 0, 1000, 0, 1000, 0, 1000, 0]);
+    let glyf = [square(100, 0, 900, 800), square(400, 300, 600, 500)].concat();
+    let loca = words(&[0, 0, 0, 0, 0, 36, 0, 72, 0, 72, 0, 72]);
+    // '@', 'A' and 'B' map to glyphs 2, 3 and 4
+    let cmap = words(&[
+        0,
+        1,
+        3,
+        1,
+        0,
+        12,
+        4,
+        32,
+        0,
+        4,
+        4,
+        1,
+        0,
+        0x42,
+        0xFFFF,
+        0,
+        0x40,
+        0xFFFF,
+        3 - 0x41,
+        1,
+        0,
+        0,
+    ]);
+
+    let mut tables = vec![
+        (b"cmap", cmap),
+        (b"glyf", glyf),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", hmtx),
+        (b"loca", loca),
+        (b"maxp", maxp),
+    ];
+    tables.extend(extra);
+    tables.sort_by_key(|&(tag, _)| *tag);
+
+    let mut font = words(&[1, 0, tables.len() as i32, 128, 3, 16]);
+    let mut data = Vec::new();
+    let data_start = 12 + 16 * tables.len();
+    for (tag, table) in &tables {
+        font.extend(*tag);
+        font.extend([0; 4]);
+        font.extend(((data_start + data.len()) as u32).to_be_bytes());
+        font.extend((table.len() as u32).to_be_bytes());
+        data.extend(table);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+    font.extend(data);
+    Font::from_bytes(font).expect("test font")
+}
+
+/// Build a test font with COLR/CPAL color glyphs
+///
+/// 'A' is a COLR version 0 glyph: the large square in palette entry 0 with
+/// the small square in the foreground color on top. 'B' is a version 1
+/// glyph filling the large square with a linear gradient from entry 0 on
+/// the left to entry 1 on the right. Palette 0 is red and blue, palette 1
+/// green and yellow.
+fn color_test_font() -> Font {
+    let mut colr = words(&[1, 1, 0, 34, 0, 40, 2, 0, 48, 0, 0, 0, 0, 0, 0, 0, 0]);
+    // Version 0: glyph 3 has layers glyph 1 in entry 0 and glyph 2 in the
+    // foreground color
+    colr.extend(words(&[3, 0, 2, 1, 0, 2, 0xFFFF]));
+    // Version 1: glyph 4 paints glyph 1 with a linear gradient
+    colr.extend(words(&[0, 1, 4, 0, 10]));
+    colr.extend([10, 0, 0, 6]);
+    colr.extend(words(&[1]));
+    colr.extend([4, 0, 0, 16]);
+    colr.extend(words(&[100, 0, 900, 0, 100, 800]));
+    colr.push(0);
+    colr.extend(words(&[2, 0, 0, 0x4000, 0x4000, 1, 0x4000]));
+
+    // Colors are stored as BGRA
+    let mut cpal = words(&[0, 2, 2, 4, 0, 16, 0, 2]);
+    cpal.extend([
+        0, 0, 255, 255, 255, 0, 0, 255, 0, 255, 0, 255, 0, 255, 255, 255,
+    ]);
+
+    test_font(vec![(b"COLR", colr), (b"CPAL", cpal)])
+}
+
+/// Build a test font with sbix bitmap glyphs
+///
+/// 'A' is a red 20x20 PNG in the 20 ppem strike, a blue 40x40 PNG in the
+/// 40 ppem strike and a green 60x60 JPEG in the 60 ppem strike, each
+/// hanging a fifth of its height below the baseline. 'B' only has a `dupe`
+/// of 'A' in the 40 ppem strike.
+fn bitmap_test_font() -> Font {
+    fn encode(size: u32, color: [u8; 3], format: ::image::ImageFormat) -> Vec<u8> {
+        let image = ::image::RgbImage::from_pixel(size, size, ::image::Rgb(color));
+        let mut data = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut data, format).unwrap();
+        data.into_inner()
+    }
+    fn strike(ppem: i32, a: Option<(i32, &[u8; 4], Vec<u8>)>, b: Option<Vec<u8>>) -> Vec<u8> {
+        let mut records = vec![Vec::new(); 5];
+        if let Some((y, graphic_type, data)) = a {
+            records[3] = [words(&[0, y]), graphic_type.to_vec(), data].concat();
+        }
+        if let Some(data) = b {
+            records[4] = [words(&[0, 0]), b"dupe".to_vec(), data].concat();
+        }
+        // Glyph data offsets follow the ppem and ppi, one past the last glyph
+        let mut strike = words(&[ppem, 72]);
+        let mut offset = 4 + 4 * 6;
+        strike.extend((offset as u32).to_be_bytes());
+        for record in &records {
+            offset += record.len();
+            strike.extend((offset as u32).to_be_bytes());
+        }
+        strike.extend(records.concat());
+        strike
+    }
+
+    let png = ::image::ImageFormat::Png;
+    let strikes = [
+        strike(20, Some((-4, b"png ", encode(20, [255, 0, 0], png))), None),
+        strike(
+            40,
+            Some((-8, b"png ", encode(40, [0, 0, 255], png))),
+            Some(words(&[3])),
+        ),
+        strike(
+            60,
+            Some((
+                -12,
+                b"jpg ",
+                encode(60, [0, 255, 0], ::image::ImageFormat::Jpeg),
+            )),
+            None,
+        ),
+    ];
+
+    let mut sbix = words(&[1, 1, 0, strikes.len() as i32]);
+    let mut offset = 8 + 4 * strikes.len();
+    for strike in &strikes {
+        sbix.extend((offset as u32).to_be_bytes());
+        offset += strike.len();
+    }
+    sbix.extend(strikes.concat());
+
+    test_font(vec![(b"sbix", sbix)])
+}
+
+/// Build a test font with CBLC/CBDT bitmap glyphs
+///
+/// 'A' is a 2x1 premultiplied BGRA bitmap in the 20 ppem strike, half
+/// transparent red then blue, with its bottom-left corner at (1, 2). In the
+/// 40 ppem strike it is a green 40x40 PNG hanging 8 pixels below the
+/// baseline. 'B' has no bitmaps.
+fn cbdt_test_font() -> Font {
+    let mut png = std::io::Cursor::new(Vec::new());
+    ::image::RgbImage::from_pixel(40, 40, ::image::Rgb([0, 255, 0]))
+        .write_to(&mut png, ::image::ImageFormat::Png)
+        .unwrap();
+    let png = png.into_inner();
+
+    // Small glyph metrics (height, width, bearings and advance), then data
+    let bgra = [vec![1, 2, 1, 3, 2], vec![0, 0, 128, 128, 255, 0, 0, 255]].concat();
+    let png = [
+        vec![40, 40, 2, 32, 40],
+        (png.len() as u32).to_be_bytes().to_vec(),
+        png,
+    ]
+    .concat();
+    // Image format 1 is byte-aligned pixels, 17 a PNG
+    let strikes = [(20, 1, bgra), (40, 17, png)];
+
+    let mut cbdt = words(&[3, 0]);
+    let mut cblc = words(&[3, 0, 0, strikes.len() as i32]);
+    let mut index = Vec::new();
+    let index_start = 8 + 48 * strikes.len();
+    for (ppem, image_format, glyph) in strikes {
+        // BitmapSize record with one index subtable, no line metrics and
+        // 32-bit color for glyph 3
+        cblc.extend(((index_start + index.len()) as u32).to_be_bytes());
+        cblc.extend(words(&[0, 24, 0, 1]));
+        cblc.extend([0; 28]);
+        cblc.extend(words(&[3, 3]));
+        cblc.extend([ppem, ppem, 32, 1]);
+
+        // Index subtable array entry, then a format 1 subtable locating the
+        // glyph in CBDT
+        index.extend(words(&[3, 3, 0, 8, 1, image_format]));
+        index.extend((cbdt.len() as u32).to_be_bytes());
+        index.extend(words(&[0, 0, 0, glyph.len() as i32]));
+        cbdt.extend(glyph);
+    }
+    cblc.extend(index);
+
+    test_font(vec![(b"CBDT", cbdt), (b"CBLC", cblc)])
+}
+
+/// Build a variable test font with a weight axis
+///
+/// The `wght` axis runs from 100 to 900 with 400 as the default, and has
+/// "Regular" (400) and "Bold" (700) instances. Towards 900 the small
+/// square's right edge and advance grow by up to 200 units; lighter weights
+/// leave it unchanged.
+fn variable_test_font() -> Font {
+    let mut fvar = words(&[1, 0, 16, 2, 1, 20, 2, 8]);
+    fvar.extend(b"wght");
+    fvar.extend(words(&[100, 0, 400, 0, 900, 0, 0, 256]));
+    fvar.extend(words(&[257, 0, 400, 0, 258, 0, 700, 0]));
+
+    // One tuple peaking at the maximum weight, with deltas for all points
+    // of glyph 2, including the four phantom points
+    let mut glyph = words(&[1, 10, 19, 0xA000, 0x4000]);
+    glyph.extend([0, 0x47]);
+    glyph.extend(words(&[0, 0, 200, 200, 0, 200, 0, 0]));
+    glyph.extend([0x87, 0]);
+    let mut gvar = words(&[1, 0, 1, 0, 0, 32, 5, 0, 0, 32]);
+    gvar.extend(words(&[0, 0, 0, 15, 15, 15]));
+    gvar.extend(glyph);
+
+    let names = ["Weight", "Regular", "Bold"];
+    let mut name = words(&[0, names.len() as i32, 6 + 12 * names.len() as i32]);
+    let mut strings = Vec::new();
+    for (i, text) in names.iter().enumerate() {
+        let text: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        name.extend(words(&[
+            3,
+            1,
+            0x409,
+            256 + i as i32,
+            text.len() as i32,
+            strings.len() as i32,
+        ]));
+        strings.extend(text);
+    }
+    name.extend(strings);
+
+    test_font(vec![(b"fvar", fvar), (b"gvar", gvar), (b"name", name)])
+}